@@ -0,0 +1,365 @@
+use rand::prelude::*;
+use rand_pcg::Pcg64;
+use rand::distributions::Standard;
+
+use super::biome::*;
+use super::cell::*;
+use super::color::*;
+use super::fluid::*;
+use super::icons::*;
+use super::inventory::*;
+use super::movable::*;
+use super::options::*;
+use super::tile::*;
+use super::world::*;
+
+#[cfg(target_arch = "wasm32")]
+use serde_derive::{Serialize, Deserialize};
+
+// Actors are the npcs that live in the world, independent of any miner. They are spawned by the
+// world generation (see spawn_cell_actor) according to options.actor_spawn_rates, move around in
+// tick_actors (from tick_world), and interact with a miner when it comes in contact with them (see
+// touch_actors, from tick_miner). Like fountains they run out eventually and then disappear.
+//
+// Actors stay inside the known world so they never grow it, which would skew the explored area.
+//
+// Every roll of an actor is derived from its own seed and its tick count. That keeps it
+// deterministic, independent of the miners, and cheap to serialize.
+
+// Actors do not spawn this close (manhattan) to the origin so the miner gets to start in peace
+pub const ACTOR_SPAWN_SAFE_DISTANCE: i32 = 5;
+// Salt for the spawn rolls so they are unrelated to the rolls of generate_cell
+const ACTOR_SPAWN_SALT: u64 = 0x5eed_ac70;
+
+// The creature wanders the tunnels and steals the most valuable resource of a miner it touches
+pub const CREATURE_INTERVAL: u32 = 2; // Ticks between moves
+pub const CREATURE_THEFTS: u32 = 5; // Leaves after this many thefts
+pub const CREATURE_COOLDOWN: u32 = 50; // Ticks before it steals again
+// The trader stands still and makes one trade with a miner it touches, the first it can afford
+pub const TRADER_TRADES: u32 = 10; // Leaves after this many trades
+pub const TRADER_COOLDOWN: u32 = 20; // Ticks before it trades again
+// What the trader takes, and what it gives for it
+pub const TRADER_OFFERS: [(Resource, u32, Resource, u32); 3] = [
+  (Resource::StoneWhite, 25, Resource::DiamondWhite, 1),
+  (Resource::Sand, 20, Resource::StoneGreen, 1),
+  (Resource::Wood, 10, Resource::Food, 1),
+];
+// The rock-eater burrows through walls, leaving soil behind. It's harmless to miners.
+pub const ROCK_EATER_INTERVAL: u32 = 10; // Ticks between moves
+pub const ROCK_EATER_APPETITE: u32 = 30; // Walls it eats before it leaves
+
+#[cfg_attr(target_arch = "wasm32", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActorKind {
+  Creature,
+  Trader,
+  RockEater,
+}
+
+#[derive(Debug)]
+pub struct Actor {
+  pub kind: ActorKind,
+  pub x: i32,
+  pub y: i32,
+  pub dir: Direction,
+  pub seed: u64,
+  pub ticks: u32,
+  // Ticks left before it interacts with a miner again
+  pub cooldown: u32,
+  // Thefts, trades, or walls left before it leaves. It is disabled once this hits zero.
+  pub remaining: u32,
+  pub disabled: bool,
+}
+
+pub type SerializedActor = (
+  u8, // kind
+  i32, // x
+  i32, // y
+  Direction, // dir
+  u64, // seed
+  u32, // ticks
+  u32, // cooldown
+  u32, // remaining
+  bool, // disabled
+);
+
+pub fn create_actor(kind: ActorKind, x: i32, y: i32, seed: u64) -> Actor {
+  return Actor {
+    kind,
+    x,
+    y,
+    dir: Direction::Up,
+    seed,
+    ticks: 0,
+    cooldown: 0,
+    remaining: match kind {
+      ActorKind::Creature => CREATURE_THEFTS,
+      ActorKind::Trader => TRADER_TRADES,
+      ActorKind::RockEater => ROCK_EATER_APPETITE,
+    },
+    disabled: false,
+  };
+}
+
+pub fn parse_actor_spawn_rates(spec: &str) -> Result<Vec<(ActorKind, f32)>, String> {
+  // Comma separated list of actor kinds and their spawn chance in percent, like `creature=0.5,rockeater=0.2`
+  let mut rates: Vec<(ActorKind, f32)> = vec!();
+  for part in spec.split(',') {
+    let (name, value) = match part.split_once('=') {
+      Some(x) => x,
+      None => return Err(format!("Missing `=` in actor spawn rates `{}`", part)),
+    };
+    let kind = match name.trim() {
+      "creature" => ActorKind::Creature,
+      "trader" => ActorKind::Trader,
+      "rockeater" => ActorKind::RockEater,
+      _ => return Err(format!("Unknown actor `{}` in actor spawn rates `{}`, must be one of `creature`, `trader`, or `rockeater`", name, spec)),
+    };
+    let value = match value.trim().parse::<f32>() {
+      Ok(value) if value >= 0.0 => value,
+      _ => return Err(format!("Invalid spawn rate `{}` for `{}` in actor spawn rates `{}`", value, name, spec)),
+    };
+    rates.push((kind, value));
+  }
+  return Ok(rates);
+}
+
+pub fn spawn_cell_actor(options: &Options, actors: &mut Vec<Actor>, x: i32, y: i32, cell: &Cell) {
+  // Called by the world generation for every cell that is added to the world. Creatures and
+  // traders spawn in open cells, rock-eaters inside walls. At most one actor per cell.
+  if options.actor_spawn_rates.is_empty() || x.abs() + y.abs() <= ACTOR_SPAWN_SAFE_DISTANCE {
+    return;
+  }
+
+  let nx: i64 = if x < 0 { -(-(x as i64) << 32) } else { (x as i64) << 32 };
+  let cell_seed: u64 = (((options.seed ^ ACTOR_SPAWN_SALT) as i64) + nx + (y as i64)) as u64;
+  let mut cell_rng = Pcg64::seed_from_u64(cell_seed);
+
+  let mut roll = cell_rng.sample::<f32, Standard>(Standard) * 100.0;
+  for &(kind, rate) in options.actor_spawn_rates.iter() {
+    if roll < rate {
+      let fits = match kind {
+        ActorKind::Creature | ActorKind::Trader => is_open_tile(cell.tile),
+        ActorKind::RockEater => is_wall_tile(cell.tile),
+      };
+      if fits {
+        actors.push(create_actor(kind, x, y, cell_rng.next_u64()));
+      }
+      return;
+    }
+    roll -= rate;
+  }
+}
+
+fn roll_actor(actor: &Actor) -> u64 {
+  return Pcg64::seed_from_u64(actor.seed.wrapping_add(actor.ticks as u64)).next_u64();
+}
+
+fn can_actor_enter(options: &Options, world: &World, kind: ActorKind, x: i32, y: i32) -> bool {
+  if x < world.min_x || x > world.max_x || y < world.min_y || y > world.max_y {
+    return false;
+  }
+  let tile = get_cell_tile_at(options, world, x, y);
+  return match kind {
+    ActorKind::RockEater => is_open_tile(tile) || is_wall_tile(tile),
+    _ => is_open_tile(tile),
+  };
+}
+
+fn wander_actor(options: &Options, world: &mut World, actor_index: usize, roll: u64) {
+  // Keep going forward with the occasional turn. Turn right until there's a way when blocked.
+  let Actor { kind, x: ax, y: ay, dir, .. } = world.actors[actor_index];
+  let mut dir = dir;
+  if roll.is_multiple_of(4) {
+    dir = turn_lr(dir, roll.is_multiple_of(8));
+  }
+  for _ in 0..4 {
+    let (fx, fy) = coord_forward(ax, ay, dir);
+    let (x, y) = wrap_coord(options, fx, fy);
+    if can_actor_enter(options, world, kind, x, y) {
+      let actor = &mut world.actors[actor_index];
+      actor.x = x;
+      actor.y = y;
+      actor.dir = dir;
+      return;
+    }
+    dir = turn_right(dir);
+  }
+  // Boxed in. Stay put.
+}
+
+fn burrow_actor(options: &Options, world: &mut World, actor_index: usize, roll: u64) {
+  // Eat the first neighbouring wall, looking clockwise from a random side, or wander when there is none
+  let mut dir = match roll % 4 {
+    0 => Direction::Up,
+    1 => Direction::Right,
+    2 => Direction::Down,
+    _ => Direction::Left,
+  };
+  for _ in 0..4 {
+    let (fx, fy) = coord_forward(world.actors[actor_index].x, world.actors[actor_index].y, dir);
+    let (x, y) = wrap_coord(options, fx, fy);
+    if can_actor_enter(options, world, ActorKind::RockEater, x, y) && is_wall_tile(get_cell_tile_at(options, world, x, y)) {
+      // Any pickup inside the wall stays behind in the soil
+      set_cell_tile_at(options, world, x, y, Tile::Soil);
+      wake_fluid_around(options, world, x, y);
+      let actor = &mut world.actors[actor_index];
+      actor.x = x;
+      actor.y = y;
+      actor.dir = dir;
+      actor.remaining -= 1;
+      if actor.remaining == 0 {
+        actor.disabled = true;
+      }
+      return;
+    }
+    dir = turn_right(dir);
+  }
+  wander_actor(options, world, actor_index, roll >> 8);
+}
+
+pub fn tick_actors(options: &Options, world: &mut World) {
+  for n in 0..world.actors.len() {
+    if world.actors[n].disabled {
+      continue;
+    }
+    world.actors[n].ticks += 1;
+    if world.actors[n].cooldown > 0 {
+      world.actors[n].cooldown -= 1;
+    }
+
+    let roll = roll_actor(&world.actors[n]);
+    let ticks = world.actors[n].ticks;
+    match world.actors[n].kind {
+      ActorKind::Creature => if ticks.is_multiple_of(CREATURE_INTERVAL) { wander_actor(options, world, n, roll) },
+      ActorKind::Trader => {} // Waits for customers
+      ActorKind::RockEater => if ticks.is_multiple_of(ROCK_EATER_INTERVAL) { burrow_actor(options, world, n, roll) },
+    }
+  }
+
+  world.actors.retain(|actor| !actor.disabled);
+}
+
+pub fn touch_actors(biome: &mut Biome) {
+  // Let the actors next to (or on top of) the miner interact with it
+  let mx = biome.miner.movable.x;
+  let my = biome.miner.movable.y;
+  for actor in biome.world.actors.iter_mut() {
+    if actor.disabled || actor.cooldown > 0 || (actor.x - mx).abs() + (actor.y - my).abs() > 1 {
+      continue;
+    }
+
+    let inventory = &mut biome.miner.meta.inventory;
+    let interacted = match actor.kind {
+      ActorKind::Creature => {
+        // Steal one of the most valuable thing the miner carries and run off
+        match RESOURCES.iter().filter(|info| info.points > 0 && inventory_get(inventory, info.resource) > 0).max_by_key(|info| info.points) {
+          Some(info) => {
+            inventory_take(inventory, info.resource, 1);
+            actor.dir = turn_back(actor.dir);
+            true
+          },
+          None => false,
+        }
+      },
+      ActorKind::Trader => {
+        match TRADER_OFFERS.iter().find(|&&(give, amount, _, _)| inventory_get(inventory, give) >= amount) {
+          Some(&(give, amount, get, get_amount)) => {
+            inventory_take(inventory, give, amount);
+            inventory_add(inventory, get, get_amount);
+            true
+          },
+          None => false,
+        }
+      },
+      ActorKind::RockEater => false,
+    };
+
+    if interacted {
+      actor.cooldown = match actor.kind {
+        ActorKind::Creature => CREATURE_COOLDOWN,
+        _ => TRADER_COOLDOWN,
+      };
+      actor.remaining -= 1;
+      if actor.remaining == 0 {
+        actor.disabled = true;
+      }
+    }
+  }
+}
+
+pub fn ui_actor(actor: &Actor, options: &Options) -> String {
+  let (icon, color) = match actor.kind {
+    ActorKind::Creature => (ICON_CREATURE, COLOR_CREATURE),
+    ActorKind::Trader => (ICON_TRADER, COLOR_TRADER),
+    ActorKind::RockEater => (ICON_ROCK_EATER, COLOR_ROCK_EATER),
+  };
+  return add_fg_color_with_reset(&icon.to_string(), color, options);
+}
+
+pub fn actor_kind_to_code(kind: ActorKind) -> u8 {
+  return match kind {
+    ActorKind::Creature => 0,
+    ActorKind::Trader => 1,
+    ActorKind::RockEater => 2,
+  };
+}
+
+pub fn code_to_actor_kind(code: u8) -> ActorKind {
+  return match code {
+    0 => ActorKind::Creature,
+    1 => ActorKind::Trader,
+    2 => ActorKind::RockEater,
+    _ => panic!("Unknown actor kind {}", code),
+  };
+}
+
+pub fn actor_serialize(actor: &Actor) -> SerializedActor {
+  return (
+    actor_kind_to_code(actor.kind),
+    actor.x,
+    actor.y,
+    actor.dir,
+    actor.seed,
+    actor.ticks,
+    actor.cooldown,
+    actor.remaining,
+    actor.disabled,
+  );
+}
+
+pub fn actor_deserialize(serialized_actor: &SerializedActor) -> Actor {
+  let (kind, x, y, dir, seed, ticks, cooldown, remaining, disabled) = serialized_actor.to_owned();
+
+  return Actor {
+    kind: code_to_actor_kind(kind),
+    x,
+    y,
+    dir,
+    seed,
+    ticks,
+    cooldown,
+    remaining,
+    disabled,
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_actor_spawn_rates_reads_each_actor() {
+    let rates = parse_actor_spawn_rates("creature=0.5, trader = 0.05,rockeater=0").unwrap();
+    assert_eq!(rates, vec!((ActorKind::Creature, 0.5), (ActorKind::Trader, 0.05), (ActorKind::RockEater, 0.0)));
+  }
+
+  #[test]
+  fn parse_actor_spawn_rates_rejects_bad_specs() {
+    assert!(parse_actor_spawn_rates("creature").is_err());
+    assert!(parse_actor_spawn_rates("dragon=1").is_err());
+    assert!(parse_actor_spawn_rates("creature=x").is_err());
+    assert!(parse_actor_spawn_rates("creature=-1").is_err());
+    assert!(parse_actor_spawn_rates("creature=1,").is_err());
+  }
+}
@@ -2,6 +2,13 @@ use super::{bridge};
 use super::helix::*;
 use super::inventory::*;
 use super::options::*;
+use super::snapshot::*;
+use super::recipe::*;
+use super::progression::*;
+use super::castle_ca::*;
+use super::pareto::*;
+use super::island::*;
+use super::mutation::*;
 
 use rand_pcg::{Lcg128Xsl64};
 
@@ -15,6 +22,18 @@ pub struct AppState {
   pub startup: bool,
 
   pub best_miner: (Helix, u64, usize, usize, Inventory),
+  // Non-dominated miners so far, when options.pareto is set. See pareto.rs
+  pub pareto_front: Vec<ParetoEntry>,
+  // Behaviour descriptors of novel miners so far, when options.novelty is set. See novelty.rs
+  pub novelty_archive: Vec<Vec<f64>>,
+  // Sub-populations when options.islands > 1. Created with the first batch. See island.rs
+  pub islands: Vec<Island>,
+  // Current (genes, slots) mutation rates of the OneFifth mutation schedule. See mutation.rs
+  pub adapted_mutation_rates: (f32, f32),
+  // Fitness of the helix that the current batch was mutated from
+  pub parent_fitness: u64,
+  // (applied, improved) count per SlotOperator. See mutation.rs
+  pub slot_operator_stats: [(u32, u32); SLOT_OPERATOR_COUNT],
   pub trail_lens: u64,
   // Seeded with input seed
   pub instance_rng_seeded: Lcg128Xsl64,
@@ -75,6 +94,17 @@ pub struct AppState {
   // When this gets set (by user interaction) the best miner is cleared and a new miner-seed is randomly picked.
   pub reset: bool,
   pub load_best_as_miner_zero: bool,
+  // Write the visible biome to disk after the next tick
+  pub save_snapshot: bool,
+  // Biome snapshot to load into the next batch (see options.snapshot_file)
+  pub snapshot_to_load: Option<SerializedBiome>,
+
+  // What miners can craft, in order of priority (see recipe.rs and options.recipes_file)
+  pub recipes: Vec<Recipe>,
+  // Milestones miners can reach, checked in this order (see progression.rs and options.progression_file)
+  pub progression: Vec<Milestone>,
+  // The parsed options.castle_ca_rule, None when the castle CA is disabled (see castle_ca.rs)
+  pub castle_ca_rule: Option<CastleCaRule>,
 }
 
 pub fn create_app_state(options: &Options, best_miner: (Helix, u64, usize, usize, Inventory), trail_lens: u64, instance_rng_seeded: Lcg128Xsl64, instance_rng_unseeded: Lcg128Xsl64) -> AppState {
@@ -82,6 +112,12 @@ pub fn create_app_state(options: &Options, best_miner: (Helix, u64, usize, usize
     startup: true,
 
     best_miner,
+    pareto_front: vec!(),
+    novelty_archive: vec!(),
+    islands: vec!(),
+    adapted_mutation_rates: (options.mutation_rate_genes, options.mutation_rate_slots),
+    parent_fitness: 0,
+    slot_operator_stats: [(0, 0); SLOT_OPERATOR_COUNT],
     trail_lens,
     instance_rng_seeded,
     instance_rng_unseeded,
@@ -131,5 +167,11 @@ pub fn create_app_state(options: &Options, best_miner: (Helix, u64, usize, usize
     // user input controls
     reset: false,
     load_best_as_miner_zero: false,
+    save_snapshot: false,
+    snapshot_to_load: None,
+
+    recipes: default_recipes(),
+    progression: default_progression(),
+    castle_ca_rule: None,
   };
 }
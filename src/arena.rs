@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use super::app_state::*;
+use super::biome::*;
+use super::fitness::*;
+use super::helix::*;
+use super::options::*;
+use super::tile::*;
+use super::turn_rule::*;
+use super::world::*;
+
+#[cfg(target_arch = "wasm32")]
+use serde_derive::{Serialize, Deserialize};
+
+// Competitive mode. With options.arena_size > 1 the biomes of a batch are grouped into arenas of
+// that many consecutive biomes. The miners of an arena, each with their own helix, spawn into one
+// shared world and compete for the same diamonds and energy.
+//
+// The world of the first biome of an arena (the host) is the shared world. Every biome still ticks
+// on its own through tick_biome; the host world is swapped into the biome for the duration of its
+// tick and swapped back after. The world itself only ticks once per arena tick. Before a miner
+// ticks, world.rivals lists the other miners of the arena that are still going. A miner can not
+// step into the cell of a rival (see ArenaConflict).
+//
+// At the end of the batch the fitness of every miner is its share of the total fitness of its
+// arena, so it's scored relative to its rivals rather than on its own. That makes the GA a
+// co-evolution arena.
+//
+// Known limitations: the sand castle phases are built around the origin and are not aware of
+// rivals, and the post batch summary of a non-host biome shows the bounds of its own (unused) world.
+
+#[cfg_attr(target_arch = "wasm32", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArenaSpawn {
+  // Every miner of the arena starts at 0,0, on top of each other
+  Origin,
+  // The miners start evenly spread on a circle with radius options.arena_spawn_radius
+  Spread,
+}
+
+#[cfg_attr(target_arch = "wasm32", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArenaConflict {
+  // Moving into a rival is like bumping into a wall, without damaging it
+  Block,
+  // Like Block but the rival loses the block_bump_cost of the miner that bumped into it as well
+  Ram,
+}
+
+// Relative fitness is a share of the arena total in this many parts
+pub const ARENA_FITNESS_SHARES: u64 = 1000;
+
+pub fn get_arena_host(options: &Options, index: usize) -> usize {
+  return index - index % options.arena_size.max(1);
+}
+
+pub fn get_arena_range(options: &Options, biomes: &[Biome], index: usize) -> (usize, usize) {
+  let host = get_arena_host(options, index);
+  return (host, (host + options.arena_size.max(1)).min(biomes.len()));
+}
+
+pub fn swap_arena_world(biomes: &mut [Biome], host: usize, index: usize) {
+  // Swap the world of the host into the biome at index, or back. Noop for the host itself.
+  if index > host {
+    let (left, right) = biomes.split_at_mut(index);
+    std::mem::swap(&mut left[host].world, &mut right[0].world);
+  }
+}
+
+pub fn get_rival_miner_at(world: &World, x: i32, y: i32) -> Option<usize> {
+  return world.rivals.iter().find(|&&(_, rx, ry)| rx == x && ry == y).map(|&(index, _, _)| index);
+}
+
+pub fn bump_rival_miner(options: &Options, biome: &mut Biome, rival: usize, nextx: i32, nexty: i32) {
+  // The miner tried to step into the cell of a rival. It bumps and turns like it would for a wall.
+  biome.miner.meta.prev_move_bumped = true;
+  biome.miner.movable.now_energy = (biome.miner.movable.now_energy - biome.miner.meta.block_bump_cost).max(0.0);
+
+  if matches!(options.arena_conflict, ArenaConflict::Ram) {
+    biome.world.rival_hits.push((rival, biome.miner.meta.block_bump_cost));
+  }
+
+  let rule = biome.miner.helix.turn_rules[TurnContext::Wall as usize];
+  let visited = get_cell_stuff_at(options, &biome.world, biome.miner.movable.x, biome.miner.movable.y).3;
+  biome.miner.movable.dir = apply_turn_rule(options, &biome.world, &rule, visited, nextx, nexty, biome.miner.movable.dir);
+}
+
+pub fn spawn_arena_miners(options: &Options, biomes: &mut [Biome]) {
+  // Put the miners of each arena in their spawn spot. The biomes of the arena keep their own world
+  // but only the one of the host is used from here on.
+  if options.arena_size <= 1 {
+    return;
+  }
+
+  let mut host = 0;
+  while host < biomes.len() {
+    let (_, end) = get_arena_range(options, biomes, host);
+    for m in host..end {
+      biomes[m].shared_world = true;
+      if matches!(options.arena_spawn, ArenaSpawn::Spread) {
+        let angle = (m - host) as f64 / (end - host) as f64 * std::f64::consts::PI * 2.0;
+        let mut radius = options.arena_spawn_radius;
+        if !matches!(options.topology, WorldTopology::Infinite) {
+          radius = radius.min(options.world_size - 1);
+        }
+        let x = (angle.cos() * radius as f64).round() as i32;
+        let y = (angle.sin() * radius as f64).round() as i32;
+        // Clear the spawn cell so the miner does not start inside a wall
+        ensure_cell_in_world(&mut biomes[host].world, options, x, y);
+        set_cell_tile_at(options, &mut biomes[host].world, x, y, Tile::Empty);
+        biomes[m].miner.movable.x = x;
+        biomes[m].miner.movable.y = y;
+      }
+    }
+    host = end;
+  }
+}
+
+pub fn tick_arenas(options: &mut Options, state: &mut AppState, biomes: &mut [Biome], hmap: &mut HashMap<u64, (u64, usize, SerializedHelix)>) {
+  let mut host = 0;
+  while host < biomes.len() {
+    let (_, end) = get_arena_range(options, biomes, host);
+
+    if (host..end).any(|m| biomes[m].miner.movable.now_energy > 0.0) {
+      tick_world(options, state, &mut biomes[host]);
+    }
+
+    for m in host..end {
+      let rivals: Vec<(usize, i32, i32)> = (host..end)
+        .filter(|&r| r != m && biomes[r].miner.movable.now_energy > 0.0)
+        .map(|r| (r, biomes[r].miner.movable.x, biomes[r].miner.movable.y))
+        .collect();
+
+      swap_arena_world(biomes, host, m);
+      biomes[m].world.rivals = rivals;
+      tick_biome(options, state, &mut biomes[m], hmap);
+      biomes[m].world.rivals.clear();
+      let hits = std::mem::take(&mut biomes[m].world.rival_hits);
+      swap_arena_world(biomes, host, m);
+
+      // Rams are applied when the rival ticks so running out of energy is handled as usual
+      for (rival, energy) in hits {
+        biomes[rival].rival_damage += energy;
+      }
+    }
+
+    host = end;
+  }
+}
+
+pub fn score_arenas(options: &Options, biomes: &mut [Biome]) {
+  // Replace the fitness of every miner with its share of the total fitness of its arena
+  if options.arena_size <= 1 {
+    return;
+  }
+
+  let mut host = 0;
+  while host < biomes.len() {
+    let (_, end) = get_arena_range(options, biomes, host);
+
+    let mut raw: Vec<u64> = vec!();
+    for m in host..end {
+      swap_arena_world(biomes, host, m);
+      biomes[m].relative_fitness = None;
+      raw.push(get_fitness(options, &biomes[m]));
+      swap_arena_world(biomes, host, m);
+    }
+
+    let total: u64 = raw.iter().sum();
+    for m in host..end {
+      biomes[m].relative_fitness = Some((raw[m - host] * ARENA_FITNESS_SHARES).checked_div(total).unwrap_or(0));
+    }
+
+    host = end;
+  }
+}
@@ -0,0 +1,245 @@
+use super::tile::*;
+use super::options::*;
+use super::world::*;
+use super::biome::*;
+use super::inventory::*;
+use super::app_state::*;
+
+// After the castle is filled (see sandrone.post_castle) its interior can be turned into a
+// cellular automaton. Every `options.castle_ca_interval` ticks one generation is computed over
+// the cells strictly inside the castle rectangle. Only Push, Impassible, and Soil tiles take part;
+// anything else inside the castle (empty cells, walls, water) is left alone and counts as dead.
+//
+// The rule is selected through `options.castle_ca_rule`. It is parsed once, at startup, into
+// state.castle_ca_rule (see load_castle_ca_rule):
+// - Empty string: the phase is disabled
+// - Life-like rules in B/S notation, like `B3/S23` (Game of Life) or `B36/S23` (HighLife). A Push
+//   tile is alive. A birth turns the cell into a Push tile, a death turns it into Soil.
+// - Custom rules: a `;` separated list of transitions in the form `<from>><to>:<tile>=<counts>`.
+//   For example `soil>push:push=3;push>soil:push=014568` means a soil cell with exactly three
+//   push neighbours becomes push and a push cell dies back to soil unless it has two or three push
+//   neighbours. Tiles are `push`, `impassible`, or `soil`. The first matching transition wins.
+//
+// Each generation costs the miner `castle_ca_energy_cost` energy. When the miner can't pay for
+// it, the automaton pauses. Each cell that turns into a Push tile yields `castle_ca_reward`
+// white stones so that evolution can decide whether building towards this phase is worth it.
+
+pub enum CastleCaRule {
+  // Indexed by neighbour count (0..=8)
+  LifeLike { birth: [bool; 9], survive: [bool; 9] },
+  Custom(Vec<CastleCaTransition>),
+}
+
+pub struct CastleCaTransition {
+  pub from: Tile,
+  pub to: Tile,
+  // Tile of the neighbours to count
+  pub count_tile: Tile,
+  // Indexed by neighbour count (0..=8)
+  pub counts: [bool; 9],
+}
+
+fn parse_counts(digits: &str, rule: &str) -> Result<[bool; 9], String> {
+  let mut counts = [false; 9];
+  for c in digits.chars() {
+    match c.to_digit(10) {
+      Some(n) if n <= 8 => counts[n as usize] = true,
+      _ => return Err(format!("Invalid neighbour count `{}` in castle CA rule `{}`", c, rule)),
+    }
+  }
+  return Ok(counts);
+}
+
+fn parse_ca_tile(name: &str, rule: &str) -> Result<Tile, String> {
+  return match name.trim().to_lowercase().as_str() {
+    "push" => Ok(Tile::Push),
+    "impassible" => Ok(Tile::Impassible),
+    "soil" => Ok(Tile::Soil),
+    _ => Err(format!("Invalid tile `{}` in castle CA rule `{}`; expecting push, impassible, or soil", name, rule)),
+  };
+}
+
+pub fn parse_castle_ca_rule(rule: &str) -> Result<CastleCaRule, String> {
+  let upper = rule.trim().to_uppercase();
+  if upper.starts_with('B') && upper.contains("/S") {
+    let parts: Vec<&str> = upper.split('/').collect();
+    if parts.len() != 2 {
+      return Err(format!("Invalid B/S castle CA rule `{}`", rule));
+    }
+    return Ok(CastleCaRule::LifeLike {
+      birth: parse_counts(&parts[0][1..], rule)?,
+      survive: parse_counts(&parts[1][1..], rule)?,
+    });
+  }
+
+  let mut transitions: Vec<CastleCaTransition> = vec!();
+  for part in rule.split(';') {
+    if part.trim().len() == 0 { continue; }
+    // `<from>><to>:<tile>=<counts>`
+    let (change, condition) = match part.split_once(':') {
+      Some(x) => x,
+      None => return Err(format!("Missing `:` in castle CA transition `{}`", part)),
+    };
+    let (from, to) = match change.split_once('>') {
+      Some(x) => x,
+      None => return Err(format!("Missing `>` in castle CA transition `{}`", part)),
+    };
+    let (count_tile, digits) = match condition.split_once('=') {
+      Some(x) => x,
+      None => return Err(format!("Missing `=` in castle CA transition `{}`", part)),
+    };
+    transitions.push(CastleCaTransition {
+      from: parse_ca_tile(from, rule)?,
+      to: parse_ca_tile(to, rule)?,
+      count_tile: parse_ca_tile(count_tile, rule)?,
+      counts: parse_counts(digits.trim(), rule)?,
+    });
+  }
+  if transitions.len() == 0 {
+    return Err(format!("Castle CA rule `{}` has no transitions", rule));
+  }
+  return Ok(CastleCaRule::Custom(transitions));
+}
+
+pub fn load_castle_ca_rule(options: &Options) -> Option<CastleCaRule> {
+  if options.castle_ca_rule.len() == 0 {
+    return None;
+  }
+  return match parse_castle_ca_rule(&options.castle_ca_rule) {
+    Ok(rule) => Some(rule),
+    Err(msg) => panic!("{}", msg),
+  };
+}
+
+fn is_ca_tile(tile: Tile) -> bool {
+  return matches!(tile, Tile::Push | Tile::Impassible | Tile::Soil);
+}
+
+fn same_tile(a: Tile, b: Tile) -> bool {
+  return std::mem::discriminant(&a) == std::mem::discriminant(&b);
+}
+
+fn count_neighbors(options: &Options, world: &World, x: i32, y: i32, tile: Tile) -> usize {
+  let mut n = 0;
+  for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+    if same_tile(get_cell_tile_at(options, world, x + dx, y + dy), tile) {
+      n += 1;
+    }
+  }
+  return n;
+}
+
+fn next_tile(options: &Options, world: &World, rule: &CastleCaRule, x: i32, y: i32, tile: Tile) -> Tile {
+  match rule {
+    CastleCaRule::LifeLike { birth, survive } => {
+      let n = count_neighbors(options, world, x, y, Tile::Push);
+      if matches!(tile, Tile::Push) {
+        return if survive[n] { Tile::Push } else { Tile::Soil };
+      }
+      return if birth[n] { Tile::Push } else { tile };
+    }
+    CastleCaRule::Custom(transitions) => {
+      for t in transitions.iter() {
+        if same_tile(tile, t.from) && t.counts[count_neighbors(options, world, x, y, t.count_tile)] {
+          return t.to;
+        }
+      }
+      return tile;
+    }
+  }
+}
+
+pub fn tick_castle_ca(options: &Options, state: &AppState, biome: &mut Biome) {
+  let rule = match &state.castle_ca_rule {
+    Some(rule) => rule,
+    None => return,
+  };
+  if biome.miner.sandrone.post_castle == 0 {
+    return;
+  }
+  if options.castle_ca_generations > 0 && biome.miner.sandrone.castle_ca_generations >= options.castle_ca_generations {
+    return;
+  }
+  let since = biome.ticks - biome.miner.sandrone.post_castle;
+  if since == 0 || since % options.castle_ca_interval.max(1) != 0 {
+    return;
+  }
+  if biome.miner.movable.now_energy <= options.castle_ca_energy_cost {
+    // Can't afford a generation right now
+    return;
+  }
+
+  let min_x = biome.miner.sandrone.expansion_min_x;
+  let min_y = biome.miner.sandrone.expansion_min_y;
+  let max_x = biome.miner.sandrone.expansion_max_x;
+  let max_y = biome.miner.sandrone.expansion_max_y;
+
+  // Compute the entire generation before applying any of it
+  let mut changes: Vec<(i32, i32, Tile)> = vec!();
+  for y in min_y+1..max_y {
+    for x in min_x+1..max_x {
+      let tile = get_cell_tile_at(options, &biome.world, x, y);
+      if !is_ca_tile(tile) { continue; }
+      let next = next_tile(options, &biome.world, rule, x, y, tile);
+      if !same_tile(tile, next) {
+        changes.push((x, y, next));
+      }
+    }
+  }
+
+  let mut born = 0;
+  for (x, y, tile) in changes {
+    if matches!(tile, Tile::Push) { born += 1; }
+    set_cell_tile_at(options, &mut biome.world, x, y, tile);
+  }
+
+  biome.miner.sandrone.castle_ca_generations += 1;
+  biome.miner.movable.now_energy -= options.castle_ca_energy_cost;
+  inventory_add(&mut biome.miner.meta.inventory, Resource::StoneWhite, born * options.castle_ca_reward);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_castle_ca_rule_reads_life_like_rules() {
+    match parse_castle_ca_rule("B36/S23") {
+      Ok(CastleCaRule::LifeLike { birth, survive }) => {
+        assert_eq!(birth, [false, false, false, true, false, false, true, false, false]);
+        assert_eq!(survive, [false, false, true, true, false, false, false, false, false]);
+      },
+      _ => panic!("expected a life-like rule"),
+    }
+    // Case and whitespace do not matter
+    assert!(matches!(parse_castle_ca_rule(" b3/s23 "), Ok(CastleCaRule::LifeLike { .. })));
+  }
+
+  #[test]
+  fn parse_castle_ca_rule_reads_custom_rules() {
+    match parse_castle_ca_rule("soil>push:push=3;push>soil:push=014568") {
+      Ok(CastleCaRule::Custom(transitions)) => {
+        assert_eq!(transitions.len(), 2);
+        assert!(matches!(transitions[0].from, Tile::Soil));
+        assert!(matches!(transitions[0].to, Tile::Push));
+        assert!(matches!(transitions[0].count_tile, Tile::Push));
+        assert_eq!(transitions[0].counts, [false, false, false, true, false, false, false, false, false]);
+        assert!(matches!(transitions[1].from, Tile::Push));
+        assert_eq!(transitions[1].counts, [true, true, false, false, true, true, true, false, true]);
+      },
+      _ => panic!("expected a custom rule"),
+    }
+  }
+
+  #[test]
+  fn parse_castle_ca_rule_rejects_bad_rules() {
+    assert!(parse_castle_ca_rule("B39/S23").is_err());
+    assert!(parse_castle_ca_rule("soil>push:push=9").is_err());
+    assert!(parse_castle_ca_rule("B3/S2/S3").is_err());
+    assert!(parse_castle_ca_rule("soil>push").is_err());
+    assert!(parse_castle_ca_rule("soil:push=3").is_err());
+    assert!(parse_castle_ca_rule("soil>push:push").is_err());
+    assert!(parse_castle_ca_rule("soil>water:push=3").is_err());
+    assert!(parse_castle_ca_rule(";").is_err());
+  }
+}
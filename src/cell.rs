@@ -8,4 +8,5 @@ pub struct Cell {
   pub pickup: Pickup, // What does the miner receive when moving onto this tile? (Potentially blocked by the tile, like wall)
   pub pickup_value: u32, // For certain kinds of pickups this indicates its value
   pub visited: u32, // How often has the miner visited this coord?
+  pub fluid: u32, // Fluid level of this cell. Only water tiles should have any. See fluid.rs
 }
@@ -30,6 +30,12 @@ pub const COLOR_FOOD: &str = rgb!(0,128,0);
 pub const COLOR_WOOD: &str = rgb!(139,69,19); // "saddlebrown"
 pub const COLOR_WIND: &str = rgb!(128,128,0);
 pub const COLOR_SAND: &str = rgb!(215,135,135);
+pub const COLOR_DYNAMITE: &str = rgb!(200,0,0);
+pub const COLOR_FLARE: &str = rgb!(255,140,0);
+pub const COLOR_RATION: &str = rgb!(150,150,150);
+pub const COLOR_CREATURE: &str = rgb!(255,60,60);
+pub const COLOR_TRADER: &str = rgb!(255,215,0);
+pub const COLOR_ROCK_EATER: &str = rgb!(150,200,80);
 pub const COLOR_IMPOSSIBLE: &str = rgb!(75,55,13);
 
 pub const COLOR_SOIL0: &str = rgb!(75,55,13);
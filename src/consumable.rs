@@ -0,0 +1,104 @@
+use super::biome::*;
+use super::fluid::*;
+use super::hunger::*;
+use super::inventory::*;
+use super::options::*;
+use super::tile::*;
+use super::values::*;
+use super::world::*;
+
+// Consumables are single use items. They are crafted from the inventory like anything else (see
+// the recipes in recipe.rs) and stay in the inventory until the miner uses one. Unlike slots they
+// do not fire on a cooldown. Instead, each consumable has a trigger condition with a threshold
+// that is a gene of the helix, so the GA decides when it's worth using one.
+// At most one of each kind is used per tick.
+
+#[derive(Debug, Clone, Copy)]
+pub enum ConsumableTrigger {
+  // Energy is below the threshold, as a percentage of the max energy
+  EnergyBelow,
+  // Boredom level is above the threshold
+  BoredomAbove,
+}
+
+pub struct ConsumableSpec {
+  pub resource: Resource,
+  pub trigger: ConsumableTrigger,
+}
+
+pub const CONSUMABLE_COUNT: usize = 3;
+pub static CONSUMABLES: [ConsumableSpec; CONSUMABLE_COUNT] = [
+  // Blow up the walls in the 3x3 area around the miner
+  ConsumableSpec { resource: Resource::Dynamite, trigger: ConsumableTrigger::BoredomAbove },
+  // Light up the area so a Jack's Compass sees diamonds much further away, for FLARE_TICKS ticks
+  ConsumableSpec { resource: Resource::Flare, trigger: ConsumableTrigger::BoredomAbove },
+  // Eat the food packed in a ration, for energy
+  ConsumableSpec { resource: Resource::Ration, trigger: ConsumableTrigger::EnergyBelow },
+];
+
+fn get_consumable_threshold(biome: &Biome, resource: Resource) -> f32 {
+  let meta = &biome.miner.meta;
+  return match resource {
+    Resource::Dynamite => meta.consumable_dynamite_boredom,
+    Resource::Flare => meta.consumable_flare_boredom,
+    Resource::Ration => meta.consumable_ration_energy,
+    _ => panic!("{:?} is not a consumable with a trigger", resource),
+  };
+}
+
+fn is_consumable_triggered(biome: &Biome, spec: &ConsumableSpec) -> bool {
+  let threshold = get_consumable_threshold(biome, spec.resource);
+  return match spec.trigger {
+    ConsumableTrigger::EnergyBelow => biome.miner.movable.now_energy < biome.miner.meta.max_energy * threshold / 100.0,
+    ConsumableTrigger::BoredomAbove => biome.miner.meta.boredom_level as f32 > threshold,
+  };
+}
+
+pub fn tick_consumables(options: &mut Options, biome: &mut Biome) {
+  if biome.miner.meta.flare_ticks > 0 {
+    biome.miner.meta.flare_ticks -= 1;
+  }
+
+  for n in 0..CONSUMABLE_COUNT {
+    let spec = &CONSUMABLES[n];
+    if inventory_get(&biome.miner.meta.inventory, spec.resource) == 0 || !is_consumable_triggered(biome, spec) {
+      continue;
+    }
+    inventory_take(&mut biome.miner.meta.inventory, spec.resource, 1);
+    biome.miner.meta.consumed[n] += 1;
+    match spec.resource {
+      Resource::Dynamite => use_dynamite(options, biome),
+      Resource::Flare => biome.miner.meta.flare_ticks = FLARE_TICKS,
+      Resource::Ration => {
+        biome.miner.movable.now_energy = (biome.miner.movable.now_energy + RATION_ENERGY).min(biome.miner.meta.max_energy);
+        // It's food, after all
+        biome.miner.meta.satiety = SATIETY_MAX;
+      }
+      _ => panic!("{:?} is not a consumable with a trigger", spec.resource),
+    }
+  }
+}
+
+fn use_dynamite(options: &mut Options, biome: &mut Biome) {
+  // Walls around the miner become empty, keeping whatever was hidden in them. Each wall yields sand
+  // like it would when broken by hand. Push and impassible tiles are not affected.
+  let mx = biome.miner.movable.x;
+  let my = biome.miner.movable.y;
+  for y in my - 1..=my + 1 {
+    for x in mx - 1..=mx + 1 {
+      ensure_cell_in_world(&mut biome.world, options, x, y);
+      if is_wall_tile(get_cell_tile_at(options, &biome.world, x, y)) {
+        set_cell_tile_at(options, &mut biome.world, x, y, Tile::Empty);
+        inventory_add(&mut biome.miner.meta.inventory, Resource::Sand, 1);
+        wake_fluid_around(options, &mut biome.world, x, y);
+      }
+    }
+  }
+  // Things are happening again
+  biome.miner.meta.boredom_level = 0;
+}
+
+pub fn ui_consumables(biome: &Biome) -> String {
+  let parts: Vec<String> = CONSUMABLES.iter().enumerate().map(|(n, spec)| format!("{:?}: {}", spec.resource, biome.miner.meta.consumed[n])).collect();
+  return format!("Used: {}{}", parts.join(", "), if biome.miner.meta.flare_ticks > 0 { " (flare lit)" } else { "" });
+}
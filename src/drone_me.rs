@@ -1,28 +1,300 @@
 use super::values::*;
 use super::options::*;
 use super::world::*;
+use super::fluid::*;
 use super::movable::*;
 use super::tile::*;
 use super::biome::*;
+use super::inventory::*;
 use super::pickup::*;
+use super::miner::*;
+use super::pathfinding::*;
+use super::hunger::*;
+use rand_pcg::{Lcg128Xsl64};
+use rand::distributions::{Distribution, Uniform};
+use serde_derive::{Serialize, Deserialize};
 
+// Genes that let drone heavy builds specialise. They apply to every drone of the miner. The energy
+// budget of a drone is a numeric gene in the GENE_SCHEMA (see helix.rs), the rest lives here.
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DroneLaunchDir {
+  // Perpendicular to the miner, to its right. The original behaviour.
+  Right,
+  Left,
+  // Same direction as the miner
+  Ahead,
+  // Opposite direction of the miner
+  Behind,
+  // Right and left on alternating launches
+  Alternate,
+}
+pub const DRONE_LAUNCH_DIRS: [DroneLaunchDir; 5] = [DroneLaunchDir::Right, DroneLaunchDir::Left, DroneLaunchDir::Ahead, DroneLaunchDir::Behind, DroneLaunchDir::Alternate];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DroneDelivery {
+  // Whatever a drone picks up goes straight into the inventory of the miner. The original behaviour.
+  Score,
+  // A drone keeps what it picks up in its cargo. Once it spent half its energy it walks back to the
+  // miner to deliver the cargo and hand back its remaining energy. The cargo is lost if the drone
+  // runs out of energy first.
+  Return,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DroneGenes {
+  pub launch_dir: DroneLaunchDir,
+  // Harvest soil for food (and energy for the miner)
+  pub collect_food: bool,
+  // Pick up stones and diamonds
+  pub collect_gems: bool,
+  // Pick up energy, which refuels the drone
+  pub collect_energy: bool,
+  pub delivery: DroneDelivery,
+}
+
+pub fn create_default_drone_genes() -> DroneGenes {
+  return DroneGenes {
+    launch_dir: DroneLaunchDir::Right,
+    collect_food: true,
+    collect_gems: true,
+    collect_energy: true,
+    delivery: DroneDelivery::Score,
+  };
+}
+
+pub fn drone_genes_to_string(genes: &DroneGenes) -> String {
+  // Like "R/fge/S": launch direction, what to collect (a dash when not collected), and delivery
+  return format!(
+    "{}/{}{}{}/{}",
+    match genes.launch_dir {
+      DroneLaunchDir::Right => 'R',
+      DroneLaunchDir::Left => 'L',
+      DroneLaunchDir::Ahead => 'A',
+      DroneLaunchDir::Behind => 'B',
+      DroneLaunchDir::Alternate => 'X',
+    },
+    if genes.collect_food { 'f' } else { '-' },
+    if genes.collect_gems { 'g' } else { '-' },
+    if genes.collect_energy { 'e' } else { '-' },
+    match genes.delivery {
+      DroneDelivery::Score => 'S',
+      DroneDelivery::Return => 'R',
+    },
+  );
+}
+
+pub fn parse_drone_genes(spec: &str) -> Result<DroneGenes, String> {
+  // Inverse of drone_genes_to_string
+  let chars: Vec<char> = spec.trim().chars().collect();
+  if chars.len() != 7 || chars[1] != '/' || chars[5] != '/' {
+    return Err(format!("Expecting drone genes like `R/fge/S`, got `{}`", spec));
+  }
+  let launch_dir = match chars[0] {
+    'R' => DroneLaunchDir::Right,
+    'L' => DroneLaunchDir::Left,
+    'A' => DroneLaunchDir::Ahead,
+    'B' => DroneLaunchDir::Behind,
+    'X' => DroneLaunchDir::Alternate,
+    c => return Err(format!("Unknown drone launch direction `{}`, expecting one of R, L, A, B, X", c)),
+  };
+  let collect = |c: char, on: char| -> Result<bool, String> {
+    return match c {
+      '-' => Ok(false),
+      _ if c == on => Ok(true),
+      _ => Err(format!("Unknown drone collect flag `{}`, expecting `{}` or `-`", c, on)),
+    };
+  };
+  let delivery = match chars[6] {
+    'S' => DroneDelivery::Score,
+    'R' => DroneDelivery::Return,
+    c => return Err(format!("Unknown drone delivery `{}`, expecting S or R", c)),
+  };
+  return Ok(DroneGenes {
+    launch_dir,
+    collect_food: collect(chars[2], 'f')?,
+    collect_gems: collect(chars[3], 'g')?,
+    collect_energy: collect(chars[4], 'e')?,
+    delivery,
+  });
+}
+
+pub fn mutate_drone_genes(rng: &mut Lcg128Xsl64, genes: &DroneGenes, rate: f32) -> DroneGenes {
+  // Each drone gene has a `rate` percent chance to be flipped or rerolled
+  let pct_roller: Uniform<f32> = Uniform::from(0.0..100.0);
+  let mut mutated = *genes;
+  if pct_roller.sample(rng) < rate {
+    mutated.launch_dir = DRONE_LAUNCH_DIRS[Uniform::from(0..DRONE_LAUNCH_DIRS.len()).sample(rng)];
+  }
+  if pct_roller.sample(rng) < rate {
+    mutated.collect_food = !mutated.collect_food;
+  }
+  if pct_roller.sample(rng) < rate {
+    mutated.collect_gems = !mutated.collect_gems;
+  }
+  if pct_roller.sample(rng) < rate {
+    mutated.collect_energy = !mutated.collect_energy;
+  }
+  if pct_roller.sample(rng) < rate {
+    mutated.delivery = if mutated.delivery == DroneDelivery::Score { DroneDelivery::Return } else { DroneDelivery::Score };
+  }
+  return mutated;
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct MeDrone {
   // Each drone has its own x, y, direction, and energy
   pub movable: Movable,
+  // How often this drone was launched
+  pub launches: u32,
+  // With DroneDelivery::Return, what the drone picked up but did not deliver yet
+  pub cargo: Vec<Resource>,
+  // Walking back to the miner to deliver the cargo
+  pub homing: bool,
+  // Path back to the miner (see pathfinding.rs)
+  pub path: CachedPath,
+  // Total number of items delivered to the miner
+  pub delivered: u32,
+}
+
+pub fn create_me_drone() -> MeDrone {
+  return MeDrone {
+    movable: Movable {
+      what: WHAT_DRONE,
+      x: 0,
+      y: 0,
+      dir: Direction::Up,
+      now_energy: 0.0,
+      init_energy: 0.0,
+      disabled: false,
+    },
+    launches: 0,
+    cargo: vec!(),
+    homing: false,
+    path: CachedPath::default(),
+    delivered: 0,
+  };
+}
+
+pub fn get_drone_launch_dir(genes: &DroneGenes, miner_dir: Direction, launches: u32) -> Direction {
+  return match genes.launch_dir {
+    DroneLaunchDir::Right => turn_right(miner_dir),
+    DroneLaunchDir::Left => turn_left(miner_dir),
+    DroneLaunchDir::Ahead => miner_dir,
+    DroneLaunchDir::Behind => turn_back(miner_dir),
+    DroneLaunchDir::Alternate => if launches.is_multiple_of(2) { turn_right(miner_dir) } else { turn_left(miner_dir) },
+  };
+}
+
+pub fn launch_me_drone(biome: &mut Biome, drone_index: usize, energy: f32) {
+  let dir = get_drone_launch_dir(&biome.miner.helix.drone_genes, biome.miner.movable.dir, biome.miner.drones[drone_index].launches);
+  let drone = &mut biome.miner.drones[drone_index];
+  drone.movable.now_energy = energy;
+  drone.movable.init_energy = energy;
+  // Position the drone on the location of the miner
+  drone.movable.x = biome.miner.movable.x;
+  drone.movable.y = biome.miner.movable.y;
+  drone.movable.dir = dir;
+  drone.launches += 1;
+  // Whatever it carried when it ran out of energy last time is lost
+  drone.cargo.clear();
+  drone.homing = false;
+}
+
+fn drone_pick_up(biome: &mut Biome, drone_index: usize, resource: Resource, x: i32, y: i32) {
+  // Stones and gems earn points (see add_scored_pickup)
+  let scored = matches!(get_resource_info(resource).category, ResourceCategory::Stone | ResourceCategory::Diamond);
+  if biome.miner.helix.drone_genes.delivery == DroneDelivery::Return {
+    biome.miner.drones[drone_index].cargo.push(resource);
+  } else if scored {
+    add_scored_pickup(biome, resource, Some(drone_index), x, y);
+  } else {
+    acquire_item(biome, resource, Some(drone_index), x, y);
+  }
+}
+
+fn deliver_drone_cargo(biome: &mut Biome, drone_index: usize) {
+  // The drone made it back. Unload and land, giving back the remaining energy at the same rate as
+  // the miner paid for it at launch.
+  let mx = biome.miner.movable.x;
+  let my = biome.miner.movable.y;
+  let cargo: Vec<Resource> = biome.miner.drones[drone_index].cargo.drain(..).collect();
+  for resource in cargo.iter() {
+    if matches!(get_resource_info(*resource).category, ResourceCategory::Stone | ResourceCategory::Diamond) {
+      add_scored_pickup(biome, *resource, Some(drone_index), mx, my);
+    } else {
+      acquire_item(biome, *resource, Some(drone_index), mx, my);
+    }
+  }
+  let drone = &mut biome.miner.drones[drone_index];
+  drone.delivered += cargo.len() as u32;
+  biome.miner.movable.now_energy = (biome.miner.movable.now_energy + drone.movable.now_energy / 2.0).min(biome.miner.movable.init_energy);
+  drone.movable.now_energy = 0.0;
+  drone.homing = false;
+}
+
+fn move_drone_home(options: &mut Options, biome: &mut Biome, drone_index: usize) -> bool {
+  // Take one step back towards the miner. Returns false if there is no way back.
+  let drone = &mut biome.miner.drones[drone_index];
+  return match next_path_step(options, &biome.world, &ME_DRONE_PATH_COSTS, &mut drone.path, drone.movable.x, drone.movable.y, biome.miner.movable.x, biome.miner.movable.y) {
+    Some((nextx, nexty)) => {
+      // On a torus the path may go through the seam so wrap the step, like move_drone does
+      let (nextx, nexty) = wrap_coord(options, nextx, nexty);
+      ensure_cell_in_world(&mut biome.world, options, nextx, nexty);
+      let unextx = (biome.world.min_x.abs() + nextx) as usize;
+      let unexty = (biome.world.min_y.abs() + nexty) as usize;
+      move_drone_pickup_from_empty_tile(options, biome, drone_index, nextx, nexty, unextx, unexty);
+      let drone = &mut biome.miner.drones[drone_index];
+      // Face the way of the step. Comparing wrapped steps also gets this right across the seam.
+      let (x, y) = (drone.movable.x, drone.movable.y);
+      drone.movable.dir = match [Direction::Up, Direction::Right, Direction::Down, Direction::Left].iter().find(|&&dir| {
+        let (deltax, deltay) = delta_forward(dir);
+        return wrap_coord(options, x + deltax, y + deltay) == (nextx, nexty);
+      }) {
+        Some(&dir) => dir,
+        None => drone.movable.dir,
+      };
+      drone.movable.x = nextx;
+      drone.movable.y = nexty;
+      true
+    },
+    None => false,
+  };
 }
 
 pub fn tick_me_drone(options: &mut Options, biome: &mut Biome, _slot_index: usize, drone_index: usize) {
   // ticks: u32, drone: &mut MeDrone, miner_movable: &mut Movable, miner_meta: &mut MinerMeta, world: &mut World, options: &mut Options
 
-  move_drone(options, biome, drone_index);
+  let genes = biome.miner.helix.drone_genes;
+
+  if genes.delivery == DroneDelivery::Return {
+    let drone = &mut biome.miner.drones[drone_index];
+    if !drone.homing && drone.cargo.len() > 0 && drone.movable.now_energy < drone.movable.init_energy / 2.0 {
+      drone.homing = true;
+    }
+  }
+
+  if biome.miner.drones[drone_index].homing {
+    if !move_drone_home(options, biome, drone_index) {
+      // Lost. Keep wandering and hope the miner opens up a way back.
+      move_drone(options, biome, drone_index);
+    }
+    let drone = &biome.miner.drones[drone_index];
+    if (drone.movable.x - biome.miner.movable.x).abs() + (drone.movable.y - biome.miner.movable.y).abs() <= 1 {
+      deliver_drone_cargo(biome, drone_index);
+      return;
+    }
+  } else {
+    move_drone(options, biome, drone_index);
+  }
 
   // Collect the current value as food / grass / plants / whatever
-  if matches!(get_cell_tile_at(options, &biome.world, biome.miner.drones[drone_index].movable.x, biome.miner.drones[drone_index].movable.y), Tile::Soil) {
-    // Reset the value.
-    let soil_value = get_cell_tile_value_at(options, &biome.world, biome.miner.drones[drone_index].movable.x, biome.miner.drones[drone_index].movable.y);
-    set_cell_tile_value_at(options, &mut biome.world, biome.miner.drones[drone_index].movable.x, biome.miner.drones[drone_index].movable.y, 0);
-    if soil_value > 2 {
-      biome.miner.meta.inventory.food += 1; // 1? Depends on state of soil and items, I guess.
+  if genes.collect_food && matches!(get_cell_tile_at(options, &biome.world, biome.miner.drones[drone_index].movable.x, biome.miner.drones[drone_index].movable.y), Tile::Soil) {
+    // Only ripe crops are picked so the rest keeps growing (see hunger.rs)
+    let x = biome.miner.drones[drone_index].movable.x;
+    let y = biome.miner.drones[drone_index].movable.y;
+    if harvest_crop(options, &mut biome.world, x, y) {
+      drone_pick_up(biome, drone_index, Resource::Food, x, y); // 1? Depends on state of soil and items, I guess.
       biome.miner.movable.now_energy = (biome.miner.movable.now_energy + 100.0).min(biome.miner.movable.init_energy); // ? TBD
     }
   }
@@ -33,8 +305,8 @@ fn move_drone(options: &mut Options, biome: &mut Biome, drone_index: usize) {
   let dy = biome.miner.drones[drone_index].movable.y;
   let dir = biome.miner.drones[drone_index].movable.dir;
   let (deltax, deltay) = delta_forward(dir);
-  let nextx = dx + deltax;
-  let nexty = dy + deltay;
+  // On a torus the step may wrap around to the other side of the world
+  let (nextx, nexty) = wrap_coord(options, dx + deltax, dy + deltay);
 
   // If this move would go OOB, expand the world to make sure that does not happen
   ensure_cell_in_world(&mut biome.world, options, nextx, nexty);
@@ -94,68 +366,64 @@ fn move_drone(options: &mut Options, biome: &mut Biome, drone_index: usize) {
     | Tile::Test2
     | Tile::Test3
       => {
-      move_drone_pickup_from_empty_tile(options, biome, drone_index, unextx, unexty);
+      move_drone_pickup_from_empty_tile(options, biome, drone_index, nextx, nexty, unextx, unexty);
       biome.miner.drones[drone_index].movable.x = nextx;
       biome.miner.drones[drone_index].movable.y = nexty;
     },
   }
 }
 
-fn move_drone_pickup_from_empty_tile(_options: &mut Options, biome: &mut Biome, drone_index: usize, unextx: usize, unexty: usize) {
-  let cell = &mut biome.world.tiles[unexty][unextx];
+fn move_drone_pickup_from_empty_tile(_options: &mut Options, biome: &mut Biome, drone_index: usize, x: i32, y: i32, unextx: usize, unexty: usize) {
+  // The cell gets replaced below but any fluid in it should stay put
+  let fluid = biome.world.tiles[unexty][unextx].fluid;
+  let cell = &biome.world.tiles[unexty][unextx];
+  let (tile, pickup, tile_value, pickup_value, visited) = (cell.tile, cell.pickup, cell.tile_value, cell.pickup_value, cell.visited);
+  let genes = biome.miner.helix.drone_genes;
 
-  match cell.pickup {
-    Pickup::Diamond => {
+  match pickup {
+    Pickup::Diamond if genes.collect_gems => {
       // Different gems with different points.
       // Drones could have properties or powerups to affect this, too.
-      match cell.pickup_value.min(3) {
-        0 => biome.miner.meta.inventory.diamond_white += 1,
-        1 => biome.miner.meta.inventory.diamond_green += 1,
-        2 => biome.miner.meta.inventory.diamond_blue += 1,
-        3 => biome.miner.meta.inventory.diamond_yellow += 1,
-        _ => panic!("what value did this diamond have: {:?}", cell),
-      };
+      drone_pick_up(biome, drone_index, resource_of_quality(ResourceCategory::Diamond, pickup_value.min(3)), x, y);
 
-      biome.world.tiles[unexty][unextx] = create_visited_cell(cell.tile, Pickup::Nothing, 0, 0, cell.visited + 1);
+      biome.world.tiles[unexty][unextx] = create_visited_cell(tile, Pickup::Nothing, 0, 0, visited + 1);
     },
-    Pickup::Energy => {
+    Pickup::Energy if genes.collect_energy => {
       let drone = &mut biome.miner.drones[drone_index];
       // Who picks up the energy? The drone? The miner? Both? Items may determine this. ("drone modifications")
       drone.movable.now_energy = (drone.movable.now_energy + (E_VALUE as f64 * ((100.0 + biome.miner.meta.multiplier_energy_pickup as f64) / 100.0)) as f32).min(biome.miner.meta.max_energy);
-      biome.miner.meta.inventory.energy += 1;
-      biome.world.tiles[unexty][unextx] = create_visited_cell(cell.tile, Pickup::Nothing, 0, 0, cell.visited + 1);
+      acquire_item(biome, Resource::Energy, Some(drone_index), x, y);
+      biome.world.tiles[unexty][unextx] = create_visited_cell(tile, Pickup::Nothing, 0, 0, visited + 1);
     },
-    Pickup::Stone => {
-      // Do we have any purity scanners primed? Bump the value by that many.
-      // Note: purity scanner only works for the miner itself. For drones, slots is empty
-      match cell.pickup_value.min(3) {
-        0 => biome.miner.meta.inventory.stone_white += 1,
-        1 => biome.miner.meta.inventory.stone_green += 1,
-        2 => biome.miner.meta.inventory.stone_blue += 1,
-        3 => biome.miner.meta.inventory.stone_yellow += 1,
-        _ => panic!("what value did this stone have: {:?}", cell),
-      }
-      biome.world.tiles[unexty][unextx] = create_visited_cell(cell.tile, Pickup::Nothing, 0, 0, cell.visited + 1);
+    Pickup::Stone if genes.collect_gems => {
+      // Purity scanners of the miner may still improve it (see SlotBehavior::on_pickup)
+      drone_pick_up(biome, drone_index, resource_of_quality(ResourceCategory::Stone, pickup_value.min(3)), x, y);
+      biome.world.tiles[unexty][unextx] = create_visited_cell(tile, Pickup::Nothing, 0, 0, visited + 1);
     },
     Pickup::Wind => {
-      biome.miner.meta.inventory.wind += 1;
-      biome.world.tiles[unexty][unextx] = create_visited_cell(cell.tile, Pickup::Nothing, 0, 0, cell.visited + 1);
+      drone_pick_up(biome, drone_index, Resource::Wind, x, y);
+      biome.world.tiles[unexty][unextx] = create_visited_cell(tile, Pickup::Nothing, 0, 0, visited + 1);
     },
     Pickup::Water => {
-      biome.miner.meta.inventory.water += 1;
-      biome.world.tiles[unexty][unextx] = create_visited_cell(cell.tile, Pickup::Nothing, 0, 0, cell.visited + 1);
+      drone_pick_up(biome, drone_index, Resource::Water, x, y);
+      biome.world.tiles[unexty][unextx] = create_visited_cell(tile, Pickup::Nothing, 0, 0, visited + 1);
     },
     Pickup::Wood => {
-      biome.miner.meta.inventory.wood += 1;
-      biome.world.tiles[unexty][unextx] = create_visited_cell(cell.tile, Pickup::Nothing, 0, 0, cell.visited + 1);
+      drone_pick_up(biome, drone_index, Resource::Wood, x, y);
+      biome.world.tiles[unexty][unextx] = create_visited_cell(tile, Pickup::Nothing, 0, 0, visited + 1);
     },
+    // Not collected by these drones, or nothing to collect. Leave it.
+    | Pickup::Diamond
+    | Pickup::Energy
+    | Pickup::Stone
     | Pickup::Nothing
     | Pickup::Expando // Ignore, fake pickup
     | Pickup::Fountain // Ignore, fake pickup... TODO: probably some special behavior?
     => {
-      biome.world.tiles[unexty][unextx] = create_visited_cell(cell.tile, cell.pickup, cell.tile_value, cell.pickup_value, cell.visited + 1);
+      biome.world.tiles[unexty][unextx] = create_visited_cell(tile, pickup, tile_value, pickup_value, visited + 1);
     },
   }
+  biome.world.tiles[unexty][unextx].fluid = fluid;
 }
 
 fn move_drone_bump_wall(
@@ -167,6 +435,7 @@ fn move_drone_bump_wall(
   let n = strength - 1;
 
   // The pickup is set at tile generation time so we just need to clear the tile here
+  let before = cell.tile;
   biome.world.tiles[unexty][unextx] = match n.max(0) {
     3 => create_unvisited_cell(Tile::Wall3, cell.pickup, cell.tile_value, cell.pickup_value),
     2 => create_unvisited_cell(Tile::Wall2, cell.pickup, cell.tile_value, cell.pickup_value),
@@ -175,11 +444,16 @@ fn move_drone_bump_wall(
     // always at least -1
     _ => panic!("A bump should always at least decrease the wall by one so it can never stay 4: {}", n),
   };
+  // Cached drone paths may go through this wall (see pathfinding.rs)
+  let after = biome.world.tiles[unexty][unextx].tile;
+  record_tile_change(&mut biome.world, before, after);
 
   if n <= 0 {
     // Broke a wall. Add sand.
     // TODO: what about the drill? What about bonuses? Should it be u32 or f32?
-    biome.miner.meta.inventory.sand += 1;
+    inventory_add(&mut biome.miner.meta.inventory, Resource::Sand, 1);
+    // Any fluid next to it may now flow in
+    wake_fluid_around(options, &mut biome.world, nextx, nexty);
   }
 
   // TODO: do drones have a different bump cost?
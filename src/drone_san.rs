@@ -10,8 +10,12 @@ use super::icons::*;
 use super::color::*;
 use super::values::*;
 use super::biome::*;
+use super::inventory::*;
 use super::miner::*;
 use super::app_state::*;
+use super::fluid::*;
+use super::pathfinding::*;
+use serde_derive::{Serialize, Deserialize};
 
 /*
 
@@ -24,7 +28,28 @@ Once the sandrone moved to 0x0 and built its first push cell, it only moves from
 Perhaps it can only create more tiles as long as there is sand?
 */
 
-#[derive(Debug)]
+// What the sandrone did to the castle. It does not move the miner through the castle phases itself,
+// it raises these and the progression turns them into phases (see progression.rs).
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CastleEvent {
+  // The castle walls are done, the sandrone goes to pick up the miner
+  WallsFinished,
+  // The sandrone reached the miner and carries it to the castle
+  MinerPickedUp,
+  // The miner was dropped off in the castle and starts filling it
+  MinerDelivered,
+  // The castle is filled and the sandrone starts redecorating it
+  CastleFilled,
+}
+
+pub fn raise_castle_event(meta: &mut MinerMeta, event: CastleEvent, ticks: u32) {
+  // Each event is raised once, later ones are ignored
+  if !meta.castle_events.iter().any(|(raised, _)| *raised == event) {
+    meta.castle_events.push((event, ticks));
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub enum SandroneState {
   // Not yet built
   Unconstructed,
@@ -45,7 +70,7 @@ pub enum SandroneState {
 
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Sandrone {
   // A sandrone is a drone that works on sand. It builds sand castles.
 
@@ -64,6 +89,7 @@ pub struct Sandrone {
   pub air_lifting: bool,
   pub air_lifted: bool,
   pub post_castle: u32, // Tick at which castle completed
+  pub castle_ca_generations: u32, // Number of castle CA generations that ran so far (see castle_ca.rs)
 
   tmp: u32,
   // Remember the last direction for backtracking over push tiles
@@ -87,6 +113,12 @@ pub struct Sandrone {
   pub expansion_max_y: i32,
   // Did the hydrone add an impassable tile to the end of a
   pub plugged_a_hole: bool,
+  // The path it is currently following (see pathfinding.rs)
+  pub path: CachedPath,
+  // Number of walls drilled through while following a path
+  pub drilled: u32,
+  // Number of steps taken through walls and the world border because no path was found
+  pub ghosted: u32,
 }
 
 pub fn create_sandrone() -> Sandrone {
@@ -114,6 +146,7 @@ pub fn create_sandrone() -> Sandrone {
     air_lifting: false,
     air_lifted: false,
     post_castle: 0,
+    castle_ca_generations: 0,
     tmp: 20,
     last_dx: 1,
     last_dy: 0,
@@ -129,6 +162,9 @@ pub fn create_sandrone() -> Sandrone {
     expansion_max_x: 0,
     expansion_max_y: 0,
     plugged_a_hole: false,
+    path: CachedPath::default(),
+    drilled: 0,
+    ghosted: 0,
   };
 }
 
@@ -154,16 +190,14 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
 
     }
     SandroneState::WaitingForWater => {
-      if biome.miner.meta.inventory.sand >= 10 {
+      if inventory_get(&biome.miner.meta.inventory, Resource::Sand) >= 10 {
         set_sandrone_state(sandrone, SandroneState::MovingToOrigin);
         // start of building a sand castle
       }
     }
     SandroneState::MovingToOrigin => {
-      // Find a way to get closer to 0x0
-      // TODO: do we take the windrone approach of ghosting or do we make it move in the real world or maybe drill through anything it encounters in its path towards 0x0 or smth?
-
-      if move_sandrone_towards(sandrone, 0, 0) {
+      // Find a way to get closer to 0x0. It drills through any wall on the cheapest path there.
+      if move_sandrone_towards(options, &mut biome.world, sandrone, 0, 0) {
         // println!("Convert 0x0 to a push tile and start generating an sand castle");
         set_cell_tile_at(options, &mut biome.world, 0, 0, Tile::Push);
         sandrone.push_tiles.push((0, 0));
@@ -173,7 +207,7 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
           set_sandrone_state(sandrone, SandroneState::PickingUpMiner);
           // bridge::focus_weak(options, biome.index, biome.miner.meta.phase, "shutting down sandrone after building enough tiles");
           sandrone.air_lifting = true;
-          biome.miner.meta.phase = Phase::FinishedCastleWalls_3;
+          raise_castle_event(&mut biome.miner.meta, CastleEvent::WallsFinished, biome.ticks);
         }
 
         // if sandrone.push_tiles.len() > 1000 {
@@ -210,7 +244,7 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
         // Then move like normal movables except the sandrone can only move over Impassible tiles.
         // The sandrone will now be redecorating the castle.
         set_sandrone_state(sandrone, SandroneState::Redecorating);
-        biome.miner.meta.phase = Phase::FilledCastle_6;
+        raise_castle_event(&mut biome.miner.meta, CastleEvent::CastleFilled, biome.ticks);
         biome.miner.meta.dying_since = biome.ticks;
 
         // With the current rules, it is not possible to create a 2x2 of castle walls. So
@@ -250,13 +284,13 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
         // println!("  Rotated right: Cycle now: {:?}", sandrone.direction_cycle);
       }
 
-      if biome.miner.meta.inventory.sand < 10 {
+      if inventory_get(&biome.miner.meta.inventory, Resource::Sand) < 10 {
         sandrone.backtracking = true;
       } else if sandrone.last_expansion_x == sandrone.movable.x && sandrone.last_expansion_y == sandrone.movable.y {
         sandrone.backtracking = false;
       }
 
-      if !sandrone.backtracking && biome.miner.meta.inventory.sand >= 10 && !sandrone.found_end {
+      if !sandrone.backtracking && inventory_get(&biome.miner.meta.inventory, Resource::Sand) >= 10 && !sandrone.found_end {
         for (dx, dy) in sandrone.direction_cycle.to_owned() {
           // println!("- Testing {},{} if sandrone can convert {},{} :: {:?}", dx, dy, fx + dx, fy + dy, get_cell_tile_at(options, &biome.world, fx + dx, fy + dy));
           if sandrone.tmp > 0 && can_convert_tile_to_push(options, &biome.world, fx + dx, fy + dy, dx, dy, sandrone) {
@@ -292,7 +326,7 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
             // println!("Going to pick up miner...");
             set_sandrone_state(sandrone, SandroneState::PickingUpMiner);
             sandrone.air_lifting = true;
-            biome.miner.meta.phase = Phase::FinishedCastleWalls_3;
+            raise_castle_event(&mut biome.miner.meta, CastleEvent::WallsFinished, biome.ticks);
             // Make sure it doesn't branch before it's back at the end...
             sandrone.backtracking = true;
 
@@ -388,7 +422,7 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
 
       sandrone.push_tiles.push((sandrone.movable.x, sandrone.movable.y));
       set_sandrone_state(sandrone, SandroneState::MovingToNeighborCell);
-      biome.miner.meta.inventory.sand = ((biome.miner.meta.inventory.sand as i32) - 10).max(0) as u32;
+      inventory_take(&mut biome.miner.meta.inventory, Resource::Sand, 10);
 
       if ((sandrone.expansion_max_x - sandrone.expansion_min_x) * (sandrone.expansion_max_y - sandrone.expansion_min_y)) as u32 > options.sandcastle_area_limit {
         // bridge::focus_weak(options, biome.index, biome.miner.meta.phase, "castle is big enough to bring in the miner");
@@ -400,7 +434,7 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
         if !sandrone.air_lifted && !sandrone.post_castle > 0 {
           set_sandrone_state(sandrone, SandroneState::PickingUpMiner);
           sandrone.air_lifting = true;
-          biome.miner.meta.phase = Phase::FinishedCastleWalls_3;
+          raise_castle_event(&mut biome.miner.meta, CastleEvent::WallsFinished, biome.ticks);
           // Make sure it doesn't branch before it's back at the end...
           sandrone.backtracking = true;
         }
@@ -409,9 +443,9 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
     SandroneState::PickingUpMiner => {
       // Home in on the miner. Whereever it is.
       // println!("SandroneState::PickingUpMiner at {}x{}", sandrone.movable.x, sandrone.movable.y);
-      if move_sandrone_towards(sandrone, biome.miner.movable.x, biome.miner.movable.y) {
+      if move_sandrone_towards(options, &mut biome.world, sandrone, biome.miner.movable.x, biome.miner.movable.y) {
         // println!("  gottem!");
-        biome.miner.meta.phase = Phase::PickedUpMiner_4;
+        raise_castle_event(&mut biome.miner.meta, CastleEvent::MinerPickedUp, biome.ticks);
         sandrone.state = SandroneState::DeliveringMiner;
         sandrone.status_desc = format!("Delivering miner to origin...");
       }
@@ -419,7 +453,7 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
     SandroneState::DeliveringMiner => {
       // println!("SandroneState::DeliveringMiner at {}x{}", sandrone.movable.x, sandrone.movable.y);
       // Home in on the miner. Whereever it is.
-      if move_sandrone_towards(sandrone, 0, 0) {
+      if move_sandrone_towards(options, &mut biome.world, sandrone, 0, 0) {
         // bridge::focus_weak(options, biome.index, biome.miner.meta.phase, "delivered miner, will now be filling");
         // state.pause_after_ticks = 50;
 
@@ -428,7 +462,7 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
         sandrone.air_lifting = false;
         sandrone.air_lifted = true;
         biome.miner.movable.disabled = false;
-        biome.miner.meta.phase = Phase::FillingCastle_5;
+        raise_castle_event(&mut biome.miner.meta, CastleEvent::MinerDelivered, biome.ticks);
         // Set the top-left corner as the initial exit tile. The exit tile is not to be filled if no other exit tiles have been seen.
         sandrone.last_empty_castle_exit_x = sandrone.expansion_min_x;
         sandrone.last_empty_castle_exit_y = sandrone.expansion_min_y;
@@ -506,9 +540,12 @@ pub fn tick_sandrone(options: &mut Options, _state: &mut AppState, biome: &mut B
         }
       }
 
-      // I think there must always be at least one?
-      let some = ways[ways.len() - 1];
-      ways.pop();
+      // There is always at least one in a world of its own, but in a shared arena world (see
+      // arena.rs) a rival may have boxed the sandrone in. Then it waits for a way to open up.
+      let some = match ways.pop() {
+        Some(way) => way,
+        None => return,
+      };
       let mut tx = some.0;
       let mut ty = some.1;
       let mut txx = some.2;
@@ -652,13 +689,28 @@ fn is_push_cell(options: &Options, world: &World, tx: i32, ty: i32) -> bool {
   return matches!(get_cell_tile_at(options, world, tx, ty), Tile::Push);
 }
 
-fn move_sandrone_towards(sandrone: &mut Sandrone, to_x: i32, to_y: i32) -> bool {
+fn move_sandrone_towards(options: &Options, world: &mut World, sandrone: &mut Sandrone, to_x: i32, to_y: i32) -> bool {
   let bx = sandrone.movable.x;
   let by = sandrone.movable.y;
 
   if bx == to_x && by == to_y {
     return true;
+  } else if let Some((nx, ny)) = next_path_step(options, world, &SANDRONE_PATH_COSTS, &mut sandrone.path, bx, by, to_x, to_y) {
+    ensure_cell_in_world(world, options, nx, ny);
+    if is_wall_tile(get_cell_tile_at(options, world, nx, ny)) {
+      // Drill through. The rest of the planned path does not change because of it.
+      set_cell_tile_at(options, world, nx, ny, Tile::Empty);
+      wake_fluid_around(options, world, nx, ny);
+      sandrone.path.tile_version = world.tile_version;
+      sandrone.drilled += 1;
+    }
+    sandrone.movable.x = nx;
+    sandrone.movable.y = ny;
+    return false;
   } else {
+    // Boxed in by the world border. Ghost straight towards the target. This should be rare so it
+    // is counted, see the sandrone slot ui.
+    sandrone.ghosted += 1;
     // Now move closer to the closest target
     let x1 = to_x as f64;
     let x2 = bx as f64;
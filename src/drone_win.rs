@@ -5,13 +5,15 @@ use super::options::*;
 use super::pickup::*;
 use super::world::*;
 use super::biome::*;
-use super::expando::*;
+use super::inventory::*;
 use super::values::*;
 use super::color::*;
 use super::icons::*;
 use super::app_state::*;
+use super::pathfinding::*;
+use serde_derive::{Serialize, Deserialize};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum WindroneState {
   // Not yet built
   Unconstructed,
@@ -29,13 +31,17 @@ pub enum WindroneState {
   ReturnedHome,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Windrone {
   pub state: WindroneState,
   // TBD but right now a sort of freeform desc of what the windrone is doing
   pub status_desc: String,
   // Each drone has its own x, y, direction, and energy
   pub movable: Movable,
+  // The path it is currently following (see pathfinding.rs)
+  pub path: CachedPath,
+  // Number of steps taken straight through walls because no path was found
+  pub ghosted: u32,
 }
 
 pub fn create_windrone() -> Windrone {
@@ -50,7 +56,9 @@ pub fn create_windrone() -> Windrone {
       now_energy: 0.0,
       init_energy: 0.0,
       disabled: false,
-    }
+    },
+    path: CachedPath::default(),
+    ghosted: 0,
   }
 }
 
@@ -67,98 +75,63 @@ pub fn set_windrone_state(biome: &mut Biome, state: WindroneState) {
   biome.miner.windrone.state = state;
 }
 
-fn find_closest_expando(expandos: &Vec<Expando>, bx: f64, by: f64) -> (bool, usize, i32, i32, f64) {
-  let mut closest_d = usize::MAX as f64;
-  let mut closest_i = 0;
-  let mut closest_x = 0;
-  let mut closest_y = 0;
-  let len = expandos.len();
-
-  let mut found = false;
-  for i in 0..len {
-    if expandos[i].disabled { continue; }
-
-    found = true;
-    let ex = expandos[i].x;
-    let ey = expandos[i].y;
-    let fx = ex as f64;
-    let fy = ey as f64;
-
-    // TODO: I don't think we actually need to sqrt since the absolute value is not relevant (?)
-    let d = ((fx - bx).powf(2.0) + (fy - by).powf(2.0)).sqrt();
+fn find_closest_goal(options: &Options, world: &World, path: &mut CachedPath, bx: i32, by: i32) -> (bool, Option<usize>, i32, i32) {
+  // Find the expando or fountain that is the cheapest to fly to. Returns whether there is any goal,
+  // the index of the expando (None for a fountain), and the coords of the goal.
+  let mut goals: Vec<(i32, i32)> = vec!();
+  let mut expando_indexes: Vec<Option<usize>> = vec!();
+  for (i, expando) in world.expandos.iter().enumerate() {
+    if expando.disabled { continue; }
+    goals.push((expando.x, expando.y));
+    expando_indexes.push(Some(i));
+  }
+  for fountain in world.fountains.iter() {
+    if fountain.disabled { continue; }
+    goals.push((fountain.x, fountain.y));
+    expando_indexes.push(None);
+  }
+  if goals.len() == 0 {
+    return (false, None, 0, 0);
+  }
 
-    // Pick the closest expando. When breaking ties, take the left-most and then the top-most expando. Expandos won't overlap so that should result in one unambiguous winner.
-    if
-    d == 0.0 ||
-      closest_d > d ||
-      (
-        closest_d == d &&
-          (
-            (closest_x as f64) < fx ||
-              (
-                (closest_x as f64) == fx &&
-                  (closest_y as f64) == fy
-              )
-          )
-      )
-    {
-      closest_d = d;
-      closest_i = i;
-      closest_x = ex;
-      closest_y = ey;
+  // Stick to the current goal as long as the path towards it holds up
+  if let Some(g) = goals.iter().position(|&(gx, gy)| gx == path.to_x && gy == path.to_y) {
+    if is_cached_path_valid(options, world, &WINDRONE_PATH_COSTS, path, bx, by, goals[g].0, goals[g].1) {
+      return (true, expando_indexes[g], goals[g].0, goals[g].1);
     }
   }
-  return (found, closest_i, closest_x, closest_y, closest_d);
-}
-
-fn find_closest_fountain(fountains: &Vec<Fountain>, bx: f64, by: f64, mut closest_x: i32, mut closest_y: i32, mut closest_d: f64) -> (bool, usize, i32, i32, f64) {
-  let len = fountains.len();
-  let mut found = false;
-  let mut closest_i = 0;
-
-  for i in 0..len {
-    if fountains[i].disabled { continue; }
-
-    found = true;
-    let ex = fountains[i].x;
-    let ey = fountains[i].y;
-    let fx = ex as f64;
-    let fy = ey as f64;
 
-    // TODO: I don't think we actually need to sqrt since the absolute value is not relevant (?)
-    let d = ((fx - bx).powf(2.0) + (fy - by).powf(2.0)).sqrt();
+  // When none could be reached the last time, the windrone is ghosting towards one of them. Don't
+  // search again for a while.
+  let (to_x, to_y) = (path.to_x, path.to_y);
+  if !(goals.contains(&(to_x, to_y)) && wait_before_replanning(world, path, to_x, to_y)) {
+    if let Some((g, steps)) = find_path_to_nearest(options, world, &WINDRONE_PATH_COSTS, bx, by, &goals) {
+      cache_path(world, path, goals[g].0, goals[g].1, steps);
+      return (true, expando_indexes[g], goals[g].0, goals[g].1);
+    }
+  }
 
-    // Pick the closest expando. When breaking ties, take the left-most and then the top-most expando. Expandos won't overlap so that should result in one unambiguous winner.
-    if
-    d == 0.0 ||
-      closest_d > d ||
-      (
-        closest_d == d &&
-          (
-            (closest_x as f64) < fx ||
-              (
-                (closest_x as f64) == fx &&
-                  (closest_y as f64) == fy
-              )
-          )
-      )
-    {
-      closest_d = d;
-      closest_i = i;
-      closest_x = ex;
-      closest_y = ey;
+  // No goal can be reached. Take the closest one as the crow flies, the windrone will ghost there.
+  // When breaking ties, take the left-most and then the top-most goal.
+  path.steps.clear();
+  let mut g = 0;
+  for i in 1..goals.len() {
+    let d = (goals[i].0 - bx).abs() + (goals[i].1 - by).abs();
+    let closest_d = (goals[g].0 - bx).abs() + (goals[g].1 - by).abs();
+    if d < closest_d || (d == closest_d && (goals[i].0, goals[i].1) < (goals[g].0, goals[g].1)) {
+      g = i;
     }
   }
-  return (found, closest_i, closest_x, closest_y, closest_d);
+  return (true, expando_indexes[g], goals[g].0, goals[g].1);
 }
 
-pub fn tick_windrone(options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+pub fn tick_windrone(options: &mut Options, _state: &mut AppState, biome: &mut Biome, _slot_index: usize) {
   match biome.miner.windrone.state {
     WindroneState::Unconstructed => {
 
     }
     WindroneState::WaitingForWind => {
-      if biome.miner.meta.inventory.wind >= 10 {
+      if inventory_get(&biome.miner.meta.inventory, Resource::Wind) >= 10 {
         set_windrone_state(biome, WindroneState::WaitingForGoal);
       }
     }
@@ -166,7 +139,6 @@ pub fn tick_windrone(options: &mut Options, _state: &mut AppState, biome: &mut B
       if biome.world.expandos.len() > 0 {
         // Get it up in the air!
         set_windrone_state(biome, WindroneState::ReadyForTakeOff);
-        biome.miner.slots[slot_index].val = 1.0;
         biome.miner.windrone.movable.x = biome.miner.movable.x;
         biome.miner.windrone.movable.y = biome.miner.movable.y;
       }
@@ -204,12 +176,9 @@ pub fn tick_windrone(options: &mut Options, _state: &mut AppState, biome: &mut B
 
       let bx = biome.miner.windrone.movable.x;
       let by = biome.miner.windrone.movable.y;
-      let bfx = bx as f64;
-      let bfy = by as f64;
-      let (found_a, closest_i_a, closest_x_a, closest_y_a, closest_d_a) = find_closest_expando(&biome.world.expandos, bfx, bfy);
-      let (found_b, closest_i, closest_x, closest_y, closest_d) = find_closest_fountain(&biome.world.fountains, bfx, bfy, closest_x_a, closest_y_a, closest_d_a);
+      let (found, closest_expando, closest_x, closest_y) = find_closest_goal(options, &biome.world, &mut biome.miner.windrone.path, bx, by);
 
-      if (found_a || found_b) && move_windrone_towards(&mut biome.miner.windrone, closest_x, closest_y, ) {
+      if found && move_windrone_towards(options, &biome.world, &mut biome.miner.windrone, closest_x, closest_y) {
         // Windrone reached an expando or fountain. Replace it.
 
         // Disable the windrone. No longer flying.
@@ -235,6 +204,7 @@ pub fn tick_windrone(options: &mut Options, _state: &mut AppState, biome: &mut B
             },
             (Tile::ExpandoWater, ..) => {
               // Pop the element and swap it with the closest (if not already last). This will drop closest.
+              let closest_i_a = closest_expando.expect("the windrone should be targeting this expando");
               let last = biome.world.expandos.pop();
               if closest_i_a != biome.world.expandos.len() {
                 // We know there must be at least two expandos since the closest one wasn't last on the list.
@@ -257,7 +227,7 @@ pub fn tick_windrone(options: &mut Options, _state: &mut AppState, biome: &mut B
               let fountain = create_fountain(options, biome);
               biome.world.fountains.push(fountain);
             },
-            _ => panic!("Expected to be at a particular cell of interest ... {:?} {} {} {} {} {} {} {} {} {} {} {} {}", cell, found_a, closest_i_a, closest_x_a, closest_y_a, closest_d_a, found_b, closest_i, closest_x, closest_y, closest_d, biome.world.expandos.len(), biome.world.fountains.len()),
+            _ => panic!("Expected to be at a particular cell of interest ... {:?} {:?} {} {} {} {}", cell, closest_expando, closest_x, closest_y, biome.world.expandos.len(), biome.world.fountains.len()),
           }
         }
       }
@@ -265,7 +235,7 @@ pub fn tick_windrone(options: &mut Options, _state: &mut AppState, biome: &mut B
     WindroneState::FlyingHome => {
       // println!("homing back from {},{} to {},{}", windrone.movable.x, windrone.movable.y, mx, my);
       // Fly back to the miner. Stop flying as soon as you hit the same coord (or next to it?).
-      if move_windrone_towards(&mut biome.miner.windrone, biome.miner.movable.x, biome.miner.movable.y) {
+      if move_windrone_towards(options, &biome.world, &mut biome.miner.windrone, biome.miner.movable.x, biome.miner.movable.y) {
         biome.miner.windrone.state = WindroneState::ReturnedHome;
         biome.miner.windrone.status_desc = format!("Idle. Waiting for enough wind...");
       }
@@ -280,13 +250,21 @@ pub fn ui_windrone(_sandrone: &Windrone, options: &Options) -> String {
   return add_fg_color_with_reset(&format!("{}", ICON_WINDRONE), COLOR_WIND, options);
 }
 
-fn move_windrone_towards(windrone: &mut Windrone, to_x: i32, to_y: i32) -> bool {
+fn move_windrone_towards(options: &Options, world: &World, windrone: &mut Windrone, to_x: i32, to_y: i32) -> bool {
   let bx = windrone.movable.x;
   let by = windrone.movable.y;
 
   if bx == to_x && by == to_y {
     return true;
+  } else if let Some((nx, ny)) = next_path_step(options, world, &WINDRONE_PATH_COSTS, &mut windrone.path, bx, by, to_x, to_y) {
+    // Fly around the walls
+    windrone.movable.x = nx;
+    windrone.movable.y = ny;
+    return false;
   } else {
+    // There is no way around. Rise above it and ghost straight towards the target. This should be
+    // rare so it is counted, see the windrone slot ui.
+    windrone.ghosted += 1;
     // Now move closer to the closest expando
     let x1 = to_x as f64;
     let x2 = bx as f64;
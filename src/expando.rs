@@ -1,12 +1,12 @@
-use std::collections::HashSet;
-
 use crate::tile::*;
 use crate::options::*;
 use crate::world::*;
-use crate::pickup::*;
+use crate::fluid::*;
 
 pub const MIN_EXPANDO_SIZE: u32 = 5;
 pub const MAX_EXPANDO_SIZE: u32 = 15;
+// Fluid units poured into the world per expando update
+pub const EXPANDO_PULSE: u32 = 2;
 
 #[derive(Debug)]
 pub struct Expando {
@@ -25,8 +25,8 @@ pub struct Expando {
   // When disabled we should consider this expando not to exist.
   pub disabled: bool,
 
-  // The volume basically tells us how far this expando can still spread
-  // Once it reaches zero, it should be removed
+  // The volume is the amount of fluid this expando can still pour into the world
+  // Once it reaches zero, it stops
   volume: u32,
 
   // Expansion rate in world ticks
@@ -43,6 +43,16 @@ pub enum ExpandoKind {
   Lava,
 }
 
+pub type SerializedExpando = (
+  u8, // kind
+  i32, // x
+  i32, // y
+  bool, // disabled
+  u32, // volume
+  u32, // speed
+  u32, // ticks_since_last_update
+);
+
 pub fn create_expando(x: i32, y: i32, size: u32) -> Expando {
   return Expando {
     kind: ExpandoKind::Water,
@@ -51,7 +61,7 @@ pub fn create_expando(x: i32, y: i32, size: u32) -> Expando {
     disabled: false,
 
     volume: size,
-    speed: 100,
+    speed: 10,
     ticks_since_last_update: 0,
   };
 }
@@ -81,95 +91,55 @@ pub fn tick_expando(expando_index: usize, world: &mut World, options: &Options)
     return;
   }
 
-  // Expandos only expand to empty cells (items and actors are ignored).
-  // When expanding (that's now) they consider the neighbors in a cross (left/right/up/down).
-  // A single expando can "feed" only a predetermined number of cells. Once it has, it can
-  // be removed. My aim is to have a cheap hacky fluid dynamic.
-  // When a neighbor cell is already the target type, repeat the action from that tile. Will
-  // have to remember which cells were already visited to prevent an infinite loop.
+  // Expandos are sources in the fluid model. Every update they pour part of their remaining
+  // volume into their own cell and the fluid model (see fluid.rs) spreads it from there.
+  // Once the volume is depleted the expando is done but the fluid it left behind remains.
 
   let x = expando.x;
   let y = expando.y;
-  let volume = expando.volume;
-
-  // Find all empty neighbour cells, recursively
-  let mut set: HashSet<(i32, i32)> = HashSet::new();
-  collect_empty_neighbors(x, y, expando_index, volume, world, options, &mut set);
-
-  // We should now have all neighbouring cells that we might expand to.
-  // Expand.
-  let mut n = 0;
-  for (wx, wy) in set {
-    let ax = world.min_x.abs() + wx;
-    let ay = world.min_y.abs() + wy;
-
-    assert!(wx >= world.min_x);
-    assert!(wy >= world.min_y);
-    assert!(wx <= world.max_x);
-    assert!(wy <= world.max_y);
-    assert!(ax >= 0);
-    assert!(ay >= 0);
-    assert!(ax < (world.min_x.abs() + 1 + world.max_x));
-    assert!(ay < (world.min_y.abs() + 1 + world.max_y));
-
-    // Convert the tile to a water tile
-    world.tiles[ay as usize][ax as usize].tile = Tile::ExpandoWater;
-    // "swallow" whatever item is here.
-    world.tiles[ay as usize][ax as usize].pickup = Pickup::Nothing;
-
-    n += 1;
-    if n > volume {
-      // println!("Removing expando {} of {}", expando_index, world.expandos.len());
-      // world.expandos.remove(expando_index);
-      // Keep the expando but remove the remaining volume
-      world.expandos[expando_index].volume = 0;
-      break;
-    }
-  }
+  let amount = expando.volume.min(EXPANDO_PULSE);
+
+  add_fluid_at(options, world, x, y, amount);
 
+  world.expandos[expando_index].volume -= amount;
   world.expandos[expando_index].ticks_since_last_update = 0;
 }
 
-fn collect_empty_neighbors(x: i32, y: i32, expando_index: usize, expando_volume: u32, world: &mut World, options: &Options, set: &mut HashSet<(i32, i32)>) {
-  if set.len() > expando_volume as usize {
-    return;
-  }
-
-  /*
-    // The tuple (x,y) should work in a set like this. I just tried it like this to confirm:
-
-    let mut set: HashSet<(i32, i32)> = HashSet::new();
-
-    let s = (1, 2);
-    set.insert(s);
+pub fn expando_serialize(expando: &Expando) -> SerializedExpando {
+  return (
+    match expando.kind {
+      ExpandoKind::Water => 0,
+      ExpandoKind::Gas => 1,
+      ExpandoKind::Toxic => 2,
+      ExpandoKind::Oil => 3,
+      ExpandoKind::Lava => 4,
+    },
+    expando.x,
+    expando.y,
+    expando.disabled,
+    expando.volume,
+    expando.speed,
+    expando.ticks_since_last_update,
+  );
+}
 
-    let t = (1, 2);
-    println!("has? {}", set.contains(&t));
-    let r = (-1, 2);
-    println!("has? {}", set.contains(&r));
-    let q = (1, 2);
-    println!("has? {}", set.contains(&q));
-   */
+pub fn expando_deserialize(serialized_expando: &SerializedExpando) -> Expando {
+  let (kind, x, y, disabled, volume, speed, ticks_since_last_update) = serialized_expando.to_owned();
 
-  match get_cell_tile_at(options, world, x, y) {
-    Tile::Empty => {
-      set.insert((x, y));
+  return Expando {
+    kind: match kind {
+      0 => ExpandoKind::Water,
+      1 => ExpandoKind::Gas,
+      2 => ExpandoKind::Toxic,
+      3 => ExpandoKind::Oil,
+      4 => ExpandoKind::Lava,
+      _ => panic!("Unknown expando kind: {}", kind),
     },
-    Tile::ExpandoWater => {
-      let xy = (x, y);
-      if !set.contains(&xy) {
-        set.insert(xy);
-
-        ensure_cell_in_world(world, options, x - 1, y - 1);
-        ensure_cell_in_world(world, options, x + 1, y + 1);
-
-        collect_empty_neighbors(x-1, y, expando_index, expando_volume, world, options, set);
-        collect_empty_neighbors(x+1, y, expando_index, expando_volume, world, options, set);
-        collect_empty_neighbors(x, y-1, expando_index, expando_volume, world, options, set);
-        collect_empty_neighbors(x, y+1, expando_index, expando_volume, world, options, set);
-
-      }
-    }
-    _ => {},
+    x,
+    y,
+    disabled,
+    volume,
+    speed,
+    ticks_since_last_update,
   };
 }
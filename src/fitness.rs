@@ -0,0 +1,109 @@
+use super::biome::*;
+use super::inventory::*;
+use super::miner::*;
+use super::options::*;
+
+#[cfg(target_arch = "wasm32")]
+use serde_derive::{Serialize, Deserialize};
+
+// The fitness of a miner decides which miner wins a batch, and so what the GA evolves towards.
+// It is also the key of the map of known miners (hmap). The active function is options.fitness.
+
+#[cfg_attr(target_arch = "wasm32", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum FitnessFunction {
+  // Points of the inventory, weighted per resource (see options.fitness_points)
+  Points,
+  // Number of ticks the miner survived
+  Ticks,
+  // Size of the world the miner (and its drones) uncovered
+  ExploredArea,
+  // How far the miner got into the game (see Phase)
+  PhaseReached,
+  // Weighted sum of other functions
+  Combined(Vec<(f64, FitnessFunction)>),
+}
+
+pub fn parse_fitness(spec: &str) -> Result<FitnessFunction, String> {
+  // Either one name, like `points`, or a sum of weighted names, like `points+1000*phase`
+  let mut terms: Vec<(f64, FitnessFunction)> = vec!();
+  for term in spec.split('+') {
+    let (weight, name) = match term.split_once('*') {
+      Some((weight, name)) => match weight.trim().parse::<f64>() {
+        // Nan or infinite weights would make every fitness compare the same
+        Ok(weight) if weight.is_finite() => (weight, name),
+        _ => return Err(format!("Invalid weight `{}` in fitness function `{}`", weight, spec)),
+      },
+      None => (1.0, term),
+    };
+    let f = match name.trim() {
+      "points" => FitnessFunction::Points,
+      "ticks" => FitnessFunction::Ticks,
+      "area" => FitnessFunction::ExploredArea,
+      "phase" => FitnessFunction::PhaseReached,
+      _ => return Err(format!("Unknown fitness function `{}` in `{}`; expecting points, ticks, area, or phase", name, spec)),
+    };
+    terms.push((weight, f));
+  }
+  if terms.len() == 1 && terms[0].0 == 1.0 {
+    return Ok(terms.pop().unwrap().1);
+  }
+  return Ok(FitnessFunction::Combined(terms));
+}
+
+pub fn parse_fitness_points(spec: &str) -> Result<Vec<(Resource, u64)>, String> {
+  // Comma separated list of resource names and their points, like `StoneWhite=2,DiamondYellow=5000`
+  let mut points: Vec<(Resource, u64)> = vec!();
+  for part in spec.split(',') {
+    let (name, value) = match part.split_once('=') {
+      Some(x) => x,
+      None => return Err(format!("Missing `=` in fitness points `{}`", part)),
+    };
+    let resource = match RESOURCES.iter().find(|info| format!("{:?}", info.resource) == name.trim()) {
+      Some(info) => info.resource,
+      None => return Err(format!("Unknown resource `{}` in fitness points `{}`", name, spec)),
+    };
+    let value = match value.trim().parse::<u64>() {
+      Ok(value) => value,
+      Err(_) => return Err(format!("Invalid points `{}` for `{}` in fitness points `{}`", value, name, spec)),
+    };
+    points.push((resource, value));
+  }
+  return Ok(points);
+}
+
+pub fn get_weighted_points(options: &Options, inventory: &Inventory) -> u64 {
+  // Like get_points except that options.fitness_points overrides the value of a resource
+  let mut points = 0;
+  for info in RESOURCES.iter() {
+    let value = match options.fitness_points.iter().find(|(r, _)| *r == info.resource) {
+      Some((_, value)) => *value,
+      None => info.points,
+    };
+    points += inventory_get(inventory, info.resource) as u64 * value;
+  }
+  return points;
+}
+
+pub fn get_phase_reached(meta: &MinerMeta) -> Phase {
+  // A miner that ran out of energy is in the last phase, so check where it was before that
+  return if matches!(meta.phase, Phase::OutOfEnergy_7) { meta.final_phase } else { meta.phase };
+}
+
+pub fn eval_fitness(options: &Options, biome: &Biome, f: &FitnessFunction) -> f64 {
+  return match f {
+    FitnessFunction::Points => get_weighted_points(options, &biome.miner.meta.inventory) as f64,
+    FitnessFunction::Ticks => biome.ticks as f64,
+    FitnessFunction::ExploredArea => ((biome.world.max_x - biome.world.min_x + 1) * (biome.world.max_y - biome.world.min_y + 1)) as f64,
+    FitnessFunction::PhaseReached => get_phase_reached(&biome.miner.meta) as u32 as f64,
+    FitnessFunction::Combined(terms) => terms.iter().map(|(weight, f)| weight * eval_fitness(options, biome, f)).sum(),
+  };
+}
+
+pub fn get_fitness(options: &Options, biome: &Biome) -> u64 {
+  if let Some(fitness) = biome.relative_fitness {
+    // Scored against its rivals in the arena (see arena.rs)
+    return fitness;
+  }
+  return eval_fitness(options, biome, &options.fitness).max(0.0).round() as u64;
+}
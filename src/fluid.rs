@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+use super::tile::*;
+use super::options::*;
+use super::world::*;
+use super::pickup::*;
+
+// The fluid model is a cheap cellular simulation. Every cell has a fluid level (`Cell.fluid`).
+// A cell with fluid "pushes" towards its cross neighbours (left/right/up/down) when their level
+// is at least two lower. The difference is split evenly between the eligible neighbours and the
+// cell itself, rounding down, so fluid is conserved exactly and levels never go negative.
+//
+// Cells that may still change are kept in a FIFO queue. Every world tick only a fixed number of
+// queued cells is processed, so the cost per tick is bounded no matter how big a spill gets.
+// Cells that did not move any fluid fall out of the queue. Since the queue is ordered and the
+// neighbours are always visited in the same order, the outcome is deterministic for a seed.
+
+// Max number of queued fluid cells to process per world tick
+pub const FLUID_CELLS_PER_TICK: usize = 64;
+
+#[derive(Debug)]
+pub struct FluidQueue {
+  // Cells that may still have fluid to move, in the order they will be processed
+  pub cells: VecDeque<(i32, i32)>,
+  // Lookup to prevent queueing the same cell twice. Never iterated, so order is irrelevant.
+  pub queued: HashSet<(i32, i32)>,
+}
+
+pub fn create_fluid_queue() -> FluidQueue {
+  return FluidQueue {
+    cells: VecDeque::new(),
+    queued: HashSet::new(),
+  };
+}
+
+pub fn enqueue_fluid_cell(world: &mut World, x: i32, y: i32) {
+  if world.fluids.queued.insert((x, y)) {
+    world.fluids.cells.push_back((x, y));
+  }
+}
+
+pub fn wake_fluid_around(options: &Options, world: &mut World, x: i32, y: i32) {
+  // Call this when a cell opens up (like a wall breaking) so neighbouring fluid starts flowing
+  // into it. Only neighbours that are already in the world can hold fluid.
+  for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+    let (nx, ny) = wrap_coord(options, nx, ny);
+    if nx >= world.min_x && nx <= world.max_x && ny >= world.min_y && ny <= world.max_y {
+      let ax = (world.min_x.abs() + nx) as usize;
+      let ay = (world.min_y.abs() + ny) as usize;
+      if world.tiles[ay][ax].fluid > 0 {
+        enqueue_fluid_cell(world, nx, ny);
+      }
+    }
+  }
+}
+
+pub fn add_fluid_at(options: &Options, world: &mut World, x: i32, y: i32, amount: u32) {
+  // Pour fluid into given cell. The cell is expected to be able to hold fluid.
+  let (x, y) = wrap_coord(options, x, y);
+  ensure_cell_in_world(world, options, x, y);
+  let ax = (world.min_x.abs() + x) as usize;
+  let ay = (world.min_y.abs() + y) as usize;
+  fill_cell(world, ax, ay, amount);
+  enqueue_fluid_cell(world, x, y);
+}
+
+fn cell_accepts_fluid(world: &World, ax: usize, ay: usize) -> bool {
+  let cell = &world.tiles[ay][ax];
+  return match cell.tile {
+    Tile::ExpandoWater => true,
+    // Unrevealed expandos and fountains are "fake" pickups that should not be swallowed
+    Tile::Empty => !matches!(cell.pickup, Pickup::Expando | Pickup::Fountain),
+    _ => false,
+  };
+}
+
+fn fill_cell(world: &mut World, ax: usize, ay: usize, amount: u32) {
+  let cell = &mut world.tiles[ay][ax];
+  cell.fluid += amount;
+  if matches!(cell.tile, Tile::Empty) {
+    // Convert the tile to a water tile and "swallow" whatever item is here.
+    cell.tile = Tile::ExpandoWater;
+    cell.pickup = Pickup::Nothing;
+    cell.pickup_value = 0;
+    record_tile_change(world, Tile::Empty, Tile::ExpandoWater);
+  }
+}
+
+pub fn tick_fluids(world: &mut World, options: &Options) {
+  let budget = world.fluids.cells.len().min(FLUID_CELLS_PER_TICK);
+
+  for _ in 0..budget {
+    let (x, y) = match world.fluids.cells.pop_front() {
+      Some(xy) => xy,
+      None => break,
+    };
+    world.fluids.queued.remove(&(x, y));
+
+    let ax = (world.min_x.abs() + x) as usize;
+    let ay = (world.min_y.abs() + y) as usize;
+    let level = world.tiles[ay][ax].fluid;
+    // Fluid in a cell that is no longer water (walled in, converted, ...) is trapped.
+    if level < 2 || !matches!(world.tiles[ay][ax].tile, Tile::ExpandoWater) {
+      continue;
+    }
+
+    // Note: expanding the world may shift array indexes so collect world coords first
+    let mut targets: Vec<(i32, i32, u32)> = vec!();
+    for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+      let (nx, ny) = wrap_coord(options, nx, ny);
+      ensure_cell_in_world(world, options, nx, ny);
+      let nax = (world.min_x.abs() + nx) as usize;
+      let nay = (world.min_y.abs() + ny) as usize;
+      if cell_accepts_fluid(world, nax, nay) && world.tiles[nay][nax].fluid + 1 < level {
+        targets.push((nx, ny, world.tiles[nay][nax].fluid));
+      }
+    }
+
+    let share = targets.len() as u32 + 1;
+    let mut moved = 0;
+    for (nx, ny, nlevel) in targets {
+      let amount = (level - nlevel) / share;
+      if amount > 0 {
+        let nax = (world.min_x.abs() + nx) as usize;
+        let nay = (world.min_y.abs() + ny) as usize;
+        fill_cell(world, nax, nay, amount);
+        enqueue_fluid_cell(world, nx, ny);
+        moved += amount;
+      }
+    }
+
+    if moved > 0 {
+      let ax = (world.min_x.abs() + x) as usize;
+      let ay = (world.min_y.abs() + y) as usize;
+      world.tiles[ay][ax].fluid -= moved;
+      enqueue_fluid_cell(world, x, y);
+    }
+  }
+}
@@ -1,20 +1,39 @@
 use std::collections::HashSet;
+use std::collections::VecDeque;
 
 use super::tile::*;
 use super::biome::*;
 use super::options::*;
 use super::world::*;
 use super::pickup::*;
+use super::fluid::*;
+
+// Number of times a fountain spouts before it runs dry
+pub const FOUNTAIN_OUTPUT: u32 = 50;
+// World ticks between spouts
+pub const FOUNTAIN_INTERVAL: u32 = 100;
 
 #[derive(Debug)]
 pub struct Fountain {
   pub x: i32,
   pub y: i32,
-  pub water_tiles: HashSet<(i32, i32)>,
+  // Sorted so that every pass over it visits the tiles in the same order
+  pub water_tiles: Vec<(i32, i32)>,
   pub ticks: u32,
+  // How many more times this fountain spouts. It is disabled once this hits zero.
+  pub remaining: u32,
   pub disabled: bool,
 }
 
+pub type SerializedFountain = (
+  i32, // x
+  i32, // y
+  Vec<(i32, i32)>, // water_tiles
+  u32, // ticks
+  u32, // remaining
+  bool, // disabled
+);
+
 pub fn create_fountain(
   options: &mut Options, biome: &mut Biome
   // biome.miner.windrone.movable.x, biome.miner.windrone.movable.y, &mut biome.world, options
@@ -22,14 +41,14 @@ pub fn create_fountain(
 ) -> Fountain {
   let x = biome.miner.windrone.movable.x;
   let y = biome.miner.windrone.movable.y;
-  let mut set: HashSet<(i32, i32)> = HashSet::new();
-  collect_connected_water_coords(x, y, &mut biome.world, options, &mut set);
+  let water_tiles = collect_connected_water_coords(x, y, &mut biome.world, options);
 
   return Fountain {
     x,
     y,
-    water_tiles: set,
+    water_tiles,
     ticks: 0,
+    remaining: FOUNTAIN_OUTPUT,
     disabled: false,
   };
 }
@@ -45,59 +64,99 @@ pub fn tick_fountain(fountain_index: usize, world: &mut World, options: &Options
 
   world.fountains[fountain_index].ticks += 1;
 
-  if world.fountains[fountain_index].ticks > 100 {
+  if world.fountains[fountain_index].ticks > FOUNTAIN_INTERVAL {
     world.fountains[fountain_index].ticks = 0;
     // println!("its doing the thing!");
-    // Find a tile that is not yet full and fill it. Otherwise ignore.
-
-    for (wx, wy) in world.fountains[fountain_index].water_tiles.iter() {
-      let stuff = get_cell_stuff_at(options, world, *wx, *wy);
-      if matches!(stuff.1, Pickup::Nothing) {
-        let ax = world.min_x.abs() + wx;
-        let ay = world.min_y.abs() + wy;
-        assert_arr_xy_in_world(world, *wx, *wy, ax as usize, ay as usize);
-        world.tiles[ay as usize][ax as usize].pickup = Pickup::Water;
-
-        // Can be this but it emits a warning that it's no longer valid later... :/
-        // set_cell_pickup_at(options, world, *wx, *wy, Pickup::Water);
-        break;
+
+    // A fountain is a finite source. Each spout it pours a unit of fluid into the shallowest
+    // of its water tiles and puts a water pickup on the first tile that is not yet full.
+    let mut shallowest: Option<(i32, i32, u32)> = None;
+    let mut filled = false;
+    for n in 0..world.fountains[fountain_index].water_tiles.len() {
+      let (wx, wy) = world.fountains[fountain_index].water_tiles[n];
+      let ax = world.min_x.abs() + wx;
+      let ay = world.min_y.abs() + wy;
+      assert_arr_xy_in_world(world, wx, wy, ax as usize, ay as usize);
+      let cell = &mut world.tiles[ay as usize][ax as usize];
+      if !matches!(cell.tile, Tile::ExpandoWater) {
+        // Skip the fountain cell itself and any tile that has since been converted
+        continue;
       }
+      if !filled && matches!(cell.pickup, Pickup::Nothing) {
+        cell.pickup = Pickup::Water;
+        filled = true;
+      }
+      match shallowest {
+        Some((_, _, level)) if level <= cell.fluid => {},
+        _ => shallowest = Some((wx, wy, cell.fluid)),
+      }
+    }
+
+    if let Some((wx, wy, _)) = shallowest {
+      add_fluid_at(options, world, wx, wy, 1);
+    }
+
+    world.fountains[fountain_index].remaining -= 1;
+    if world.fountains[fountain_index].remaining == 0 {
+      // Ran dry
+      world.fountains[fountain_index].disabled = true;
     }
   }
 }
 
-fn collect_connected_water_coords(x: i32, y: i32, world: &World, options: &Options, set: &mut HashSet<(i32, i32)>) {
-  /*
-    // The tuple (x,y) should work in a set like this. I just tried it like this to confirm:
-
-    let mut set: HashSet<(i32, i32)> = HashSet::new();
-
-    let s = (1, 2);
-    set.insert(s);
-
-    let t = (1, 2);
-    println!("has? {}", set.contains(&t));
-    let r = (-1, 2);
-    println!("has? {}", set.contains(&r));
-    let q = (1, 2);
-    println!("has? {}", set.contains(&q));
-   */
-
-  // The initial cell is empty but has a fountain. The other (targeted) cells are water and empty.
-  if matches!(get_cell_tile_at(options, world, x, y), Tile::ExpandoWater) || matches!(get_cell_stuff_at(options, world, x, y).1, Pickup::Fountain){
-    let xy = (x, y);
-    if !set.contains(&xy) {
-      set.insert(xy);
-
-      // Since water tiles are not procedurally generates and we only care about water tiles
-      // we should be able to ignore any tile that is not inside the already generated world.
-      // Visit all cells in a cross if they are generated.
-      for (tx, ty) in [(x -1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
-        if tx >= world.min_x && tx <= world.max_x && ty >= world.min_y && ty <= world.max_y {
-          collect_connected_water_coords(tx, ty, world, options, set);
-        }
+fn collect_connected_water_coords(x: i32, y: i32, world: &World, options: &Options) -> Vec<(i32, i32)> {
+  // Flood fill with an explicit queue. The initial cell is empty but has a fountain. The other
+  // (targeted) cells are water and empty.
+  // Since water tiles are not procedurally generates and we only care about water tiles
+  // we should be able to ignore any tile that is not inside the already generated world.
+
+  let mut seen: HashSet<(i32, i32)> = HashSet::new();
+  let mut queue: VecDeque<(i32, i32)> = VecDeque::new();
+  let mut found: Vec<(i32, i32)> = vec!();
+
+  seen.insert((x, y));
+  queue.push_back((x, y));
+
+  while let Some((cx, cy)) = queue.pop_front() {
+    if !(matches!(get_cell_tile_at(options, world, cx, cy), Tile::ExpandoWater) || matches!(get_cell_stuff_at(options, world, cx, cy).1, Pickup::Fountain)) {
+      continue;
+    }
+    found.push((cx, cy));
+
+    // Visit all cells in a cross if they are generated.
+    for (tx, ty) in [(cx - 1, cy), (cx + 1, cy), (cx, cy - 1), (cx, cy + 1)] {
+      let (tx, ty) = wrap_coord(options, tx, ty);
+      if tx >= world.min_x && tx <= world.max_x && ty >= world.min_y && ty <= world.max_y && seen.insert((tx, ty)) {
+        queue.push_back((tx, ty));
       }
     }
+  }
+
+  found.sort();
+  return found;
+}
+
+pub fn fountain_serialize(fountain: &Fountain) -> SerializedFountain {
+  return (
+    fountain.x,
+    fountain.y,
+    fountain.water_tiles.to_owned(),
+    fountain.ticks,
+    fountain.remaining,
+    fountain.disabled,
+  );
+}
+
+pub fn fountain_deserialize(serialized_fountain: &SerializedFountain) -> Fountain {
+  let (x, y, water_tiles, ticks, remaining, disabled) = serialized_fountain.to_owned();
+
+  return Fountain {
+    x,
+    y,
+    water_tiles,
+    ticks,
+    remaining,
+    disabled,
   };
 }
 
@@ -1,16 +1,22 @@
 use std::fmt::Write;
 
 use std::fmt;
+use std::collections::BTreeMap;
 use rand_pcg::{Lcg128Xsl64};
 use rand::distributions::{Distribution, Uniform};
 
 use super::options::*;
 use super::slottable::*;
+use super::mutation::*;
+use super::turn_rule::*;
+use super::drone_me::*;
+use super::slot_drone_launcher::*;
+use serde_derive::{Serialize, Deserialize};
 
 /**
  * Describe the genes for a single Miner instantiation
  */
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Helix {
   // Seed of the world. Known results should be guaranteed valid in a world generated by this seed.
   // May have been used to generate this helix but the helix may also have been derived
@@ -23,72 +29,283 @@ pub struct Helix {
   // Gene: How much energy does the miner start with
   pub multiplier_energy_start: f32,
 
-  // Gene: How fast does the miner receive points
-  pub multiplier_points: f32, // TODO: unused until we can properly balance this. This property should probably be a hidden side effect of another gene.
+  // Gene: How fast does the miner receive points. Percent chance of an extra stone or gem per pickup.
+  pub multiplier_points: f32,
 
   // Gene: How expensive is it to bump against a block?
   pub block_bump_cost: f32,
 
-  // Gene: How effective are pickups?
+  // Gene: How effective are pickups? Percent of extra energy per energy pickup.
   pub multiplier_energy_pickup: f32,
 
   // Gene: How effective are items (slottables)?
   //  multiplier_cooldown: i32,
 
+  // Genes: When to use a consumable (see consumable.rs)
+  // Use a ration when the energy drops below this percentage of the max energy
+  pub consumable_ration_energy: f32,
+  // Use dynamite when the boredom level exceeds this
+  pub consumable_dynamite_boredom: f32,
+  // Use a flare when the boredom level exceeds this
+  pub consumable_flare_boredom: f32,
+
+  // Genes: how the drones of the drone launchers behave. The energy budget is a numeric gene, the
+  // rest is in drone_genes (see drone_me.rs), serialized like drone_genes_to_string.
+  pub drone_energy: f32,
+  pub drone_genes: DroneGenes,
+
+  // Gene: Langton's ant style turning rules, one per TurnContext (see turn_rule.rs)
+  pub turn_rules: [TurnRule; TURN_CONTEXT_COUNT],
+
+  // Genes: mutation step sizes (in percent) for the other genes and the slots. Only used with
+  // the SelfAdaptive mutation schedule, see mutation.rs. Serialized with the numeric genes, but
+  // they are not part of the GENE_SCHEMA since they mutate differently.
+  pub mutation_step_genes: f32,
+  pub mutation_step_slots: f32,
+
+  // Bit set of the SlotOperators that were applied when this helix was mutated from its parent.
+  // Only used to track which operators lead to improvements. Not part of the SerializedHelix.
+  pub slot_operators: u8,
+
   pub slots: [SlotKind; 32],
 }
 
-// Workaround for Serde; we serialize the helix to a plain tuple
+// The numeric genes of a helix, with their bounds. To add a gene, add a field to the Helix, an
+// entry to the Gene enum and the GENE_SCHEMA, and map it in get_gene and set_gene. Creating,
+// mutating, printing, and serializing a helix is driven by the schema.
+
+#[derive(Debug, Clone, Copy)]
+pub enum Gene {
+  DroneGenCooldown,
+  EnergyStart,
+  Points,
+  BlockBumpCost,
+  EnergyPickup,
+  RationEnergy,
+  DynamiteBoredom,
+  FlareBoredom,
+  DroneEnergy,
+}
+
+pub struct GeneSpec {
+  pub gene: Gene,
+  // Used in helix_to_string, and the key of the gene in a SerializedHelix. Renaming it drops the
+  // gene from stored helices.
+  pub label: &'static str,
+  // Mutations never take the gene outside of these bounds
+  pub min: f32,
+  pub max: f32,
+  // A random helix gets a value between min and this
+  pub initial_max: f32,
+  // Value of a helix that does not carry this gene, like the null helix or a helix deserialized
+  // from a SerializedHelix without it
+  pub default: f32,
+}
+
+pub const GENE_COUNT: usize = 9;
+pub static GENE_SCHEMA: [GeneSpec; GENE_COUNT] = [
+  GeneSpec { gene: Gene::DroneGenCooldown, label: "drone gen", min: 0.0, max: 1000.0, initial_max: 100.0, default: 0.0 },
+  GeneSpec { gene: Gene::EnergyStart, label: "energy start", min: 0.0, max: 1000.0, initial_max: 100.0, default: 0.0 },
+  GeneSpec { gene: Gene::Points, label: "points", min: 0.0, max: 100.0, initial_max: 25.0, default: 0.0 },
+  GeneSpec { gene: Gene::BlockBumpCost, label: "bump cost", min: 1.0, max: 1000.0, initial_max: 100.0, default: 0.0 },
+  GeneSpec { gene: Gene::EnergyPickup, label: "energy pickups", min: 0.0, max: 200.0, initial_max: 25.0, default: 0.0 },
+  GeneSpec { gene: Gene::RationEnergy, label: "ration below energy %", min: 0.0, max: 100.0, initial_max: 50.0, default: 25.0 },
+  GeneSpec { gene: Gene::DynamiteBoredom, label: "dynamite above boredom", min: 0.0, max: 500.0, initial_max: 100.0, default: 50.0 },
+  GeneSpec { gene: Gene::FlareBoredom, label: "flare above boredom", min: 0.0, max: 500.0, initial_max: 100.0, default: 25.0 },
+  GeneSpec { gene: Gene::DroneEnergy, label: "drone energy", min: 100.0, max: 5000.0, initial_max: 2000.0, default: DRONE_INITIAL_ENERGY },
+];
+
+pub fn get_gene(helix: &Helix, gene: Gene) -> f32 {
+  return match gene {
+    Gene::DroneGenCooldown => helix.drone_gen_cooldown,
+    Gene::EnergyStart => helix.multiplier_energy_start,
+    Gene::Points => helix.multiplier_points,
+    Gene::BlockBumpCost => helix.block_bump_cost,
+    Gene::EnergyPickup => helix.multiplier_energy_pickup,
+    Gene::RationEnergy => helix.consumable_ration_energy,
+    Gene::DynamiteBoredom => helix.consumable_dynamite_boredom,
+    Gene::FlareBoredom => helix.consumable_flare_boredom,
+    Gene::DroneEnergy => helix.drone_energy,
+  };
+}
+
+pub fn set_gene(helix: &mut Helix, gene: Gene, value: f32) {
+  match gene {
+    Gene::DroneGenCooldown => helix.drone_gen_cooldown = value,
+    Gene::EnergyStart => helix.multiplier_energy_start = value,
+    Gene::Points => helix.multiplier_points = value,
+    Gene::BlockBumpCost => helix.block_bump_cost = value,
+    Gene::EnergyPickup => helix.multiplier_energy_pickup = value,
+    Gene::RationEnergy => helix.consumable_ration_energy = value,
+    Gene::DynamiteBoredom => helix.consumable_dynamite_boredom = value,
+    Gene::FlareBoredom => helix.consumable_flare_boredom = value,
+    Gene::DroneEnergy => helix.drone_energy = value,
+  }
+}
+
+fn get_gene_label(gene: Gene) -> &'static str {
+  return GENE_SCHEMA.iter().find(|spec| spec.gene as usize == gene as usize).expect("every gene should be in the GENE_SCHEMA").label;
+}
+
+fn genes_to_string(helix: &Helix) -> String {
+  let genes: Vec<String> = GENE_SCHEMA.iter().map(|spec| format!("{}: {}", spec.label, get_gene(helix, spec.gene))).collect();
+  return genes.join(", ");
+}
+
+// Workaround for Serde; we serialize the helix to a plain tuple. The numeric genes are a map
+// keyed by their GENE_SCHEMA label so a gene can be added without breaking stored helices. A gene
+// that is missing from the map gets its default.
+
+// Keys of the mutation step sizes in the genes map
+pub const MUTATION_STEP_GENES_KEY: &str = "mutation step genes";
+pub const MUTATION_STEP_SLOTS_KEY: &str = "mutation step slots";
 
 pub type SerializedHelix = (
   u64, // seed
-  f32, // drone_gen_cooldown
-  f32, // multiplier_energy_start
-  f32, // multiplier_points
-  f32, // block_bump_cost
-  f32, // multiplier_energy_pickup
-  String // slots: [SlotKind; 32]
+  BTreeMap<String, f32>, // numeric genes, by label
+  String, // slots: [SlotKind; 32]
+  String, // turn_rules, like "N/V"
+  String, // drone_genes, like "R/fge/S"
 );
 
+// The SerializedHelix before it had the drone genes. Still accepted by parse_serialized_helix.
+pub type MapSerializedHelix = (u64, BTreeMap<String, f32>, String, String);
+
+// The SerializedHelix before the genes were a map. Still accepted by parse_serialized_helix.
+pub type PositionalSerializedHelix = (u64, f32, f32, f32, f32, f32, String, String);
+// The SerializedHelix before it had the turn rules. Still accepted by parse_serialized_helix.
+pub type LegacySerializedHelix = (u64, f32, f32, f32, f32, f32, String);
+
+pub fn parse_serialized_helix(json: &str) -> Result<SerializedHelix, String> {
+  return match serde_json::from_str::<serde_json::Value>(json) {
+    Ok(value) => serialized_helix_from_value(value),
+    Err(err) => Err(format!("Unable to parse miner code `{}`: {}", json, err)),
+  };
+}
+
+pub fn serialized_helix_from_value(value: serde_json::Value) -> Result<SerializedHelix, String> {
+  // Accepts the current and the older formats
+  let serialized =
+    if let Ok(serialized) = serde_json::from_value::<SerializedHelix>(value.clone()) {
+      serialized
+    } else if let Ok(map) = serde_json::from_value::<MapSerializedHelix>(value.clone()) {
+      map_to_serialized_helix(map)
+    } else if let Ok(positional) = serde_json::from_value::<PositionalSerializedHelix>(value.clone()) {
+      positional_to_serialized_helix(positional)
+    } else {
+      match serde_json::from_value::<LegacySerializedHelix>(value.clone()) {
+        Ok(legacy) => legacy_to_serialized_helix(legacy),
+        Err(err) => return Err(format!("Unable to parse miner code `{}`: {}", value, err)),
+      }
+    };
+  if let Err(msg) = validate_serialized_helix(&serialized) {
+    return Err(format!("Invalid miner code `{}`: {}", value, msg));
+  }
+  return Ok(serialized);
+}
+
+fn validate_serialized_helix(serialized: &SerializedHelix) -> Result<(), String> {
+  // The parts that helix_deserialize would otherwise choke on
+  let (_, _, slots, turn_rules, drone_genes) = serialized;
+  if slots.chars().count() != 32 {
+    return Err(format!("Expecting 32 slots, got {}", slots.chars().count()));
+  }
+  if let Some(c) = slots.chars().find(|&c| !SLOT_REGISTRY.iter().any(|reg| reg.symbol == c)) {
+    return Err(format!("Unknown slot `{}`", c));
+  }
+  parse_turn_rules(turn_rules)?;
+  parse_drone_genes(drone_genes)?;
+  return Ok(());
+}
+
+pub fn positional_to_serialized_helix(positional: PositionalSerializedHelix) -> SerializedHelix {
+  let (seed, drone_gen_cooldown, multiplier_energy_start, multiplier_points, block_bump_cost, multiplier_energy_pickup, slots, turn_rules) = positional;
+  let genes: BTreeMap<String, f32> = [
+    (Gene::DroneGenCooldown, drone_gen_cooldown),
+    (Gene::EnergyStart, multiplier_energy_start),
+    (Gene::Points, multiplier_points),
+    (Gene::BlockBumpCost, block_bump_cost),
+    (Gene::EnergyPickup, multiplier_energy_pickup),
+  ].iter().map(|&(gene, value)| (get_gene_label(gene).to_string(), value)).collect();
+  return map_to_serialized_helix((seed, genes, slots, turn_rules));
+}
+
+pub fn map_to_serialized_helix(map: MapSerializedHelix) -> SerializedHelix {
+  let (seed, genes, slots, turn_rules) = map;
+  return (seed, genes, slots, turn_rules, drone_genes_to_string(&create_default_drone_genes()));
+}
+
+pub fn legacy_to_serialized_helix(legacy: LegacySerializedHelix) -> SerializedHelix {
+  let (seed, drone_gen_cooldown, multiplier_energy_start, multiplier_points, block_bump_cost, multiplier_energy_pickup, slots) = legacy;
+  return positional_to_serialized_helix((seed, drone_gen_cooldown, multiplier_energy_start, multiplier_points, block_bump_cost, multiplier_energy_pickup, slots, turn_rules_to_string(&create_default_turn_rules())));
+}
+
 impl fmt::Display for Helix {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "Helix [ seed: {}, drone gen: {}, energy start: {}, points: {}, bump cost: {}, energy pickups: {} ]", self.seed, self.drone_gen_cooldown, self.multiplier_energy_start, self.multiplier_points, self.block_bump_cost, self.multiplier_energy_pickup)
+    write!(f, "Helix [ seed: {}, {}, drones: {}, turns: {} ]", self.seed, genes_to_string(self), drone_genes_to_string(&self.drone_genes), turn_rules_to_string(&self.turn_rules))
   }
 }
 
 pub fn helix_to_json(helix: &Helix) -> String {
   return format!(
-    "{{drone_gen_cooldown: {}, multiplier_energy_start: {}, block_bump_cost: {}, multiplier_energy_pickup: {}, slots: {}}}",
+    "{{drone_gen_cooldown: {}, multiplier_energy_start: {}, multiplier_points: {}, block_bump_cost: {}, multiplier_energy_pickup: {}, consumable_ration_energy: {}, consumable_dynamite_boredom: {}, consumable_flare_boredom: {}, drone_energy: {}, drone_genes: {}, turn_rules: {}, slots: {}}}",
     helix.drone_gen_cooldown,
     helix.multiplier_energy_start,
+    helix.multiplier_points,
     helix.block_bump_cost,
     helix.multiplier_energy_pickup,
+    helix.consumable_ration_energy,
+    helix.consumable_dynamite_boredom,
+    helix.consumable_flare_boredom,
+    helix.drone_energy,
+    drone_genes_to_string(&helix.drone_genes),
+    turn_rules_to_string(&helix.turn_rules),
     slots_to_short_string(helix.slots),
   );
 }
 
 pub fn create_null_helix() -> Helix {
-  return Helix {
+  let mut h = Helix {
     seed: 0,
     drone_gen_cooldown: 0.0,
     multiplier_energy_start: 0.0,
     multiplier_points: 0.0,
     block_bump_cost: 0.0,
     multiplier_energy_pickup: 0.0,
+    consumable_ration_energy: 0.0,
+    consumable_dynamite_boredom: 0.0,
+    consumable_flare_boredom: 0.0,
+    drone_energy: 0.0,
+    drone_genes: create_default_drone_genes(),
+    turn_rules: create_default_turn_rules(),
+    mutation_step_genes: INITIAL_MUTATION_STEP,
+    mutation_step_slots: INITIAL_MUTATION_STEP,
+    slot_operators: 0,
     slots: [SlotKind::Emptiness; 32],
   };
+  for spec in GENE_SCHEMA.iter() {
+    set_gene(&mut h, spec.gene, spec.default);
+  }
+  return h;
 }
 
 pub fn create_initial_helix(rng: &mut Lcg128Xsl64, seed: u64) -> Helix {
-  let multiplier_percent: Uniform<f32> = Uniform::from(0.0..100.0);
-
-  let h = Helix {
+  let mut h = Helix {
     seed,
-    drone_gen_cooldown: multiplier_percent.sample(rng).round(),
-    multiplier_energy_start: multiplier_percent.sample(rng).round(),
-    multiplier_points: 0f32, // multiplier_percent.sample(rng),
-    block_bump_cost: multiplier_percent.sample(rng).max(1.0).round(),
-    multiplier_energy_pickup: 0.0, // multiplier_percent.sample(rng),
+    drone_gen_cooldown: 0.0,
+    multiplier_energy_start: 0.0,
+    multiplier_points: 0.0,
+    block_bump_cost: 0.0,
+    multiplier_energy_pickup: 0.0,
+    consumable_ration_energy: 0.0,
+    consumable_dynamite_boredom: 0.0,
+    consumable_flare_boredom: 0.0,
+    drone_energy: 0.0,
+    drone_genes: create_default_drone_genes(),
+    turn_rules: create_default_turn_rules(),
+    mutation_step_genes: INITIAL_MUTATION_STEP,
+    mutation_step_slots: INITIAL_MUTATION_STEP,
+    slot_operators: 0,
     slots: [
       get_random_slot(rng),
       get_random_slot(rng),
@@ -125,21 +342,26 @@ pub fn create_initial_helix(rng: &mut Lcg128Xsl64, seed: u64) -> Helix {
     ],
   };
 
+  for spec in GENE_SCHEMA.iter() {
+    let roller: Uniform<f32> = Uniform::from(spec.min..spec.initial_max);
+    set_gene(&mut h, spec.gene, roller.sample(rng).round().clamp(spec.min, spec.max));
+  }
+
   return h;
 }
 
-fn mutate_gen_maybe(current: f32, roll: f32, options: &Options) -> f32 {
+fn mutate_gen_maybe(current: f32, roll: f32, rate: f32) -> f32 {
   // Roll is 0..100
   // Move the value up or down by 5%
   // Return a rounded value
   // Do not underflow
-  let delta = (roll / 100.0) * (2.0 * options.mutation_rate_genes) - options.mutation_rate_genes;
+  let delta = (roll / 100.0) * (2.0 * rate) - rate;
   let mutated = current + delta;
   return mutated.round().max(0.0);
 }
 
-fn mutate_slot_maybe(current: SlotKind, roll: f32, rng: &mut Lcg128Xsl64, options: &Options) -> SlotKind {
-  if roll < options.mutation_rate_slots {
+fn mutate_slot_maybe(current: SlotKind, roll: f32, rng: &mut Lcg128Xsl64, rate: f32) -> SlotKind {
+  if roll < rate {
     get_random_slot(rng)
   } else {
     current
@@ -147,95 +369,150 @@ fn mutate_slot_maybe(current: SlotKind, roll: f32, rng: &mut Lcg128Xsl64, option
 }
 
 pub fn mutate_helix(rng: &mut Lcg128Xsl64, helix: &Helix, options: &Options) -> Helix {
+  return mutate_helix_at_rates(rng, helix, options, options.mutation_rate_genes, options.mutation_rate_slots);
+}
+
+pub fn mutate_helix_at_rates(rng: &mut Lcg128Xsl64, helix: &Helix, options: &Options, rate_genes: f32, rate_slots: f32) -> Helix {
   // Modify each gene by up to x%, up or down. Make sure the final value does not underflow or overflow.
   let pct_roller: Uniform<f32> = Uniform::from(0.0..100.0);
 
-  return Helix {
+  // With self-adaptation the step sizes mutate first and then determine the rates for the rest
+  let (mutation_step_genes, mutation_step_slots) =
+    if matches!(options.mutation_schedule, MutationSchedule::SelfAdaptive) {
+      (self_adapt_step(rng, helix.mutation_step_genes, options), self_adapt_step(rng, helix.mutation_step_slots, options))
+    } else {
+      (helix.mutation_step_genes, helix.mutation_step_slots)
+    };
+  let (rate_genes, rate_slots) =
+    if matches!(options.mutation_schedule, MutationSchedule::SelfAdaptive) { (mutation_step_genes, mutation_step_slots) }
+    else { (rate_genes, rate_slots) };
+
+  let mut slots = helix.slots;
+  for i in 0..slots.len() {
+    slots[i] = mutate_slot_maybe(slots[i], pct_roller.sample(rng), rng, rate_slots);
+  }
+  // Slot order matters too (nth, magnet direction), so also try the structural operators
+  let slot_operators = mutate_slot_structure(rng, &mut slots, options);
+
+  let mut mutated = Helix {
     seed: options.seed, // World seed where this miner will be tested in
-    drone_gen_cooldown: mutate_gen_maybe(helix.drone_gen_cooldown, pct_roller.sample(rng), options),
-    multiplier_energy_start: mutate_gen_maybe(helix.multiplier_energy_start, pct_roller.sample(rng), options),
-    multiplier_points: 0.0,
-    block_bump_cost: mutate_gen_maybe(helix.block_bump_cost, pct_roller.sample(rng), options).max(1.0),
-    multiplier_energy_pickup: 0.0,
-    slots: [
-      mutate_slot_maybe(helix.slots[0], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[1], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[2], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[3], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[4], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[5], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[6], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[7], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[8], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[9], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[10], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[11], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[12], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[13], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[14], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[15], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[16], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[17], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[19], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[20], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[21], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[22], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[23], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[24], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[25], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[26], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[27], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[28], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[29], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[30], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[31], pct_roller.sample(rng), rng, options),
-      mutate_slot_maybe(helix.slots[18], pct_roller.sample(rng), rng, options),
-    ],
+    drone_gen_cooldown: helix.drone_gen_cooldown,
+    multiplier_energy_start: helix.multiplier_energy_start,
+    multiplier_points: helix.multiplier_points,
+    block_bump_cost: helix.block_bump_cost,
+    multiplier_energy_pickup: helix.multiplier_energy_pickup,
+    consumable_ration_energy: helix.consumable_ration_energy,
+    consumable_dynamite_boredom: helix.consumable_dynamite_boredom,
+    consumable_flare_boredom: helix.consumable_flare_boredom,
+    drone_energy: helix.drone_energy,
+    drone_genes: mutate_drone_genes(rng, &helix.drone_genes, rate_genes),
+    turn_rules: mutate_turn_rules(rng, &helix.turn_rules, rate_genes),
+    mutation_step_genes,
+    mutation_step_slots,
+    slot_operators,
+    slots,
+  };
+  for spec in GENE_SCHEMA.iter() {
+    let value = mutate_gen_maybe(get_gene(helix, spec.gene), pct_roller.sample(rng), rate_genes);
+    set_gene(&mut mutated, spec.gene, value.clamp(spec.min, spec.max));
   }
+
+  return mutated;
 }
 
 pub fn helix_to_string(into: &mut String, helix: &Helix) {
   // let mut out: String = "".to_string();
-  write!(into, "Helix {{ drone gen: {}, energy start: {}, points: {}, bump cost: {}, energy pickups: {}, slots: {} }}",
-    helix.drone_gen_cooldown,
-    helix.multiplier_energy_start,
-    helix.multiplier_points,
-    helix.block_bump_cost,
-    helix.multiplier_energy_pickup,
+  write!(into, "Helix {{ {}, drones: {}, turns: {}, mutation steps: {:.1}/{:.1}, slots: {} }}",
+    genes_to_string(helix),
+    drone_genes_to_string(&helix.drone_genes),
+    turn_rules_to_string(&helix.turn_rules),
+    helix.mutation_step_genes,
+    helix.mutation_step_slots,
     slots_to_short_string(helix.slots)
   ).unwrap();
 }
 
 pub fn helix_serialize(helix: &Helix) -> SerializedHelix {
+  let mut genes: BTreeMap<String, f32> = GENE_SCHEMA.iter().map(|spec| (spec.label.to_string(), get_gene(helix, spec.gene))).collect();
+  genes.insert(MUTATION_STEP_GENES_KEY.to_string(), helix.mutation_step_genes);
+  genes.insert(MUTATION_STEP_SLOTS_KEY.to_string(), helix.mutation_step_slots);
   return (
     helix.seed,
-    helix.drone_gen_cooldown,
-    helix.multiplier_energy_start,
-    helix.multiplier_points,
-    helix.block_bump_cost,
-    helix.multiplier_energy_pickup,
+    genes,
     slots_to_short_string(helix.slots),
+    turn_rules_to_string(&helix.turn_rules),
+    drone_genes_to_string(&helix.drone_genes),
   );
 }
 
 pub fn helix_deserialize(serialized_helix: &SerializedHelix) -> Helix {
   let (
     seed,
-    drone_gen_cooldown,
-    multiplier_energy_start,
-    multiplier_points,
-    block_bump_cost,
-    multiplier_energy_pickup,
+    genes,
     slots,
+    turn_rules,
+    drone_genes,
   ) = serialized_helix.to_owned();
 
-  return Helix {
+  // Genes that are not serialized get their default value. The slots, turn rules, and drone genes
+  // were checked by parse_serialized_helix, or come from helix_serialize.
+  let mut helix = Helix {
     seed,
-    drone_gen_cooldown,
-    multiplier_energy_start,
-    multiplier_points,
-    block_bump_cost,
-    multiplier_energy_pickup,
     slots: short_string_to_slots(slots),
+    turn_rules: parse_turn_rules(&turn_rules).expect("serialized turn rules should be valid"),
+    drone_genes: parse_drone_genes(&drone_genes).expect("serialized drone genes should be valid"),
+    ..create_null_helix()
   };
+  for spec in GENE_SCHEMA.iter() {
+    if let Some(value) = genes.get(spec.label) {
+      set_gene(&mut helix, spec.gene, *value);
+    }
+  }
+  if let Some(step) = genes.get(MUTATION_STEP_GENES_KEY) {
+    helix.mutation_step_genes = step.clamp(MIN_MUTATION_RATE, MAX_MUTATION_RATE);
+  }
+  if let Some(step) = genes.get(MUTATION_STEP_SLOTS_KEY) {
+    helix.mutation_step_slots = step.clamp(MIN_MUTATION_RATE, MAX_MUTATION_RATE);
+  }
+  return helix;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::SeedableRng;
+  use rand_pcg::Pcg64;
+
+  #[test]
+  fn miner_code_round_trips() {
+    let mut rng: Lcg128Xsl64 = Pcg64::seed_from_u64(210143);
+    let mut helix = create_initial_helix(&mut rng, 210143);
+    helix.drone_genes = parse_drone_genes("X/-g-/R").unwrap();
+    helix.turn_rules = parse_turn_rules("RL/V").unwrap();
+    let json = serde_json::to_string(&helix_serialize(&helix)).unwrap();
+    let back = helix_deserialize(&parse_serialized_helix(&json).unwrap());
+    assert_eq!(helix_serialize(&back), helix_serialize(&helix));
+  }
+
+  #[test]
+  fn older_miner_codes_get_the_default_drone_genes() {
+    let code = r#"[210143,{"drone gen":43.0},"..DDDDDDDDd.h.dd.EEE.JJJEP.EdPhh","RL/V"]"#;
+    let helix = helix_deserialize(&parse_serialized_helix(code).unwrap());
+    assert_eq!(drone_genes_to_string(&helix.drone_genes), drone_genes_to_string(&create_default_drone_genes()));
+    assert_eq!(helix.drone_gen_cooldown, 43.0);
+
+    let positional = r#"[210143,43.0,129.0,0.0,8.0,0.0,"..DDDDDDDDd.h.dd.EEE.JJJEP.EdPhh"]"#;
+    assert!(parse_serialized_helix(positional).is_ok());
+  }
+
+  #[test]
+  fn invalid_miner_codes_are_rejected() {
+    let slots = "..DDDDDDDDd.h.dd.EEE.JJJEP.EdPhh";
+    assert!(parse_serialized_helix(&format!(r#"[1,{{}},"{}","RL/V","R/fge/S"]"#, slots)).is_ok());
+    assert!(parse_serialized_helix(&format!(r#"[1,{{}},"{}","RL/V","Q/fge/S"]"#, slots)).is_err());
+    assert!(parse_serialized_helix(&format!(r#"[1,{{}},"{}","RL/V","R/gfe/S"]"#, slots)).is_err());
+    assert!(parse_serialized_helix(&format!(r#"[1,{{}},"{}","RL","R/fge/S"]"#, slots)).is_err());
+    assert!(parse_serialized_helix(&format!(r#"[1,{{}},"{}Z","RL/V","R/fge/S"]"#, &slots[1..])).is_err());
+    assert!(parse_serialized_helix(r#"[1,{},"..","RL/V","R/fge/S"]"#).is_err());
+  }
 }
@@ -0,0 +1,100 @@
+use super::app_state::*;
+use super::biome::*;
+use super::inventory::*;
+use super::miner::*;
+use super::options::*;
+use super::tile::*;
+use super::utils::*;
+use super::world::*;
+
+// Food and hunger. The miner has a hunger meter (meta.satiety) that drains every tick at
+// options.hunger_rate, which is zero (off) unless set through --hunger-rate. Hunger is not lethal
+// but a hungry miner gets bored faster and its slots cool down slower, increasingly so as the
+// meter approaches empty.
+//
+// Food grows as crops on soil. The tile_value of a soil cell is its crop, which grows every
+// CROP_GROWTH_INTERVAL ticks up to CROP_MAX_VALUE. A crop is ripe from CROP_RIPE_VALUE on and
+// harvesting it (by the miner walking over it or by a drone of the drone launcher) yields food
+// and resets the crop. The miner eats from its inventory when it gets hungry. Rations count too.
+//
+// The world tracks its soil cells in world.soil_cells so the crops can grow without scanning the
+// whole world (see set_cell_tile_at).
+
+pub const SATIETY_MAX: f32 = 100.0;
+// Below this the miner is hungry, and it eats when it has food
+pub const SATIETY_HUNGRY: f32 = 50.0;
+// Satiety restored by eating one food. A ration fills the meter.
+pub const FOOD_SATIETY: f32 = 40.0;
+// At an empty meter the boredom cost is multiplied by one plus this
+pub const HUNGER_BOREDOM_PENALTY: f32 = 1.0;
+// At an empty meter the slots lose this much of their cooldown progress per tick
+pub const HUNGER_COOLDOWN_PENALTY: f32 = 0.5;
+
+pub const CROP_GROWTH_INTERVAL: i32 = 50;
+pub const CROP_RIPE_VALUE: u32 = 3;
+// Also the highest soil color (see tile.rs)
+pub const CROP_MAX_VALUE: u32 = 10;
+
+pub fn get_hunger(biome: &Biome) -> f32 {
+  // Zero while the miner is not hungry, going up to one when the meter is empty
+  return ((SATIETY_HUNGRY - biome.miner.meta.satiety) / SATIETY_HUNGRY).max(0.0);
+}
+
+pub fn get_hunger_boredom_multiplier(biome: &Biome) -> f32 {
+  return 1.0 + get_hunger(biome) * HUNGER_BOREDOM_PENALTY;
+}
+
+pub fn get_hunger_cooldown_penalty(biome: &Biome) -> f32 {
+  return get_hunger(biome) * HUNGER_COOLDOWN_PENALTY;
+}
+
+pub fn tick_hunger(options: &Options, biome: &mut Biome) {
+  let meta = &mut biome.miner.meta;
+  meta.satiety = (meta.satiety - options.hunger_rate).max(0.0);
+  if meta.satiety < SATIETY_HUNGRY && inventory_take(&mut meta.inventory, Resource::Food, 1) > 0 {
+    meta.satiety = (meta.satiety + FOOD_SATIETY).min(SATIETY_MAX);
+    meta.eaten += 1;
+  }
+}
+
+pub fn tick_crops(options: &Options, state: &AppState, world: &mut World) {
+  if state.batch_ticks % CROP_GROWTH_INTERVAL != 0 {
+    return;
+  }
+
+  // Soil may have been turned into something else since it was added
+  let cells: Vec<(i32, i32)> = world.soil_cells.iter().copied().collect();
+  for (x, y) in cells {
+    if !matches!(get_cell_tile_at(options, world, x, y), Tile::Soil) {
+      world.soil_cells.remove(&(x, y));
+      continue;
+    }
+    let value = get_cell_tile_value_at(options, world, x, y);
+    if value < CROP_MAX_VALUE {
+      set_cell_tile_value_at(options, world, x, y, value + 1);
+    }
+  }
+}
+
+pub fn harvest_crop(options: &Options, world: &mut World, x: i32, y: i32) -> bool {
+  // Returns true when there was a ripe crop at x,y. The crop starts over.
+  if !matches!(get_cell_tile_at(options, world, x, y), Tile::Soil) || get_cell_tile_value_at(options, world, x, y) < CROP_RIPE_VALUE {
+    return false;
+  }
+  set_cell_tile_value_at(options, world, x, y, 0);
+  return true;
+}
+
+pub fn miner_harvest_crop(options: &Options, biome: &mut Biome, x: i32, y: i32) -> bool {
+  // The miner picks the crop of the cell it steps into. Returns true if it did.
+  if !harvest_crop(options, &mut biome.world, x, y) {
+    return false;
+  }
+  acquire_item(biome, Resource::Food, None, x, y);
+  return true;
+}
+
+pub fn ui_hunger(biome: &Biome) -> String {
+  let meta = &biome.miner.meta;
+  return format!("Satiety: {} Eaten: {}{}", progress_bar(10, meta.satiety, SATIETY_MAX, true), meta.eaten, if get_hunger(biome) > 0.0 { " (hungry)" } else { "" });
+}
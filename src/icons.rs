@@ -1,3 +1,4 @@
+pub const ICON_BAT: char = '🦇';
 pub const ICON_BIRD: char = '🦅'; // 🕊️ 🦅 🐦 🦉
 pub const ICON_BLOCK_100: char = '█';
 pub const ICON_BLOCK_75: char = '▓';
@@ -9,6 +10,8 @@ pub const ICON_BORDER_TR: char = '╗';
 pub const ICON_BORDER_BR: char = '╝';
 pub const ICON_BORDER_V: char = '║';
 pub const ICON_BORDER_H: char = '═';
+pub const ICON_BUG: char = '🐛'; // 🐛 🪱
+pub const ICON_CAMEL: char = '🐪';
 pub const ICON_BRAILLE_1: char = '⠁'; // https://en.wikipedia.org/wiki/Braille_Patterns
 pub const ICON_BRAILLE_2: char = '⠃';
 pub const ICON_BRAILLE_3: char = '⠇';
@@ -20,10 +23,12 @@ pub const ICON_BRAILLE_8: char = '⣿';
 pub const ICON_CIRCLE_LEFT: char = '⮈';
 pub const ICON_DIAMOND: char = '💎';
 pub const ICON_DOUBLE_DOWN: char = '⮇';
+pub const ICON_DYNAMITE: char = '🧨';
 pub const ICON_END: char = '🔚';
 pub const ICON_ENERGY: char = '🔋';
 pub const ICON_EYES_ONLY: char = '😶';
 pub const ICON_FOOD: char = '🍽'; // 🍇 🍈 🍉 🍊 🍋 🍌 🍍 🥭 🍎 🍏 🍐 🍑 🍒 🍓 🫐 🥝 🍅 🫒 🥥 🥑 🍆 🥔 🥕 🌽 🌶 🫑 🥒 🥬 🥦 🧄 🧅 🍄 🥜 🌰 🍞 🥐 🥖 🫓 🥨 🥯 🥞 🧇 🧀 🍖 🍗 🥩 🥓 🍔 🍟 🍕 🌭 🥪 🌮 🌯 🫔 🥙 🧆 🥚 🍳 🥘 🍲 🫕 🥣 🥗 🍿 🧈 🧂 🥫 🍱 🍘 🍙 🍚 🍛 🍜 🍝 🍠 🍢 🍣 🍤 🍥 🥮 🍡 🥟 🥠 🥡 🦪 🍦 🍧 🍨 🍩 🍪 🎂 🍰 🧁 🥧 🍫 🍬 🍭 🍮 🍯 🍼 🥛 ☕ 🫖 🍵 🍶 🍾 🍷 🍸 🍹 🍺 🍻 🥂 🥃 🥤 🧋 🧃 🧉 🧊 🥢 🍽 🍴 🥄 // https://emojipedia.org/food-drink/
+pub const ICON_FLARE: char = '🎆'; // 🎆 🎇 🔦
 pub const ICON_FOUNTAIN: char= '⛲';
 pub const ICON_HEAVY_UP: char = '🡅';
 pub const ICON_HEAVY_RIGHT: char = '🡆';
@@ -31,6 +36,7 @@ pub const ICON_HEAVY_DOWN: char = '🡇';
 pub const ICON_HEAVY_LEFT: char = '🡄';
 pub const ICON_NO_ENTRY: char = '🚫';
 pub const ICON_PUSH_PIN: char = '📍'; // 📍 📌
+pub const ICON_RATION: char = '🥫';
 pub const ICON_SNAKE: char = '🐍';
 pub const ICON_TRIANGLE_DOWN: char = '⮛';
 pub const ICON_TRIANGLE_LEFT: char = '⮘';
@@ -62,6 +68,10 @@ pub const ICON_WINDRONE_POWER: char = ICON_TORNADO;
 
 pub const ICON_PUSH: char = ICON_WORLD_MAP;
 
+pub const ICON_CREATURE: char = ICON_BAT;
+pub const ICON_TRADER: char = ICON_CAMEL;
+pub const ICON_ROCK_EATER: char = ICON_BUG;
+
 pub const ICON_DRONE_UP: char = ICON_TRIPLE_UP;
 pub const ICON_DRONE_RIGHT: char = ICON_TRIPLE_RIGHT;
 pub const ICON_DRONE_DOWN: char = ICON_TRIPLE_DOWN;
@@ -2,6 +2,10 @@ use super::helix::*;
 use super::options::*;
 use super::app_state::*;
 use super::inventory::*;
+use super::snapshot::*;
+use super::recipe::*;
+use super::progression::*;
+use super::castle_ca::*;
 
 use std::fs;
 use std::path::Path;
@@ -29,7 +33,14 @@ pub fn initialize(options: &mut Options) -> (AppState, Helix, HashMap<u64, (u64,
     println!("Loading from file... `{}`", seed_hmap_file);
     let s = fs::read_to_string(&seed_hmap_path).expect("Unable to read file");
     println!("Parsing {} bytes into hash map", s.len());
-    hmap = serde_json::from_str(&s).unwrap();
+    // Older files store the helices in an older format, so convert them one by one. Broken ones are skipped.
+    let raw: HashMap<u64, (u64, usize, serde_json::Value)> = serde_json::from_str(&s).unwrap();
+    for (key, (points, unique_steps, value)) in raw.into_iter() {
+      match serialized_helix_from_value(value) {
+        Ok(serialized_helix) => { hmap.insert(key, (points, unique_steps, serialized_helix)); }
+        Err(msg) => println!("Skipping miner {} in `{}`: {}", key, seed_hmap_file, msg),
+      }
+    }
 
     let len = hmap.len();
 
@@ -44,6 +55,17 @@ pub fn initialize(options: &mut Options) -> (AppState, Helix, HashMap<u64, (u64,
     println!("Loaded {} miners from disk. Most points: {}. Best helix: {}", len, best_points_from_file, best_helix_from_file);
   }
 
+  let snapshot_to_load =
+    if options.snapshot_file.len() != 0 {
+      let snapshot = read_biome_snapshot(&options.snapshot_file);
+      // The snapshot dictates the world so this must happen before the seed is used
+      apply_snapshot_world_gen(options, &snapshot);
+      println!("Loaded biome snapshot from `{}`, world seed is now {}", options.snapshot_file, options.seed);
+      Some(snapshot)
+    } else {
+      None
+    };
+
   let mut seed_rng = rand::thread_rng();
   let seed_range = Uniform::from(0..1000000);
 
@@ -64,18 +86,20 @@ pub fn initialize(options: &mut Options) -> (AppState, Helix, HashMap<u64, (u64,
 
   println!("Miner seed: {}", options.seed);
   let new_inv = create_inventory();
+  // The cli already rejects a bad miner code but the web options are not checked before this
+  let initial_miner = if options.initial_miner_code.len() != 0 {
+    match parse_serialized_helix(&options.initial_miner_code) {
+      Ok(serialized) => Some(helix_deserialize(&serialized)),
+      Err(msg) => {
+        println!("Ignoring the initial miner code: {}", msg);
+        None
+      }
+    }
+  } else {
+    None
+  };
   let best_miner: (Helix, u64, usize, usize, Inventory) =
-    if options.initial_miner_code.len() != 0 {
-      let x: (
-        u64, // seed
-        f32, // drone_gen_cooldown
-        f32, // multiplier_energy_start
-        f32, // multiplier_points
-        f32, // block_bump_cost
-        f32, // multiplier_energy_pickup
-        String // slots: [SlotKind; 32]
-      ) = serde_json::from_str(&options.initial_miner_code).unwrap();
-      let y = helix_deserialize(&x);
+    if let Some(y) = initial_miner {
       (
         y,
         0,
@@ -104,6 +128,17 @@ pub fn initialize(options: &mut Options) -> (AppState, Helix, HashMap<u64, (u64,
   let next_root_helix = best_miner.0;
   let mut state = create_app_state(options, best_miner, trail_lens, instance_rng_seeded, instance_rng_unseeded);
   state.load_best_as_miner_zero = load_best_as_miner_zero;
+  state.snapshot_to_load = snapshot_to_load;
+  if options.recipes_file.len() != 0 {
+    state.recipes = load_recipes(&options.recipes_file);
+    println!("Loaded {} recipes from `{}`", state.recipes.len(), options.recipes_file);
+  }
+  if options.progression_file.len() != 0 {
+    state.progression = load_progression(&options.progression_file);
+    println!("Loaded {} milestones from `{}`", state.progression.len(), options.progression_file);
+  }
+  // Web options skip the cli validation so this is where a bad rule is reported
+  state.castle_ca_rule = load_castle_ca_rule(options);
 
   return (state, next_root_helix, hmap);
 }
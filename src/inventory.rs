@@ -20,103 +20,138 @@
 // Is fossils something to toy with?
 
 
+use std::collections::BTreeMap;
+
 use super::icons::*;
 use super::color::*;
 use super::options::*;
+use serde_derive::{Serialize, Deserialize};
+
+// Everything a miner can collect. To add a resource, add it here and add an entry to RESOURCES.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Resource {
+  StoneWhite = 0,
+  StoneGreen = 1,
+  StoneBlue = 2,
+  StoneYellow = 3,
+  DiamondWhite = 4,
+  DiamondGreen = 5,
+  DiamondBlue = 6,
+  DiamondYellow = 7,
+  Sand = 8,
+  Energy = 9,
+  Wind = 10,
+  Wood = 11,
+  Water = 12,
+  Food = 13,
+  Dynamite = 14,
+  Flare = 15,
+  Ration = 16,
+  // Make sure to update RESOURCE_COUNT and RESOURCES!
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResourceCategory {
+  Stone,
+  Diamond,
+  Material,
+  Consumable,
+}
+
+pub struct ResourceInfo {
+  pub resource: Resource,
+  pub icon: char,
+  pub color: &'static str,
+  // How many points is one of these worth? See get_points
+  pub points: u64,
+  pub category: ResourceCategory,
+}
+
+pub const RESOURCE_COUNT: usize = 17;
+
+// Must be ordered by the Resource value. Within a category, cheapest quality first.
+// This is also the order in which the inventory is shown.
+pub static RESOURCES: [ResourceInfo; RESOURCE_COUNT] = [
+  ResourceInfo { resource: Resource::StoneWhite, icon: ICON_STONE, color: COLOR_LEVEL_1, points: 1, category: ResourceCategory::Stone },
+  ResourceInfo { resource: Resource::StoneGreen, icon: ICON_STONE, color: COLOR_LEVEL_2, points: 10, category: ResourceCategory::Stone },
+  ResourceInfo { resource: Resource::StoneBlue, icon: ICON_STONE, color: COLOR_LEVEL_3, points: 5, category: ResourceCategory::Stone },
+  ResourceInfo { resource: Resource::StoneYellow, icon: ICON_STONE, color: COLOR_LEVEL_4, points: 50, category: ResourceCategory::Stone },
+  ResourceInfo { resource: Resource::DiamondWhite, icon: ICON_DIAMOND, color: COLOR_LEVEL_1, points: 100, category: ResourceCategory::Diamond },
+  ResourceInfo { resource: Resource::DiamondGreen, icon: ICON_DIAMOND, color: COLOR_LEVEL_2, points: 500, category: ResourceCategory::Diamond },
+  ResourceInfo { resource: Resource::DiamondBlue, icon: ICON_DIAMOND, color: COLOR_LEVEL_3, points: 250, category: ResourceCategory::Diamond },
+  ResourceInfo { resource: Resource::DiamondYellow, icon: ICON_DIAMOND, color: COLOR_LEVEL_4, points: 1000, category: ResourceCategory::Diamond },
+  ResourceInfo { resource: Resource::Sand, icon: ICON_SAND, color: COLOR_SAND, points: 0, category: ResourceCategory::Material },
+  ResourceInfo { resource: Resource::Energy, icon: ICON_ENERGY, color: COLOR_ENERGY, points: 0, category: ResourceCategory::Consumable },
+  ResourceInfo { resource: Resource::Wind, icon: ICON_WINDRONE_POWER, color: COLOR_WIND, points: 0, category: ResourceCategory::Material },
+  ResourceInfo { resource: Resource::Wood, icon: ICON_WOOD, color: COLOR_WOOD, points: 0, category: ResourceCategory::Material },
+  ResourceInfo { resource: Resource::Water, icon: ICON_WATER, color: COLOR_WATER, points: 0, category: ResourceCategory::Material },
+  ResourceInfo { resource: Resource::Food, icon: ICON_FOOD, color: COLOR_FOOD, points: 0, category: ResourceCategory::Consumable },
+  ResourceInfo { resource: Resource::Dynamite, icon: ICON_DYNAMITE, color: COLOR_DYNAMITE, points: 0, category: ResourceCategory::Consumable },
+  ResourceInfo { resource: Resource::Flare, icon: ICON_FLARE, color: COLOR_FLARE, points: 0, category: ResourceCategory::Consumable },
+  ResourceInfo { resource: Resource::Ration, icon: ICON_RATION, color: COLOR_RATION, points: 0, category: ResourceCategory::Consumable },
+];
+
+pub fn get_resource_info(resource: Resource) -> &'static ResourceInfo {
+  let info = &RESOURCES[resource as usize];
+  assert_eq!(info.resource, resource, "RESOURCES should be ordered by Resource");
+  return info;
+}
+
+pub fn resource_of_quality(category: ResourceCategory, quality: u32) -> Resource {
+  // Returns the nth resource of given category, like the green stone for (Stone, 1)
+  return RESOURCES.iter().filter(|info| info.category == category).nth(quality as usize).expect("quality should exist in category").resource;
+}
+
+pub fn quality_of_resource(resource: Resource) -> u32 {
+  // Inverse of resource_of_quality, like 1 for the green stone
+  let category = get_resource_info(resource).category;
+  return RESOURCES.iter().filter(|info| info.category == category).position(|info| info.resource == resource).expect("resource should exist in its category") as u32;
+}
+
+pub fn max_quality(category: ResourceCategory) -> u32 {
+  return RESOURCES.iter().filter(|info| info.category == category).count() as u32 - 1;
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Inventory {
-  pub stone_white: u32,
-  pub stone_blue: u32,
-  pub stone_green: u32,
-  pub stone_yellow: u32,
-
-  pub diamond_white: u32,
-  pub diamond_blue: u32,
-  pub diamond_green: u32,
-  pub diamond_yellow: u32,
-
-  pub energy: u32,
-  pub water: u32,
-  pub wind: u32,
-  pub wood: u32,
-  pub sand: u32,
-  pub food: u32,
+  // Resources that were never collected are absent. Ordered, so iteration is deterministic.
+  pub counts: BTreeMap<Resource, u32>,
 }
 
 pub fn create_inventory() -> Inventory {
   return Inventory {
-    stone_white: 0,
-    stone_green: 0,
-    stone_blue: 0,
-    stone_yellow: 0,
-
-    diamond_white: 0,
-    diamond_green: 0,
-    diamond_blue: 0,
-    diamond_yellow: 0,
-
-    energy: 0,
-    water: 0,
-    wind: 0,
-    wood: 0,
-    sand: 0,
-    food: 0,
+    counts: BTreeMap::new(),
   };
 }
 
 pub fn clone_inventory(inventory: &Inventory) -> Inventory {
-  return Inventory {
-    stone_white: inventory.stone_white,
-    stone_green: inventory.stone_green,
-    stone_blue: inventory.stone_blue,
-    stone_yellow: inventory.stone_yellow,
-
-    diamond_white: inventory.diamond_white,
-    diamond_green: inventory.diamond_green,
-    diamond_blue: inventory.diamond_blue,
-    diamond_yellow: inventory.diamond_yellow,
-
-    energy: inventory.energy,
-    water: inventory.wind,
-    wind: inventory.wind,
-    wood: inventory.wood,
-    sand: inventory.sand,
-    food: inventory.food,
-  };
+  return inventory.clone();
+}
+
+pub fn inventory_get(inventory: &Inventory, resource: Resource) -> u32 {
+  return *inventory.counts.get(&resource).unwrap_or(&0);
+}
+
+pub fn inventory_add(inventory: &mut Inventory, resource: Resource, amount: u32) {
+  *inventory.counts.entry(resource).or_insert(0) += amount;
+}
+
+pub fn inventory_take(inventory: &mut Inventory, resource: Resource, amount: u32) -> u32 {
+  // Take up to amount of resource. Returns how many were actually taken.
+  let count = inventory.counts.entry(resource).or_insert(0);
+  let taken = (*count).min(amount);
+  *count -= taken;
+  return taken;
 }
 
 pub fn ui_inventory(inventory: &Inventory, options: &Options) -> String {
-  return format!(
-    "{}: {: <5} {}: {: <5} {}: {: <5} {}: {: <5} {}: {: <5} {}: {: <5} {}: {: <5} {}: {: <5}  {}: {: <5}   {}: {: <5}  {} : {: <5}  {}: {: <5}   {}: {: <5} {} : {: <5} {: <10}",
-    add_fg_color_with_reset(&ICON_STONE.to_string(), COLOR_LEVEL_1, options), inventory.stone_white,
-    add_fg_color_with_reset(&ICON_STONE.to_string(), COLOR_LEVEL_2, options), inventory.stone_green,
-    add_fg_color_with_reset(&ICON_STONE.to_string(), COLOR_LEVEL_3, options), inventory.stone_blue,
-    add_fg_color_with_reset(&ICON_STONE.to_string(), COLOR_LEVEL_4, options), inventory.stone_yellow,
-
-    add_fg_color_with_reset(&ICON_DIAMOND.to_string(), COLOR_LEVEL_1, options), inventory.diamond_white,
-    add_fg_color_with_reset(&ICON_DIAMOND.to_string(), COLOR_LEVEL_2, options), inventory.diamond_green,
-    add_fg_color_with_reset(&ICON_DIAMOND.to_string(), COLOR_LEVEL_3, options), inventory.diamond_blue,
-    add_fg_color_with_reset(&ICON_DIAMOND.to_string(), COLOR_LEVEL_4, options), inventory.diamond_yellow,
-
-    add_fg_color_with_reset(&ICON_SAND.to_string(), COLOR_SAND, options), inventory.sand,
-    add_fg_color_with_reset(&ICON_ENERGY.to_string(), COLOR_ENERGY, options), inventory.energy,
-    add_fg_color_with_reset(&ICON_WINDRONE_POWER.to_string(), COLOR_WIND, options), inventory.wind,
-    add_fg_color_with_reset(&ICON_WOOD.to_string(), COLOR_WOOD, options), inventory.wood,
-    add_fg_color_with_reset(&ICON_WATER.to_string(), COLOR_WATER, options), inventory.water,
-    add_fg_color_with_reset(&ICON_FOOD.to_string(), COLOR_FOOD, options), inventory.food,
-    ' '
-  );
+  let mut out = "".to_string();
+  for info in RESOURCES.iter() {
+    out.push_str(&format!("{}: {: <5} ", add_fg_color_with_reset(&info.icon.to_string(), info.color, options), inventory_get(inventory, info.resource)));
+  }
+  return format!("{}{: <10}", out, ' ');
 }
 
 pub fn get_points(inventory: &Inventory) -> u64 {
-  return
-    inventory.stone_white as u64 +
-    (inventory.stone_blue as u64 * 5u64) +
-    (inventory.stone_yellow as u64 * 10u64) +
-    (inventory.stone_yellow as u64 * 50u64) +
-    (inventory.diamond_white as u64 * 100u64) +
-    (inventory.diamond_blue as u64 * 250u64) +
-    (inventory.diamond_green as u64 * 500u64) +
-    (inventory.diamond_yellow as u64 * 1000u64);
+  return RESOURCES.iter().map(|info| inventory_get(inventory, info.resource) as u64 * info.points).sum();
 }
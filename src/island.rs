@@ -0,0 +1,213 @@
+use rand::prelude::*;
+use rand_pcg::{Lcg128Xsl64, Pcg64};
+
+use super::app_state::*;
+use super::biome::*;
+use super::fitness::*;
+use super::helix::*;
+use super::miner::*;
+use super::mutation::*;
+use super::options::*;
+use super::world::*;
+
+#[cfg(target_arch = "wasm32")]
+use serde_derive::{Serialize, Deserialize};
+
+// Island model. With options.islands > 1 the GA runs that many sub-populations side by side. Every
+// batch contains options.batch_size miners per island, each mutated from the root helix of its own
+// island with its own rng and (optionally) its own base mutation rates. The mutation schedule
+// applies per island (see mutation.rs). The islands evolve on their own,
+// except that every options.island_migration_interval batches the best options.island_migrants
+// helices of each island migrate to its neighbours (see IslandTopology). Migrants take the first
+// biomes of the next batch of the island they arrive at, unmutated, so they get a fair chance.
+
+#[cfg_attr(target_arch = "wasm32", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IslandTopology {
+  // Island n sends its migrants to island n+1, the last one to the first one
+  Ring,
+  // Every island sends its migrants to every other island
+  Full,
+}
+
+pub struct Island {
+  pub root_helix: Helix,
+  pub rng: Lcg128Xsl64,
+  pub mutation_rate_genes: f32,
+  pub mutation_rate_slots: f32,
+  // Rates of the OneFifth mutation schedule for this island
+  pub adapted_mutation_rates: (f32, f32),
+  // Fitness of the root helix, which the miners of the next batch try to beat
+  pub parent_fitness: u64,
+  // Best helix of this island so far and its fitness
+  pub best: (Helix, u64),
+  // Batch (state.stats_total_batches) in which the best last improved
+  pub last_improvement: i32,
+  // Average fraction of differing slots between two miners of the last batch of this island
+  pub diversity: f64,
+  // Helices that migrated here, to be put in the next batch
+  pub immigrants: Vec<Helix>,
+}
+
+pub fn create_islands(options: &Options, state: &mut AppState, root_helix: &Helix) -> Vec<Island> {
+  // The first island starts from the given root, the others from a fresh random helix
+  let mut islands: Vec<Island> = vec!();
+  for n in 0..options.islands {
+    let mut rng: Lcg128Xsl64 = Pcg64::seed_from_u64(state.instance_rng_seeded.next_u64());
+    let helix = if n == 0 { *root_helix } else { create_initial_helix(&mut rng, options.seed) };
+    let (mutation_rate_genes, mutation_rate_slots) =
+      if n < options.island_mutation_rates.len() { options.island_mutation_rates[n] }
+      else { (options.mutation_rate_genes, options.mutation_rate_slots) };
+    islands.push(Island {
+      root_helix: helix,
+      rng,
+      mutation_rate_genes,
+      mutation_rate_slots,
+      adapted_mutation_rates: (mutation_rate_genes, mutation_rate_slots),
+      parent_fitness: 0,
+      best: (helix, 0),
+      last_improvement: state.stats_total_batches,
+      diversity: 0.0,
+      immigrants: vec!(),
+    });
+  }
+  return islands;
+}
+
+pub fn get_island_mutation_rates(options: &Options, state: &AppState, island: &Island) -> (f32, f32) {
+  let batches = (state.stats_total_batches - island.last_improvement).max(0) as u32;
+  return get_scheduled_mutation_rates(options, (island.mutation_rate_genes, island.mutation_rate_slots), island.adapted_mutation_rates, batches, &island.root_helix);
+}
+
+fn create_island_helix(options: &Options, state: &mut AppState, island_index: usize) -> Helix {
+  // Returns the helix for the next miner of given island
+  let (rate_genes, rate_slots) = get_island_mutation_rates(options, state, &state.islands[island_index]);
+  let island = &mut state.islands[island_index];
+  if island.immigrants.len() > 0 {
+    return island.immigrants.remove(0);
+  }
+  return mutate_helix_at_rates(&mut island.rng, &island.root_helix, options, rate_genes, rate_slots);
+}
+
+pub fn generate_island_biomes(options: &Options, state: &mut AppState, curr_root_helix: &Helix) -> Vec<Biome> {
+  // Generate batch_size biomes for each island, in island order
+  if state.islands.len() == 0 {
+    state.islands = create_islands(options, state, curr_root_helix);
+  }
+  if state.load_best_as_miner_zero {
+    state.load_best_as_miner_zero = false;
+    println!("loading best miner into island 0... {}", curr_root_helix);
+    state.islands[0].immigrants.insert(0, *curr_root_helix);
+  }
+
+  let mut biomes: Vec<Biome> = vec!();
+  for n in 0..state.islands.len() {
+    for _ in 0..options.batch_size {
+      let helix = create_island_helix(options, state, n);
+      let biome = Biome {
+        index: biomes.len(),
+        island: n,
+        ticks: 0,
+        world: generate_world(options),
+        miner: create_miner_from_helix(state, &helix),
+        shared_world: false,
+        relative_fitness: None,
+        rival_damage: 0.0,
+      };
+      biomes.push(biome);
+    }
+    // Migrants that did not fit in this batch are dropped
+    state.islands[n].immigrants.clear();
+  }
+  return biomes;
+}
+
+fn get_diversity(biomes: &Vec<&Biome>) -> f64 {
+  let mut pairs = 0.0;
+  let mut differences = 0.0;
+  for a in 0..biomes.len() {
+    for b in a+1..biomes.len() {
+      let slots_a = &biomes[a].miner.helix.slots;
+      let slots_b = &biomes[b].miner.helix.slots;
+      let differing = (0..slots_a.len()).filter(|&i| slots_a[i] as usize != slots_b[i] as usize).count();
+      differences += differing as f64 / slots_a.len() as f64;
+      pairs += 1.0;
+    }
+  }
+  return if pairs > 0.0 { differences / pairs } else { 0.0 };
+}
+
+pub fn post_islands(options: &Options, state: &mut AppState, biomes: &Vec<Biome>) -> Helix {
+  // Update each island with the results of its miners and migrate when it's time. Returns the
+  // best helix of this batch.
+  let mut migrants: Vec<Vec<Helix>> = vec!();
+  let mut batch_best: (Helix, u64) = (biomes[0].miner.helix, 0);
+  for n in 0..state.islands.len() {
+    let mut own: Vec<&Biome> = biomes.iter().filter(|biome| biome.island == n).collect();
+    if own.len() == 0 {
+      migrants.push(vec!());
+      continue;
+    }
+    own.sort_by_key(|biome| std::cmp::Reverse(get_fitness(options, biome)));
+    let winner = (own[0].miner.helix, get_fitness(options, own[0]));
+    if winner.1 > batch_best.1 {
+      batch_best = winner;
+    }
+
+    // Successes are miners that beat the root of their island
+    let parent_fitness = state.islands[n].parent_fitness;
+    let mut successes = 0;
+    for biome in own.iter() {
+      let improved = get_fitness(options, biome) > parent_fitness;
+      if improved { successes += 1; }
+      record_biome_slot_operators(state, biome, improved);
+    }
+
+    let island = &mut state.islands[n];
+    island.diversity = get_diversity(&own);
+    if matches!(options.mutation_schedule, MutationSchedule::OneFifth) {
+      island.adapted_mutation_rates = get_one_fifth_rates(island.adapted_mutation_rates, successes, own.len());
+    }
+    if winner.1 > island.best.1 {
+      island.best = winner;
+      island.last_improvement = state.stats_total_batches;
+    }
+    island.root_helix = if options.mutate_from_best { island.best.0 } else { winner.0 };
+    island.parent_fitness = if options.mutate_from_best { island.best.1 } else { winner.1 };
+    migrants.push(own.iter().take(options.island_migrants).map(|biome| biome.miner.helix).collect());
+  }
+
+  let count = state.islands.len();
+  if count > 1 && state.stats_total_batches % options.island_migration_interval.max(1) as i32 == 0 {
+    for from in 0..count {
+      for to in 0..count {
+        if from == to { continue; }
+        let neighbour = match options.island_topology {
+          IslandTopology::Ring => to == (from + 1) % count,
+          IslandTopology::Full => true,
+        };
+        if neighbour {
+          state.islands[to].immigrants.extend(migrants[from].iter());
+        }
+      }
+    }
+    println!("Migrated the best {} helices of each island ({:?})", options.island_migrants, options.island_topology);
+  }
+
+  for (n, island) in state.islands.iter().enumerate() {
+    let (rate_genes, rate_slots) = get_island_mutation_rates(options, state, island);
+    println!(
+      "- Island {: <2}: best fitness: {: >6}, diversity: {:.2}, last improvement: {: >3} batches ago, mutation rates ({:?}): {:.1}/{:.1}",
+      n,
+      island.best.1,
+      island.diversity,
+      state.stats_total_batches - island.last_improvement,
+      options.mutation_schedule,
+      rate_genes,
+      rate_slots,
+    );
+  }
+  println!("{}", ui_slot_operators(state));
+
+  return batch_best.0;
+}
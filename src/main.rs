@@ -13,12 +13,15 @@ pub mod main_loop;
 pub mod options;
 pub mod drone_san;
 pub mod fountain;
+pub mod actor;
 pub mod slottable;
 pub mod color;
 pub mod slot_windrone;
 pub mod slot_sandrone;
 pub mod drone_win;
 pub mod world;
+pub mod world_diff;
+pub mod snapshot;
 pub mod values;
 pub mod icons;
 pub mod drone_me;
@@ -39,9 +42,29 @@ pub mod slot_drone_launcher;
 pub mod slot_energy_cell;
 pub mod slot_emptiness;
 pub mod slot_jacks_compass;
+pub mod slot_random_start;
+pub mod consumable;
+pub mod hunger;
+pub mod turn_rule;
+pub mod pathfinding;
+pub mod slot_diagonal;
+pub mod slot_bigfoot;
+pub mod slot_basher;
+pub mod slot_radar;
+pub mod slot_grappling_hook;
 pub mod tile;
 pub mod utils;
 pub mod expando;
+pub mod fluid;
+pub mod castle_ca;
+pub mod recipe;
+pub mod progression;
+pub mod fitness;
+pub mod pareto;
+pub mod novelty;
+pub mod island;
+pub mod arena;
+pub mod mutation;
 pub mod bridge;
 pub mod app_state;
 pub mod initialize;
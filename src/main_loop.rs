@@ -4,8 +4,16 @@ use super::helix::*;
 use super::options::*;
 use super::app_state::*;
 use super::inventory::*;
+use super::fitness::*;
+use super::pareto::*;
+use super::novelty::*;
+use super::island::*;
+use super::arena::*;
+use super::hunger::*;
+use super::mutation::*;
 use super::{bridge};
 use super::utils::*;
+use super::snapshot::*;
 
 use std::collections::HashMap;
 
@@ -20,7 +28,8 @@ pub fn pre_ga_loop(options: &mut Options, state: &mut AppState, curr_root_helix:
 
   state.stats_total_batches += 1;
 
-  let biomes: Vec<Biome> = generate_biomes(options, state, curr_root_helix);
+  let mut biomes: Vec<Biome> = generate_biomes(options, state, curr_root_helix);
+  spawn_arena_miners(options, &mut biomes);
 
   // bridge::log("loaded");
 
@@ -28,10 +37,21 @@ pub fn pre_ga_loop(options: &mut Options, state: &mut AppState, curr_root_helix:
   state.batch_ticks = 0; // How many times did we tick the current biomes that are still up?
   state.cost_increase_value = 0.0;
 
+  if let Some(snapshot) = state.snapshot_to_load.take() {
+    // Replace the first biome with the snapshot and start stepping so it can be inspected
+    biomes[0] = restore_biome_snapshot(options, state, snapshot, 0);
+    biomes[0].shared_world = options.arena_size > 1;
+    options.visible_index = 0;
+    options.return_to_move = true;
+  }
+
   return biomes;
 }
 
-pub fn post_ga_loop(options: &mut Options, state: &mut AppState, biomes: Vec<Biome>, curr_root_helix: &mut Helix, hmap: &mut HashMap<u64, (u64, usize, SerializedHelix)>) -> Helix {
+pub fn post_ga_loop(options: &mut Options, state: &mut AppState, mut biomes: Vec<Biome>, curr_root_helix: &mut Helix, hmap: &mut HashMap<u64, (u64, usize, SerializedHelix)>) -> Helix {
+
+  // In competitive mode the miners are judged against their rivals
+  score_arenas(options, &mut biomes);
 
   // if state.load_best_as_miner_zero {
   //   return *state.best_helix_from_file;
@@ -39,12 +59,41 @@ pub fn post_ga_loop(options: &mut Options, state: &mut AppState, biomes: Vec<Bio
 
   let mut next_root_helix = *curr_root_helix;
 
-  if !state.reset {
-    let points = get_points(&biomes[options.visible_index].miner.meta.inventory);
+  if !state.reset && options.pareto {
+    // Judge the batch against its parent before a new parent is selected
+    adapt_mutation_rates(options, state, &biomes);
+    record_slot_operators(options, state, &biomes);
+    println!("{}", ui_slot_operators(state));
+    if update_pareto_front(options, state, &biomes) {
+      println!("\x1b[32;1mPareto front changed!\x1b[0m");
+      state.miner_count_since_last_best = 0;
+    }
+    print!("{}", ui_pareto_front(options, state));
+    next_root_helix = select_pareto_parent(state);
+    check_auto_reset(options, state);
+  } else if !state.reset && options.islands > 1 {
+    let winner = post_islands(options, state, &biomes);
+    let fitness = state.islands.iter().map(|island| island.best.1).max().unwrap_or(0);
+    if fitness > state.best_miner.1 {
+      println!("\x1b[32;1mFound a new best!\x1b[0m: From {} to {}", state.best_miner.1, fitness);
+      state.best_miner.1 = fitness;
+      state.best_miner.0 = state.islands.iter().find(|island| island.best.1 == fitness).unwrap().best.0;
+      state.miner_count_since_last_best = 0;
+    }
+    next_root_helix = winner;
+    check_auto_reset(options, state);
+  } else if !state.reset && options.novelty {
+    adapt_mutation_rates(options, state, &biomes);
+    record_slot_operators(options, state, &biomes);
+    println!("{}", ui_slot_operators(state));
+    next_root_helix = select_by_novelty(options, state, &biomes);
+    check_auto_reset(options, state);
+  } else if !state.reset {
+    let fitness = get_fitness(options, &biomes[options.visible_index]);
     let inv = clone_inventory(&biomes[options.visible_index].miner.meta.inventory);
     let mut winner: (Helix, u64, &World, usize, usize, Inventory) = (
       biomes[options.visible_index].miner.helix,
-      points as u64,
+      fitness,
       &biomes[options.visible_index].world,
       0,
       0,
@@ -54,12 +103,12 @@ pub fn post_ga_loop(options: &mut Options, state: &mut AppState, biomes: Vec<Bio
     // Find best biome
     for m in 1..biomes.len() { // 1 because zero is used as init above
       let biome: &Biome = &biomes[m];
-      let points = get_points(&biome.miner.meta.inventory) as u64;
+      let fitness = get_fitness(options, biome);
       let inv = clone_inventory(&biome.miner.meta.inventory);
-      if points > winner.1 {
+      if fitness > winner.1 {
         winner = (
           biome.miner.helix,
-          points,
+          fitness,
           &biome.world,
           0,
           0,
@@ -71,10 +120,11 @@ pub fn post_ga_loop(options: &mut Options, state: &mut AppState, biomes: Vec<Bio
     if options.visual {
       for m in 0..biomes.len() {
         let biome: &Biome = &biomes[m];
-        let points = get_points(&biome.miner.meta.inventory) as u64;
+        let points = get_points(&biome.miner.meta.inventory);
+        let fitness = get_fitness(options, biome);
         println!(
-          "- Biome {: <2}: Points: {: <6} [{: >4}x{: <4} , {: >4}x{: <4}] :: {}{: <100}",
-          m, points,
+          "- Biome {: <2}: Points: {: <6} Fitness: {: <6} [{: >4}x{: <4} , {: >4}x{: <4}] :: {}{: <100}",
+          m, points, fitness,
           biome.world.min_x, biome.world.min_y, biome.world.max_x, biome.world.max_y,
           biome.miner.helix,
           ' '
@@ -84,14 +134,18 @@ pub fn post_ga_loop(options: &mut Options, state: &mut AppState, biomes: Vec<Bio
 
     let mut he : String = "".to_string();
     helix_to_string(&mut he, &winner.0);
+    let (rate_genes, rate_slots) = get_mutation_rates(options, state, &winner.0);
 
     println!(
-      "Time: {} s, batches: {: <5} bath loops: {: <5} miners: {}, in current seed: {}. Winner/Best points: {: >5} / {: >5}. Winner @ [{}x{} , {}x{}] -> {}{: >50}",
+      "Time: {} s, batches: {: <5} bath loops: {: <5} miners: {}, in current seed: {}. Mutation rates ({:?}): {:.1}/{:.1}. Winner/Best fitness: {: >5} / {: >5}. Winner @ [{}x{} , {}x{}] -> {}{: >50}",
       bridge::date_now() - state.start_time,
       state.stats_total_batches,
       state.batch_ticks,
       state.total_miner_count,
       state.current_miner_count,
+      options.mutation_schedule,
+      rate_genes,
+      rate_slots,
 
       winner.1,
       state.best_miner.1,
@@ -106,7 +160,7 @@ pub fn post_ga_loop(options: &mut Options, state: &mut AppState, biomes: Vec<Bio
 
     if winner.1 > state.best_miner.1 {
       println!("\x1b[32;1mFound a new best!\x1b[0m: From {} to {}. Inventory: {}", state.best_miner.1, winner.1, ui_inventory(&winner.5, options));
-      state.best_miner = (winner.0, winner.1, winner.3, winner.4, winner.5); // helix, points, steps, uniques, inventory
+      state.best_miner = (winner.0, winner.1, winner.3, winner.4, winner.5); // helix, fitness, steps, uniques, inventory
       next_root_helix = winner.0;
       state.best_min_x = winner.2.min_x;
       state.best_min_y = winner.2.min_y;
@@ -118,6 +172,10 @@ pub fn post_ga_loop(options: &mut Options, state: &mut AppState, biomes: Vec<Bio
       // Mutate from last winner regardless of whether it was a new best
       next_root_helix = winner.0;
     }
+    adapt_mutation_rates(options, state, &biomes);
+    record_slot_operators(options, state, &biomes);
+    println!("{}", ui_slot_operators(state));
+    state.parent_fitness = if options.mutate_from_best { state.best_miner.1 } else { winner.1 };
 
     println!(
       "Hash Map has {} nodes with average trail len of {}. Ticks/s: {}",
@@ -126,14 +184,7 @@ pub fn post_ga_loop(options: &mut Options, state: &mut AppState, biomes: Vec<Bio
       state.stats_last_ticks_sec
     );
 
-    if if options.reset_after_noop { state.miner_count_since_last_best } else { state.current_miner_count } > options.reset_rate {
-      if options.reset_after_noop {
-        println!("Auto reset after no new best in {} iterations", state.miner_count_since_last_best);
-      } else {
-        println!("Auto reset after {} iterations, auto resets after {}", options.reset_rate, state.current_miner_count);
-      }
-      state.reset = true;
-    }
+    check_auto_reset(options, state);
   }
 
   if state.reset {
@@ -141,6 +192,14 @@ pub fn post_ga_loop(options: &mut Options, state: &mut AppState, biomes: Vec<Bio
     bridge::log(format!("New miner seed: {}", new_seed).as_str());
     next_root_helix = create_initial_helix(&mut state.instance_rng_seeded, new_seed);
     state.current_miner_count = 0;
+    // Islands are recreated from the new root with the next batch
+    state.islands = vec!();
+    // The old front would dominate the fresh lineage and be selected right back
+    state.pareto_front = vec!();
+    // Novelty is relative to the new lineage, not the behaviours of the previous run
+    state.novelty_archive = vec!();
+    state.adapted_mutation_rates = (options.mutation_rate_genes, options.mutation_rate_slots);
+    state.parent_fitness = 0;
 
     // Do we reset other counters?
 
@@ -154,6 +213,17 @@ pub fn post_ga_loop(options: &mut Options, state: &mut AppState, biomes: Vec<Bio
   return next_root_helix;
 }
 
+fn check_auto_reset(options: &Options, state: &mut AppState) {
+  if if options.reset_after_noop { state.miner_count_since_last_best } else { state.current_miner_count } > options.reset_rate {
+    if options.reset_after_noop {
+      println!("Auto reset after no new best in {} iterations", state.miner_count_since_last_best);
+    } else {
+      println!("Auto reset after {} iterations, auto resets after {}", options.reset_rate, state.current_miner_count);
+    }
+    state.reset = true;
+  }
+}
+
 pub fn go_iteration(options: &mut Options, state: &mut AppState, biomes: &mut Vec<Biome>, hmap: &mut HashMap<u64, (u64, usize, SerializedHelix)>) {
   // This is basically the main game loop
 
@@ -174,9 +244,25 @@ pub fn go_iteration(options: &mut Options, state: &mut AppState, biomes: &mut Ve
   }
 
   // Tick the biomes
-  for m in 0..biomes.len() {
-    let biome = &mut biomes[m];
-    tick_biome(options, state, biome, hmap);
+  if options.arena_size > 1 {
+    tick_arenas(options, state, biomes, hmap);
+  } else {
+    for m in 0..biomes.len() {
+      let biome = &mut biomes[m];
+      tick_biome(options, state, biome, hmap);
+    }
+  }
+
+  // In competitive mode the visible miner plays in the world of its arena host
+  let visible_host = if options.arena_size > 1 { get_arena_host(options, options.visible_index) } else { options.visible_index };
+
+  #[cfg(not(target_arch = "wasm32"))]
+  if state.save_snapshot {
+    state.save_snapshot = false;
+    swap_arena_world(biomes, visible_host, options.visible_index);
+    let f = save_biome_snapshot(options, state, &biomes[options.visible_index]);
+    swap_arena_world(biomes, visible_host, options.visible_index);
+    bridge::log(format!("Stored snapshot of biome {} to `{}`", options.visible_index, f).as_str());
   }
 
   // Stop drawing the world when the main miner is out of energy. Speed things up visually.
@@ -187,7 +273,7 @@ pub fn go_iteration(options: &mut Options, state: &mut AppState, biomes: &mut Ve
       options.frames_now = 0;
 
       let table_str: String = serialize_world(
-        &biomes[options.visible_index].world,
+        &biomes[visible_host].world,
         &biomes,
         options,
         state,
@@ -256,10 +342,15 @@ pub fn go_iteration(options: &mut Options, state: &mut AppState, biomes: &mut Ve
   for m in 0..biomes.len() {
     let biome: &mut Biome = &mut biomes[m];
     if biome.miner.movable.now_energy > 0.0 {
+      // On the other hand, a hungry miner cools down slower (see hunger.rs)
+      let hunger_penalty = get_hunger_cooldown_penalty(biome);
       for slot in biome.miner.slots.iter_mut() {
         if biome.miner.meta.prev_move_bumped {
           slot.cur_cooldown *= 1.0 + (biome.miner.helix.block_bump_cost / 50000.0);
         }
+        if slot.cur_cooldown < slot.max_cooldown {
+          slot.cur_cooldown = (slot.cur_cooldown - hunger_penalty).max(0.0);
+        }
       }
     }
   }
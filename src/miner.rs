@@ -6,29 +6,29 @@ use super::cell::*;
 use super::drone_san::*;
 use super::options::*;
 use super::biome::*;
-use super::slot_windrone::*;
-use super::slot_sandrone::*;
 use super::slot_emptiness::*;
 use super::world::*;
-use super::slot_drone_launcher::*;
-use super::slot_magnet::*;
-use super::slot_energy_cell::*;
+use super::fluid::*;
 use super::values::*;
 use super::helix::*;
 use super::movable::*;
-use super::slot_hammer::*;
-use super::slot_drill::*;
-use super::slot_purity_scanner::*;
-use super::slot_broken_gps::*;
-use super::slot_jacks_compass::*;
 use super::inventory::*;
 use super::drone_me::*;
 use super::drone_win::*;
 use super::tile::*;
 use super::pickup::*;
+use super::recipe::*;
+use super::consumable::*;
+use super::turn_rule::*;
+use super::arena::*;
+use super::actor::*;
+use super::hunger::*;
+use super::progression::*;
+use serde_derive::{Serialize, Deserialize};
 
 pub type MinerSlots = Vec<Slottable>;
 
+#[derive(Serialize, Deserialize)]
 pub struct Miner {
   // The genes that generated this miner
   pub helix: Helix,
@@ -50,7 +50,7 @@ pub struct Miner {
 
 // In order ...
 #[allow(non_camel_case_types)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum Phase {
   // Step 0: Start of the game. Building a windrone.
   Start_0,
@@ -77,7 +77,7 @@ pub enum Phase {
  * Since we want to pass around drones and the miner to a function generically, but
  * always the miner too to update points, we have to to separate it into its own object.
  */
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MinerMeta {
   // A miner may not exceed its initial energy
   pub max_energy: f32,
@@ -87,7 +87,9 @@ pub struct MinerMeta {
 
   // How many points has the miner accrued so far?
   // pub points: i32,
-  pub points_last_move: i32, // How many points has the miner gathered last time it moved? Does not include points from drones (or whatever else).
+  // Everything the miner and its drones picked up during this biome tick, after the slots had their
+  // say (see SlotBehavior::on_pickup). Cleared at the start of every biome tick.
+  pub acquired: Vec<AcquiredItem>,
 
   // Tally of number of slots per kind
   pub kind_counts: Vec<i32>,
@@ -110,55 +112,86 @@ pub struct MinerMeta {
   // Gene: How effective are pickups?
   pub multiplier_energy_pickup: i32,
 
+  // Gene: Percent chance of an extra stone or gem per pickup (see add_scored_pickup)
+  pub multiplier_points: f32,
+  // Fraction of an extra stone or gem that was earned but not yet received
+  pub points_carry: f32,
+  // Energy drained per tick to pay for the points and energy pickup genes (see GENE_UPKEEP)
+  pub gene_upkeep: f32,
+
   // Gene: How effective are items (slottables)?
   //  multiplier_cooldown: i32,
 
+  // Genes: trigger thresholds of the consumables (see consumable.rs)
+  pub consumable_ration_energy: f32,
+  pub consumable_dynamite_boredom: f32,
+  pub consumable_flare_boredom: f32,
+  // How many of each consumable were used, in CONSUMABLES order
+  pub consumed: [u32; CONSUMABLE_COUNT],
+  // While positive, a flare is lit. Counts down every tick.
+  pub flare_ticks: u32,
+
+  // Hunger meter, SATIETY_MAX when fed, drains every tick (see hunger.rs)
+  pub satiety: f32,
+  // How many food the miner ate
+  pub eaten: u32,
+
   // phase
   pub phase: Phase,
+  // The phase the miner was in when it ran out of energy. Only valid in OutOfEnergy_7.
+  pub final_phase: Phase,
   pub dying_since: u32,
+  // Every phase the miner entered, with the tick it entered it at (see set_miner_phase)
+  pub phase_history: Vec<(Phase, u32)>,
+  // The milestones the miner reached, with the tick it reached them at (see progression.rs)
+  pub milestones: Vec<(String, u32)>,
+  // What the sandrone did to the castle, with the tick it did it at (see raise_castle_event)
+  pub castle_events: Vec<(CastleEvent, u32)>,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AcquiredItem {
+  pub resource: Resource,
+  // Index of the drone that picked it up, or None when the miner picked it up
+  pub drone: Option<usize>,
+  pub x: i32,
+  pub y: i32,
+}
 
-fn create_slot(kind: SlotKind, i: usize, nth: i32, helix: &Helix, state: &mut AppState) -> Slottable {
-  match kind {
-    SlotKind::BrokenGps => {
-      return create_slot_broken_gps(i, nth, 100.0 * 2.0_f32.powf((nth + 1) as f32));
-    },
-    SlotKind::Drill => {
-      return create_drill(i, nth);
-    },
-    SlotKind::DroneLauncher => {
-      return create_drone_launcher(i, nth, i as i32, helix.drone_gen_cooldown * 2.0_f32.powf(((nth as f32 / 2.0) + 1.0) as f32));
-    },
-    SlotKind::Emptiness => {
-      return create_empty_slot(i);
-    },
-    SlotKind::EnergyCell => {
-      return create_slot_energy_cell(i, nth, 100, 100.0 * 2.0_f32.powf((nth + 1) as f32));
-    },
-    SlotKind::Hammer => {
-      return create_hammer(i, nth);
-    },
-    SlotKind::JacksCompass => {
-      return create_slot_jacks_compass(i, nth, 40.0 * 2.0_f32.powf((nth + 1) as f32));
-    }
-    SlotKind::Magnet => {
-      return create_slot_magnet(i, nth);
-    }
-    SlotKind::PurityScanner => {
-      return create_slot_purity_scanner(i, nth, 100.0 * 2.0_f32.powf((nth + 1) as f32));
-    },
-    SlotKind::Sandrone => {
-      panic!("The sandrone is not a valid starting slot");
-    }
-    SlotKind::RandomStart => {
-      let slot = get_random_slot(&mut state.instance_rng_unseeded);
-      return create_slot(slot, i, nth, helix, state);
-    }
-    SlotKind::Windrone => {
-      panic!("The windrone is not a valid starting slot");
+pub fn acquire_item(biome: &mut Biome, resource: Resource, drone: Option<usize>, x: i32, y: i32) -> Resource {
+  // The slots may change what is acquired (see SlotBehavior::on_pickup). Returns what was acquired.
+  let mut item = AcquiredItem { resource, drone, x, y };
+  for slot_index in 0..biome.miner.slots.len() {
+    if let Some(resource) = get_slot_behavior(biome.miner.slots[slot_index].kind).on_pickup(biome, slot_index, &item) {
+      item.resource = resource;
     }
   }
+  inventory_add(&mut biome.miner.meta.inventory, item.resource, 1);
+  biome.miner.meta.acquired.push(item);
+  return item.resource;
+}
+
+pub fn set_miner_phase(meta: &mut MinerMeta, phase: Phase, ticks: u32) {
+  // Some phases are entered more than once. Only record actual changes.
+  if meta.phase as u32 != phase as u32 {
+    meta.phase_history.push((phase, ticks));
+  }
+  meta.phase = phase;
+}
+
+pub fn add_scored_pickup(biome: &mut Biome, resource: Resource, drone: Option<usize>, x: i32, y: i32) {
+  // Stones and gems are what earn points. The points gene gives a chance at an extra one.
+  let resource = acquire_item(biome, resource, drone, x, y);
+  let meta = &mut biome.miner.meta;
+  meta.points_carry += meta.multiplier_points / 100.0;
+  if meta.points_carry >= 1.0 {
+    meta.points_carry -= 1.0;
+    inventory_add(&mut meta.inventory, resource, 1);
+  }
+}
+
+fn create_slot(kind: SlotKind, i: usize, nth: i32, helix: &Helix, state: &mut AppState) -> Slottable {
+  return get_slot_behavior(kind).create(state, helix, i, nth);
 }
 
 pub fn create_miner_from_helix(state: &mut AppState, helix: &Helix) -> Miner {
@@ -215,17 +248,7 @@ pub fn create_miner_from_helix(state: &mut AppState, helix: &Helix) -> Miner {
   // The 32 objects should not be a big deal regardless so this is just easier
   let mut drones: Vec<MeDrone> = vec!();
   for _ in 0..32 {
-    drones.push(MeDrone {
-      movable: Movable {
-        what: WHAT_DRONE,
-        x: 0,
-        y: 0,
-        dir: Direction::Up,
-        now_energy: 0.0,
-        init_energy: 0.0,
-        disabled: false,
-      },
-    });
+    drones.push(create_me_drone());
   }
 
   // Initialize the slots from their helix config
@@ -249,7 +272,7 @@ pub fn create_miner_from_helix(state: &mut AppState, helix: &Helix) -> Miner {
       disabled: false,
     },
     meta: MinerMeta {
-      points_last_move: 0,
+      acquired: vec!(),
       max_energy,
 
       inventory: create_inventory(),
@@ -262,10 +285,27 @@ pub fn create_miner_from_helix(state: &mut AppState, helix: &Helix) -> Miner {
       drone_gen_cooldown: helix.drone_gen_cooldown as i32,
       block_bump_cost: helix.block_bump_cost,
       prev_move_bumped: false,
-      multiplier_energy_pickup: 1, // TODO
+      multiplier_energy_pickup: helix.multiplier_energy_pickup as i32,
+
+      multiplier_points: helix.multiplier_points,
+      points_carry: 0.0,
+      gene_upkeep: (helix.multiplier_points + helix.multiplier_energy_pickup) * GENE_UPKEEP,
+
+      consumable_ration_energy: helix.consumable_ration_energy,
+      consumable_dynamite_boredom: helix.consumable_dynamite_boredom,
+      consumable_flare_boredom: helix.consumable_flare_boredom,
+      consumed: [0; CONSUMABLE_COUNT],
+      flare_ticks: 0,
+
+      satiety: SATIETY_MAX,
+      eaten: 0,
 
       phase: Phase::Start_0,
+      final_phase: Phase::Start_0,
       dying_since: 0,
+      phase_history: vec!((Phase::Start_0, 0)),
+      milestones: vec!(),
+      castle_events: vec!(),
 
       // picked_up_miner: false,
       // filling_castle: false,
@@ -279,41 +319,6 @@ pub fn create_miner_from_helix(state: &mut AppState, helix: &Helix) -> Miner {
   };
 }
 
-fn can_craft_windrone(miner: &Miner) -> bool {
-  let meta = &miner.meta;
-  let slots = &miner.slots;
-  let mut has_empty = false;
-  for i in 0..slots.len() {
-    match slots[i].kind {
-      SlotKind::Windrone => return false,
-      SlotKind::Emptiness => has_empty = true,
-      _ => {},
-    }
-  }
-
-  // Must have an available slot for the windrone
-  if !has_empty { return false; }
-
-  // Must have enough materials to craft a windrone
-  return meta.inventory.wood > 5 && (meta.inventory.stone_white + meta.inventory.stone_blue + meta.inventory.stone_green + meta.inventory.stone_yellow) > 5;
-}
-fn can_craft_sandrone(meta: &MinerMeta, slots: &Vec<Slottable>) -> bool {
-  let mut has_empty = false;
-  for i in 0..slots.len() {
-    match slots[i].kind {
-      SlotKind::Sandrone => return false,
-      SlotKind::Emptiness => has_empty = true,
-      _ => {},
-    }
-  }
-
-  // Must have an available slot for the sandrone
-  if !has_empty { return false; }
-
-  // Must have enough materials to craft a sandrone
-  return meta.inventory.wood > 5 && meta.inventory.water > 10 && (/*meta.inventory.stone_white + */meta.inventory.stone_blue + meta.inventory.stone_green + meta.inventory.stone_yellow) > 5;
-}
-
 pub fn tick_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome) {
   // If;
   // - There are slots available
@@ -326,52 +331,23 @@ pub fn tick_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
 
   // For now the drone can phase through walls but later we'll want to add some pathfinding.
 
-  match biome.miner.windrone.state {
-    WindroneState::Unconstructed => {
-      if can_craft_windrone(&biome.miner) {
-        // Deduct materials
-        biome.miner.meta.inventory.wood -= 5;
-        let mut left = 5;
-        let mut next = biome.miner.meta.inventory.stone_white.min(left);
-        left -= next;
-        biome.miner.meta.inventory.stone_white -= next;
-        if left > 0 {
-          next = biome.miner.meta.inventory.stone_blue.min(left);
-          left -= next;
-          biome.miner.meta.inventory.stone_blue -= next;
-        }
-        if left > 0 {
-          next = biome.miner.meta.inventory.stone_green.min(left);
-          left -= next;
-          biome.miner.meta.inventory.stone_green -= next;
-        }
-        if left > 0 {
-          next = biome.miner.meta.inventory.stone_yellow.min(left);
-          left -= next;
-          biome.miner.meta.inventory.stone_yellow -= next;
-        }
-        assert_eq!(left, 0, "we asserted that there were enough stones, so we should have consumed that many stones now");
-
-        // Add a windrone to the first empty slot
-        let len = biome.miner.slots.len();
-        for i in 0..len {
-          if matches!(biome.miner.slots[i].kind, SlotKind::Emptiness) {
-            biome.miner.slots[i] = create_slot_windrone(i, 1);
-            biome.miner.windrone.state = WindroneState::WaitingForWind;
-            break;
-          }
-          assert!(i < len - 1, "should have asserted beforehand that the windrone would fit somewhere");
-        }
+  // Drones (and anything else) are crafted through recipes
+  tick_crafting(state, biome);
+  // Reach milestones and apply their effects
+  tick_progression(state, biome);
+  // Consumables are used when their gene says so
+  tick_consumables(options, biome);
+  // Get hungry, and eat when there's food
+  tick_hunger(options, biome);
 
-        biome.miner.meta.phase = Phase::HasWindrone_1;
-      }
-    }
+  match biome.miner.windrone.state {
+    WindroneState::Unconstructed => {} // See recipe.rs
     WindroneState::WaitingForWind => {}
     WindroneState::WaitingForGoal => {}
     WindroneState::ReadyForTakeOff => {
       // The windrone has enough wind and at least one target to go to. Deduct the wind and go.
       set_windrone_state(biome, WindroneState::FlyingToGoal);
-      biome.miner.meta.inventory.wind -= 10;
+      inventory_take(&mut biome.miner.meta.inventory, Resource::Wind, 10);
     }
     WindroneState::FlyingToGoal => {}
     WindroneState::FlyingHome => {}
@@ -384,47 +360,7 @@ pub fn tick_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
   }
 
   match biome.miner.sandrone.state {
-    SandroneState::Unconstructed => {
-      if can_craft_sandrone(&mut biome.miner.meta, &mut biome.miner.slots) {
-        // Deduct materials
-        biome.miner.meta.inventory.wood -= 5;
-        biome.miner.meta.inventory.water -= 10;
-        let mut left = 5;
-        let mut next = biome.miner.meta.inventory.stone_white.min(left);
-        left -= next;
-        // Do not allow the white stones. Only use more expensive stones to build a sandrone.
-        // biome.miner.meta.inventory.stone_white -= next;
-        // if left > 0 {
-          next = biome.miner.meta.inventory.stone_blue.min(left);
-          left -= next;
-          biome.miner.meta.inventory.stone_blue -= next;
-        // }
-        if left > 0 {
-          next = biome.miner.meta.inventory.stone_green.min(left);
-          left -= next;
-          biome.miner.meta.inventory.stone_green -= next;
-        }
-        if left > 0 {
-          next = biome.miner.meta.inventory.stone_yellow.min(left);
-          left -= next;
-          biome.miner.meta.inventory.stone_yellow -= next;
-        }
-        assert_eq!(left, 0, "we asserted that there were enough stones, so we should have consumed that many stones now");
-
-        // Add a sandrone to the first empty slot
-        let len = biome.miner.slots.len();
-        for i in 0..len {
-          if matches!(biome.miner.slots[i].kind, SlotKind::Emptiness) {
-            biome.miner.slots[i] = create_slot_sandrone(i, 1);
-            set_sandrone_state(&mut biome.miner.sandrone, SandroneState::WaitingForWater);
-            break;
-          }
-          assert!(i < len - 1, "should have asserted beforehand that the sandrone would fit somewhere");
-        }
-
-        biome.miner.meta.phase = Phase::HasSandrone_2;
-      }
-    }
+    SandroneState::Unconstructed => {} // See recipe.rs
     SandroneState::WaitingForWater => {}
     SandroneState::MovingToOrigin => {}
     SandroneState::MovingToNeighborCell => {}
@@ -437,11 +373,12 @@ pub fn tick_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
   if !biome.miner.sandrone.air_lifting {
     move_miner(options, state, biome);
   }
+
+  // Creatures steal, traders trade (see actor.rs)
+  touch_actors(biome);
 }
 
 pub fn move_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome) {
-  biome.miner.meta.points_last_move = 0;
-
   // if options.return_to_move {
   //   println!("           \nmove_miner:           ");
   // }
@@ -450,8 +387,8 @@ pub fn move_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
   let cy = biome.miner.movable.y;
   let dir = biome.miner.movable.dir;
   let (deltax, deltay) = delta_forward(dir);
-  let nextx = cx + deltax;
-  let nexty = cy + deltay;
+  // On a torus the step may wrap around to the other side of the world
+  let (nextx, nexty) = wrap_coord(options, cx + deltax, cy + deltay);
 
   // If this move would go OOB, expand the world to make sure that does not happen
   ensure_cell_in_world(&mut biome.world, options, nextx, nexty);
@@ -470,6 +407,7 @@ pub fn move_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
       // This must have been an expando that was just revealed. TODO: prevent this case..? :)
       // Set the cell to water tile and add the expando to the world so it can flow.
       biome.world.tiles[unexty][unextx].tile = Tile::ExpandoWater;
+      record_tile_change(&mut biome.world, Tile::Empty, Tile::ExpandoWater);
       biome.world.expandos.push(create_expando(nextx, nexty, pickup_value));
     },
     _ => {},
@@ -567,7 +505,7 @@ pub fn move_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
 
   let mut was_boring = false; // Did we just move forward? No blocks, no pickups?
 
-  let filling_phase = matches!(biome.miner.meta.phase, Phase::FillingCastle_5);
+  let filling_phase = is_filling_castle(biome);
 
   // if options.return_to_move {
   //   println!("- normal move           ");
@@ -576,11 +514,15 @@ pub fn move_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
   // let drills = biome.miner.meta.kind_counts[SlotKind::Drill as usize];
   // let hammers = biome.miner.meta.kind_counts[SlotKind::Hammer as usize];
   let tile = biome.world.tiles[unexty][unextx].tile;
+  let rival = get_rival_miner_at(&biome.world, nextx, nexty);
   match tile {
-    Tile::Wall4 => bump_wall_miner(options, biome, 4, nextx, nexty, deltax, deltay, unextx, unexty),
-    Tile::Wall3 => bump_wall_miner(options, biome, 3, nextx, nexty, deltax, deltay, unextx, unexty),
-    Tile::Wall2 => bump_wall_miner(options, biome, 2, nextx, nexty, deltax, deltay, unextx, unexty),
-    Tile::Wall1 => bump_wall_miner(options, biome, 1, nextx, nexty, deltax, deltay, unextx, unexty),
+    // Another miner of the arena is in the way (see arena.rs)
+    _ if rival.is_some() => bump_rival_miner(options, biome, rival.unwrap(), nextx, nexty),
+
+    Tile::Wall4 => bump_wall_miner(options, biome, 4, nextx, nexty, deltax, deltay),
+    Tile::Wall3 => bump_wall_miner(options, biome, 3, nextx, nexty, deltax, deltay),
+    Tile::Wall2 => bump_wall_miner(options, biome, 2, nextx, nexty, deltax, deltay),
+    Tile::Wall1 => bump_wall_miner(options, biome, 1, nextx, nexty, deltax, deltay),
 
     | Tile::Push
     | Tile::Impassible
@@ -673,87 +615,29 @@ pub fn move_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
       if move_miner_pickup_from_empty_tile(options, biome, nextx, nexty) {
         was_boring = false;
       }
+      // And the crop on it, if it's ripe
+      if miner_harvest_crop(options, biome, nextx, nexty) {
+        was_boring = false;
+      }
 
-      // Do we have any magnets primed? Bump the value by that many.
-      // Note: purity scanner only works for the miner itself
+      // Give the slots a chance to act on the move (like magnets picking up neighbouring tiles)
       for slot_index in 0..biome.miner.slots.len() {
-        match biome.miner.slots[slot_index].kind {
-          SlotKind::Magnet => {
-            // Magnets allow to pick up neighboring tiles from the tile you started at
-            // In order of magnet count; back, left, right, forward-left, forward-right, back-left, back-right
-            // There's no advantage to having more than 7 magnets.
-            // TBD whether there's any other drawback than taking up a slot
-            match biome.miner.slots[slot_index].nth {
-              0 => {
-                let (nx, ny) = coord_back(cx, cy, biome.miner.movable.dir);
-                if move_miner_pickup_from_empty_tile(options, biome, nx, ny) {
-                  was_boring = false;
-                } else {
-                  biome.miner.slots[slot_index].sum += 1.0;
-                }
-              },
-              1 => {
-                let (nx, ny) = coord_left(cx, cy, biome.miner.movable.dir);
-                if move_miner_pickup_from_empty_tile(options, biome, nx, ny) {
-                  was_boring = false;
-                } else {
-                  biome.miner.slots[slot_index].sum += 1.0;
-                }
-              },
-              2 => {
-                let (nx, ny) = coord_right(cx, cy, biome.miner.movable.dir);
-                if move_miner_pickup_from_empty_tile(options, biome, nx, ny) {
-                  was_boring = false;
-                } else {
-                  biome.miner.slots[slot_index].sum += 1.0;
-                }
-              },
-              3 => {
-                let (nx, ny) = coord_fl(cx, cy, biome.miner.movable.dir);
-                if move_miner_pickup_from_empty_tile(options, biome, nx, ny) {
-                  was_boring = false;
-                } else {
-                  biome.miner.slots[slot_index].sum += 1.0;
-                }
-              },
-              4 => {
-                let (nx, ny) = coord_fr(cx, cy, biome.miner.movable.dir);
-                if move_miner_pickup_from_empty_tile(options, biome, nx, ny) {
-                  was_boring = false;
-                } else {
-                  biome.miner.slots[slot_index].sum += 1.0;
-                }
-              },
-              5 => {
-                let (nx, ny) = coord_bl(cx, cy, biome.miner.movable.dir);
-                if move_miner_pickup_from_empty_tile(options, biome, nx, ny) {
-                  was_boring = false;
-                } else {
-                  biome.miner.slots[slot_index].sum += 1.0;
-                }
-              },
-              6 => {
-                let (nx, ny) = coord_br(cx, cy, biome.miner.movable.dir);
-                if move_miner_pickup_from_empty_tile(options, biome, nx, ny) {
-                  was_boring = false;
-                } else {
-                  biome.miner.slots[slot_index].sum += 1.0;
-                }
-              },
-              _ => {
-                // Unused magnet
-              }
-            }
-          },
-          _ => {
-
-          }
+        if get_slot_behavior(biome.miner.slots[slot_index].kind).on_move(options, biome, slot_index, cx, cy) {
+          was_boring = false;
         }
       }
 
+      let visited = biome.world.tiles[unexty][unextx].visited;
       biome.world.tiles[unexty][unextx].visited += 1;
       biome.miner.movable.x = nextx;
       biome.miner.movable.y = nexty;
+
+      // Langton's ant: the visits of the cell decide the turn (see turn_rule.rs). The castle
+      // phases have their own rules.
+      if !filling_phase && !biome.miner.sandrone.air_lifted {
+        let rule = biome.miner.helix.turn_rules[TurnContext::Open as usize];
+        biome.miner.movable.dir = apply_turn_rule(options, &biome.world, &rule, visited, nextx, nexty, biome.miner.movable.dir);
+      }
     },
   }
 
@@ -768,7 +652,8 @@ pub fn move_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
   // Cannot be in an infinite loop while building the sand castle
   if was_boring && !filling_phase {
     // Prevent endless loops by making it increasingly more difficult to make consecutive moves that where nothing happens
-    biome.miner.movable.now_energy = (biome.miner.movable.now_energy - biome.miner.meta.boredom_level as f32).max(0.0);
+    // A hungry miner gets bored faster
+    biome.miner.movable.now_energy = (biome.miner.movable.now_energy - biome.miner.meta.boredom_level as f32 * get_hunger_boredom_multiplier(biome)).max(0.0);
     // The cost grows the longer nothing keeps happening ("You're getting antsy, thirsty for an event")
     biome.miner.meta.boredom_level = biome.miner.meta.boredom_level + 1;
   } else {
@@ -776,6 +661,7 @@ pub fn move_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
   }
 
   biome.miner.movable.now_energy = (biome.miner.movable.now_energy - if filling_phase { state.cost_increase_value / 4.0 } else { state.cost_increase_value }).max(0.0);
+  biome.miner.movable.now_energy = (biome.miner.movable.now_energy - biome.miner.meta.gene_upkeep).max(0.0);
 
   if biome.miner.meta.dying_since > 0 {
     // Start decaying the miner after the last phase.
@@ -788,19 +674,26 @@ pub fn move_miner(options: &mut Options, state: &mut AppState, biome: &mut Biome
   // }
 }
 
-pub fn bump_wall_miner(options: &mut Options, biome: &mut Biome, strength: i32, nextx: i32, nexty: i32, deltax: i32, deltay: i32, unextx: usize, unexty: usize) {
+pub fn bump_wall_miner(options: &mut Options, biome: &mut Biome, strength: i32, nextx: i32, nexty: i32, deltax: i32, deltay: i32) {
   // if options.return_to_move {
   //   println!("- bumping against wall, facing {:?}", biome.miner.movable.dir);
   // }
 
 
-  let cell = &mut biome.world.tiles[unexty][unextx];
+  // Let the slots act on the bump (hammers hit harder, drills drill deeper)
+  let mut extra = 0;
+  for slot_index in 0..biome.miner.slots.len() {
+    extra += get_slot_behavior(biome.miner.slots[slot_index].kind).on_bump(options, biome, slot_index, nextx, nexty, deltax, deltay);
+  }
 
-  let hammers = biome.miner.meta.kind_counts[SlotKind::Hammer as usize];
-  let drills = biome.miner.meta.kind_counts[SlotKind::Drill as usize];
+  // The slots may have grown the world, which shifts the array indexes
+  let unextx = (biome.world.min_x.abs() + nextx) as usize;
+  let unexty = (biome.world.min_y.abs() + nexty) as usize;
+  let cell = &mut biome.world.tiles[unexty][unextx];
 
-  let n = strength - (1 + hammers);
+  let n = strength - (1 + extra);
 
+  let before = cell.tile;
   biome.world.tiles[unexty][unextx] = match n.max(0) {
     3 => create_unvisited_cell(Tile::Wall3, cell.pickup, cell.tile_value, cell.pickup_value),
     2 => create_unvisited_cell(Tile::Wall2, cell.pickup, cell.tile_value, cell.pickup_value),
@@ -809,108 +702,100 @@ pub fn bump_wall_miner(options: &mut Options, biome: &mut Biome, strength: i32,
     // always at least -1
     _ => panic!("A bump should always at least decrease the wall by one so it can never stay 4: {}", n),
   };
+  // Cached drone paths may go through this wall (see pathfinding.rs)
+  let after = biome.world.tiles[unexty][unextx].tile;
+  record_tile_change(&mut biome.world, before, after);
+  let mut keep_dir = false;
   if n <= 0 {
     // Broke a wall. Add sand.
     // TODO: what about the drill? What about bonuses? Should it be u32 or f32?
-    biome.miner.meta.inventory.sand += 1;
-  }
+    inventory_add(&mut biome.miner.meta.inventory, Resource::Sand, 1);
+    // Any fluid next to it may now flow in
+    wake_fluid_around(options, &mut biome.world, nextx, nexty);
 
-  if drills > 0 {
-    drill_deeper(drills, hammers, nextx, nexty, deltax, deltay, &mut biome.world, options);
+    // Slots may prevent the turn (like a basher). The first one that does is the only one used.
+    for slot_index in 0..biome.miner.slots.len() {
+      keep_dir = keep_dir || get_slot_behavior(biome.miner.slots[slot_index].kind).on_break(options, biome, slot_index, nextx, nexty);
+    }
   }
+
   biome.miner.meta.prev_move_bumped = true;
 
   biome.miner.movable.now_energy = (biome.miner.movable.now_energy - biome.miner.meta.block_bump_cost).max(0.0);
-  // TODO: should drones use same "prefer visited tiles" heuristic as miner?
-  biome.miner.movable.dir = get_most_visited_dir_from_xydir(options, &mut biome.world, nextx, nexty, biome.miner.movable.dir);
+  if !keep_dir {
+    // TODO: should drones use same "prefer visited tiles" heuristic as miner?
+    // By default the rule turns towards the more visited side (see turn_rule.rs)
+    let rule = biome.miner.helix.turn_rules[TurnContext::Wall as usize];
+    let visited = get_cell_stuff_at(options, &biome.world, biome.miner.movable.x, biome.miner.movable.y).3;
+    biome.miner.movable.dir = apply_turn_rule(options, &biome.world, &rule, visited, nextx, nexty, biome.miner.movable.dir);
+  }
 
   // if options.return_to_move {
   //   println!("- now facing {:?}", biome.miner.movable.dir);
   // }
 }
 
+pub fn can_slots_move_miner(biome: &Biome) -> bool {
+  // Slots that move the miner around (like the bigfoot or the grappling hook) must not interfere
+  // with the sand castle phases, where the sandrone and the magic wall decide where it goes.
+  return !biome.miner.movable.disabled
+    && !biome.miner.sandrone.air_lifting
+    && !is_filling_castle(biome);
+}
+
+pub fn is_filling_castle(biome: &Biome) -> bool {
+  // From the miner being dropped off in the castle until the sandrone starts redecorating it. This
+  // does not look at the phase since the progression decides when that changes.
+  return biome.miner.sandrone.air_lifted
+    || (biome.miner.sandrone.post_castle > 0 && !matches!(biome.miner.sandrone.state, SandroneState::Redecorating));
+}
+
+pub fn can_miner_step_into(options: &Options, world: &World, x: i32, y: i32) -> bool {
+  let (tile, pickup, _, _) = get_cell_stuff_at(options, world, x, y);
+  // Unrevealed expandos are handled by move_miner
+  return is_open_tile(tile) && !matches!(pickup, Pickup::Expando) && get_rival_miner_at(world, x, y).is_none();
+}
+
+pub fn step_miner_into(options: &mut Options, biome: &mut Biome, x: i32, y: i32) -> bool {
+  // Move the miner into the open cell at x,y outside of a regular move, picking up whatever is
+  // there. Returns true if anything was picked up.
+  let picked = move_miner_pickup_from_empty_tile(options, biome, x, y);
+  let (x, y) = wrap_coord(options, x, y);
+  let ux = (biome.world.min_x.abs() + x) as usize;
+  let uy = (biome.world.min_y.abs() + y) as usize;
+  biome.world.tiles[uy][ux].visited += 1;
+  biome.miner.movable.x = x;
+  biome.miner.movable.y = y;
+  return picked;
+}
+
 pub fn move_miner_pickup_from_empty_tile(options: &mut Options, biome: &mut Biome, x: i32, y: i32) -> bool {
   // Return true if anything was picked up. False if nothing. Used for the boring stat.
+  let (x, y) = wrap_coord(options, x, y);
   ensure_cell_in_world(&mut biome.world, options, x, y);
 
   let unextx = (biome.world.min_x.abs() + x) as usize;
   let unexty = (biome.world.min_y.abs() + y) as usize;
 
   let tile = &mut biome.world.tiles[unexty][unextx];
-  let meta = &mut biome.miner.meta;
+  let meta = &biome.miner.meta;
 
-  match tile.pickup {
+  // The resource to acquire and whether it scores
+  let (resource, scored) = match tile.pickup {
     Pickup::Diamond => {
-      let mut primed = 0;
-      // Do we have any purity scanners primed? Bump the value by that many.
-      // Note: purity scanner only works for the miner itself
-      for n in &mut biome.miner.slots {
-        if matches!(n.kind, SlotKind::PurityScanner) && n.cur_cooldown >= n.max_cooldown {
-          primed += 1;
-        }
-      }
-
       // Different gems with different points.
       // Miners could have properties or powerups to affect this, too.
-      let gv: i32 = (tile.pickup_value + primed).min(3) as i32;
-      match gv {
-        0 => meta.inventory.diamond_white += 1,
-        1 => meta.inventory.diamond_green += 1,
-        2 => meta.inventory.diamond_blue += 1,
-        3 => meta.inventory.diamond_yellow += 1,
-        _ => panic!("what value did this diamond have: {:?}", tile),
-      };
-      let gem_value: i32 = gv + 1;
-
-      meta.points_last_move = gem_value;
-      tile.pickup = Pickup::Nothing;
-      tile.pickup_value = 0;
+      // Purity scanners may still improve it (see SlotBehavior::on_pickup)
+      (resource_of_quality(ResourceCategory::Diamond, tile.pickup_value.min(3)), true)
     },
     Pickup::Energy => {
       biome.miner.movable.now_energy = (biome.miner.movable.now_energy + (E_VALUE as f64 * ((100.0 + meta.multiplier_energy_pickup as f64) / 100.0)) as f32).min(meta.max_energy);
-      meta.inventory.energy += 1;
-      tile.pickup = Pickup::Nothing;
-      tile.pickup_value = 0;
-    },
-    Pickup::Stone => {
-      // Do we have any purity scanners primed? Bump the value by that many.
-      // Note: purity scanner only works for the miner itself
-      let mut primed = 0;
-      for n in &mut biome.miner.slots {
-        match n.kind {
-          SlotKind::PurityScanner => if n.cur_cooldown >= n.max_cooldown {
-            primed += 1;
-          },
-          _ => ()
-        }
-      }
-
-      match (tile.pickup_value + primed).min(3) {
-        0 => meta.inventory.stone_white += 1,
-        1 => meta.inventory.stone_green += 1,
-        2 => meta.inventory.stone_blue += 1,
-        3 => meta.inventory.stone_yellow += 1,
-        _ => panic!("what value did this stone have: {:?}", tile),
-      }
-      meta.points_last_move = tile.pickup_value as i32;
-      tile.pickup = Pickup::Nothing;
-      tile.pickup_value = 0;
-    },
-    Pickup::Wind => {
-      meta.inventory.wind += 1;
-      tile.pickup = Pickup::Nothing;
-      tile.pickup_value = 0;
-    },
-    Pickup::Water => {
-      meta.inventory.water += 1;
-      tile.pickup = Pickup::Nothing;
-      tile.pickup_value = 0;
-    },
-    Pickup::Wood => {
-      meta.inventory.wood += 1;
-      tile.pickup = Pickup::Nothing;
-      tile.pickup_value = 0;
+      (Resource::Energy, false)
     },
+    Pickup::Stone => (resource_of_quality(ResourceCategory::Stone, tile.pickup_value.min(3)), true),
+    Pickup::Wind => (Resource::Wind, false),
+    Pickup::Water => (Resource::Water, false),
+    Pickup::Wood => (Resource::Wood, false),
     | Pickup::Nothing
     | Pickup::Expando // Ignore, fake pickup
     | Pickup::Fountain // Ignore, fake pickup... TODO: probably some special behavior?
@@ -918,6 +803,14 @@ pub fn move_miner_pickup_from_empty_tile(options: &mut Options, biome: &mut Biom
       // Ignore this "pickup"
       return true; // "boring", nothing happened
     },
+  };
+  tile.pickup = Pickup::Nothing;
+  tile.pickup_value = 0;
+
+  if scored {
+    add_scored_pickup(biome, resource, None, x, y);
+  } else {
+    acquire_item(biome, resource, None, x, y);
   }
 
   // Was not boring
@@ -1,9 +1,11 @@
 use super::world::*;
+use super::fluid::*;
 use super::cell::*;
 use super::options::*;
 use super::tile::*;
+use serde_derive::{Serialize, Deserialize};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum Direction {
   Up = 0,
   Right = 1,
@@ -11,7 +13,7 @@ pub enum Direction {
   Left = 3,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Movable {
   pub what: i32,
   pub x: i32,
@@ -28,11 +30,10 @@ pub fn drill_deeper(drills: i32, hammers: i32, x: i32, y: i32, dx: i32, dy: i32,
   // From where you're standing, move drill count+1 steps into dx and dy direction
   // For every block encountered decrease the drill count by one
   // For every block encountered passed the first, apply a bump of the drill count left
-  // Respect the world wrapping around edges
+  // Respect the world wrapping around edges (see WorldTopology)
 
   // Offset the first block. No action here, this is the one we already bumped
-  let mut next_x = x + dx;
-  let mut next_y = y + dy;
+  let (mut next_x, mut next_y) = wrap_coord(options, x + dx, y + dy);
   let mut strength = if hammers > 0 { hammers - 1 } else { 0 }; // Start with the hammer strength - 1
   let mut remaining = drills; // Stop after punching through this many blocks
 
@@ -44,6 +45,7 @@ pub fn drill_deeper(drills: i32, hammers: i32, x: i32, y: i32, dx: i32, dy: i32,
     let unext_y = (world.min_y.abs() + next_y) as usize;
 
     // Apply the drill power
+    let before = world.tiles[unext_y][unext_x].tile;
     match world.tiles[unext_y][unext_x] {
       Cell { tile: Tile::Wall4, pickup, tile_value, pickup_value, .. } => {
         // let multiplier_percent: Uniform<f32> = Uniform::from(0.0..100.0);
@@ -88,8 +90,17 @@ pub fn drill_deeper(drills: i32, hammers: i32, x: i32, y: i32, dx: i32, dy: i32,
       }
     }
 
-    next_x = next_x + dx;
-    next_y = next_y + dy;
+    // Cached drone paths may go through this wall (see pathfinding.rs)
+    let after = world.tiles[unext_y][unext_x].tile;
+    record_tile_change(world, before, after);
+    if matches!(world.tiles[unext_y][unext_x].tile, Tile::Empty) {
+      // Any fluid next to it may now flow in
+      wake_fluid_around(options, world, next_x, next_y);
+    }
+
+    let (nx, ny) = wrap_coord(options, next_x + dx, next_y + dy);
+    next_x = nx;
+    next_y = ny;
 
     remaining = remaining - 1;
     strength = strength - 1;
@@ -0,0 +1,200 @@
+use rand_pcg::{Lcg128Xsl64};
+use rand::distributions::{Distribution, Uniform};
+
+use super::app_state::*;
+use super::biome::*;
+use super::fitness::*;
+use super::helix::*;
+use super::options::*;
+use super::slottable::*;
+
+#[cfg(target_arch = "wasm32")]
+use serde_derive::{Serialize, Deserialize};
+
+// How the mutation rates change while the GA runs. See options.mutation_schedule.
+//
+// The base rates are options.mutation_rate_genes and options.mutation_rate_slots, which can still
+// be tuned by hand with the o/p/k/l keys. The schedule decides the effective rates from those:
+// - Fixed: the base rates, as is
+// - SelfAdaptive: every helix carries its own step sizes as genes. When a helix is mutated, its
+//   step sizes are mutated first (log-normal, like an evolution strategy) and the new step sizes
+//   are used to mutate the other genes. Good step sizes survive along with the miners using them.
+// - OneFifth: Rechenberg's 1/5th success rule. After every batch, if more than a fifth of the
+//   miners beat their parent the rates grow, otherwise they shrink.
+// - Decay: the rates shrink while there is no new best, by options.mutation_decay for every batch
+//   worth of miners since the last best (state.miner_count_since_last_best). A new best restores
+//   the base rates.
+//
+// With islands (see island.rs) every island applies the schedule to its own base rates, with its
+// own 1/5th rule and its own batches since its last improvement.
+
+#[cfg_attr(target_arch = "wasm32", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MutationSchedule {
+  Fixed,
+  SelfAdaptive,
+  OneFifth,
+  Decay,
+}
+
+// Step sizes of a new helix, or a helix deserialized from a format that did not have them yet
+pub const INITIAL_MUTATION_STEP: f32 = 5.0;
+// Bounds of effective mutation rates, in percent
+pub const MIN_MUTATION_RATE: f32 = 0.1;
+pub const MAX_MUTATION_RATE: f32 = 100.0;
+
+fn sample_standard_normal(rng: &mut Lcg128Xsl64) -> f32 {
+  // Box-Muller transform
+  let roller: Uniform<f32> = Uniform::from(f32::EPSILON..1.0);
+  let u1 = roller.sample(rng);
+  let u2 = roller.sample(rng);
+  return (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos();
+}
+
+pub fn self_adapt_step(rng: &mut Lcg128Xsl64, step: f32, options: &Options) -> f32 {
+  return (step * (options.mutation_tau * sample_standard_normal(rng)).exp()).clamp(MIN_MUTATION_RATE, MAX_MUTATION_RATE);
+}
+
+pub fn get_scheduled_mutation_rates(options: &Options, base: (f32, f32), adapted: (f32, f32), batches_without_best: u32, helix: &Helix) -> (f32, f32) {
+  // Effective (genes, slots) mutation rates for mutating given helix. See MutationSchedule.
+  let (base_genes, base_slots) = base;
+  return match options.mutation_schedule {
+    MutationSchedule::Fixed => base,
+    MutationSchedule::SelfAdaptive => (helix.mutation_step_genes, helix.mutation_step_slots),
+    MutationSchedule::OneFifth => adapted,
+    MutationSchedule::Decay => {
+      let factor = options.mutation_decay.powi(batches_without_best as i32);
+      (
+        (base_genes * factor).max(MIN_MUTATION_RATE.min(base_genes)),
+        (base_slots * factor).max(MIN_MUTATION_RATE.min(base_slots)),
+      )
+    }
+  };
+}
+
+pub fn get_mutation_rates(options: &Options, state: &AppState, helix: &Helix) -> (f32, f32) {
+  let batches = state.miner_count_since_last_best / (options.batch_size as u32).max(1);
+  return get_scheduled_mutation_rates(options, (options.mutation_rate_genes, options.mutation_rate_slots), state.adapted_mutation_rates, batches, helix);
+}
+
+pub fn get_one_fifth_rates(rates: (f32, f32), successes: usize, total: usize) -> (f32, f32) {
+  // Grow the rates when more than a fifth of the miners was a success, shrink them when fewer were
+  let ratio = successes as f32 / total.max(1) as f32;
+  let factor = if ratio > 0.2 { 1.0 / 0.82 } else if ratio < 0.2 { 0.82 } else { 1.0 };
+  let (genes, slots) = rates;
+  return (
+    (genes * factor).clamp(MIN_MUTATION_RATE, MAX_MUTATION_RATE),
+    (slots * factor).clamp(MIN_MUTATION_RATE, MAX_MUTATION_RATE),
+  );
+}
+
+pub fn adapt_mutation_rates(options: &Options, state: &mut AppState, biomes: &Vec<Biome>) {
+  // Apply the 1/5th success rule after a batch. A miner is a success when it beats its parent.
+  if !matches!(options.mutation_schedule, MutationSchedule::OneFifth) {
+    return;
+  }
+  let successes = biomes.iter().filter(|biome| get_fitness(options, biome) > state.parent_fitness).count();
+  state.adapted_mutation_rates = get_one_fifth_rates(state.adapted_mutation_rates, successes, biomes.len());
+}
+
+// Structural slot mutations. Besides replacing single slots with a random kind (see
+// options.mutation_rate_slots), a mutated helix may get each of these operators applied once, with
+// the chance (in percent) set in the options. The order of the slots matters since the nth slot of
+// a kind scales its cooldown and the magnet direction depends on it.
+
+#[derive(Debug, Clone, Copy)]
+pub enum SlotOperator {
+  // Swap two random slots
+  Swap = 0,
+  // Rotate a run of 2 to 8 slots by one position, left or right
+  Rotate = 1,
+  // Copy a random non-empty slot into a random empty slot
+  Duplicate = 2,
+  // Turn a random non-empty slot into Emptiness
+  Delete = 3,
+}
+pub const SLOT_OPERATOR_COUNT: usize = 4;
+pub const SLOT_OPERATORS: [SlotOperator; SLOT_OPERATOR_COUNT] = [SlotOperator::Swap, SlotOperator::Rotate, SlotOperator::Duplicate, SlotOperator::Delete];
+
+fn get_slot_operator_chance(options: &Options, op: SlotOperator) -> f32 {
+  return match op {
+    SlotOperator::Swap => options.mutation_slot_swap,
+    SlotOperator::Rotate => options.mutation_slot_rotate,
+    SlotOperator::Duplicate => options.mutation_slot_duplicate,
+    SlotOperator::Delete => options.mutation_slot_delete,
+  };
+}
+
+fn apply_slot_operator(rng: &mut Lcg128Xsl64, slots: &mut [SlotKind; 32], op: SlotOperator) -> bool {
+  // Returns false when the operator could not do anything (like duplicating without a free slot)
+  let index_roller: Uniform<usize> = Uniform::from(0..slots.len());
+  let filled: Vec<usize> = (0..slots.len()).filter(|&i| !matches!(slots[i], SlotKind::Emptiness)).collect();
+  let empty: Vec<usize> = (0..slots.len()).filter(|&i| matches!(slots[i], SlotKind::Emptiness)).collect();
+  match op {
+    SlotOperator::Swap => {
+      let a = index_roller.sample(rng);
+      let b = index_roller.sample(rng);
+      if slots[a] as usize == slots[b] as usize { return false; }
+      slots.swap(a, b);
+    }
+    SlotOperator::Rotate => {
+      let len = Uniform::from(2..=8).sample(rng);
+      let start = Uniform::from(0..=slots.len() - len).sample(rng);
+      if Uniform::from(0..2).sample(rng) == 0 {
+        slots[start..start + len].rotate_left(1);
+      } else {
+        slots[start..start + len].rotate_right(1);
+      }
+    }
+    SlotOperator::Duplicate => {
+      if filled.len() == 0 || empty.len() == 0 { return false; }
+      let from = filled[Uniform::from(0..filled.len()).sample(rng)];
+      let to = empty[Uniform::from(0..empty.len()).sample(rng)];
+      slots[to] = slots[from];
+    }
+    SlotOperator::Delete => {
+      if filled.len() == 0 { return false; }
+      slots[filled[Uniform::from(0..filled.len()).sample(rng)]] = SlotKind::Emptiness;
+    }
+  }
+  return true;
+}
+
+pub fn mutate_slot_structure(rng: &mut Lcg128Xsl64, slots: &mut [SlotKind; 32], options: &Options) -> u8 {
+  // Apply each structural operator with its own chance. Returns the bit set of applied operators.
+  let pct_roller: Uniform<f32> = Uniform::from(0.0..100.0);
+  let mut applied = 0;
+  for op in SLOT_OPERATORS {
+    if pct_roller.sample(rng) < get_slot_operator_chance(options, op) && apply_slot_operator(rng, slots, op) {
+      applied |= 1 << op as u8;
+    }
+  }
+  return applied;
+}
+
+pub fn record_slot_operators(options: &Options, state: &mut AppState, biomes: &Vec<Biome>) {
+  // Count how often each operator was applied and how often that beat the parent of the batch
+  for biome in biomes.iter() {
+    let improved = get_fitness(options, biome) > state.parent_fitness;
+    record_biome_slot_operators(state, biome, improved);
+  }
+}
+
+pub fn record_biome_slot_operators(state: &mut AppState, biome: &Biome, improved: bool) {
+  for op in SLOT_OPERATORS {
+    if biome.miner.helix.slot_operators & (1 << op as u8) != 0 {
+      state.slot_operator_stats[op as usize].0 += 1;
+      if improved {
+        state.slot_operator_stats[op as usize].1 += 1;
+      }
+    }
+  }
+}
+
+pub fn ui_slot_operators(state: &AppState) -> String {
+  let parts: Vec<String> = SLOT_OPERATORS.iter().map(|&op| {
+    let (applied, improved) = state.slot_operator_stats[op as usize];
+    format!("{:?}: {}/{}", op, improved, applied)
+  }).collect();
+  return format!("Slot operators (improved/applied): {}", parts.join(", "));
+}
@@ -0,0 +1,138 @@
+use super::app_state::*;
+use super::biome::*;
+use super::fitness::*;
+use super::helix::*;
+use super::inventory::*;
+use super::options::*;
+
+// Novelty search. Rather than rewarding a miner for its points, reward it for doing something
+// that no miner did before. Every miner is summarized by a behaviour descriptor (see
+// get_behaviour_descriptor) and its novelty is the average distance between its descriptor and
+// the options.novelty_k nearest descriptors among its batch and the novelty archive.
+//
+// After each batch the most novel miners are added to the archive (up to options.novelty_per_batch
+// of them, and only if their novelty is at least options.novelty_threshold) and the miner with the
+// best score, `novelty + options.novelty_fitness_weight * fitness`, becomes the next parent.
+// The oldest descriptors are dropped once the archive exceeds options.novelty_archive_size.
+
+// Number of sectors (around the origin) in the visited-cell histogram of a descriptor
+pub const NOVELTY_SECTORS: usize = 8;
+
+pub fn get_behaviour_descriptor(biome: &Biome) -> Vec<f64> {
+  let mut descriptor: Vec<f64> = vec!();
+
+  // Where did the miner end up, in tens of cells
+  descriptor.push(biome.miner.movable.x as f64 / 10.0);
+  descriptor.push(biome.miner.movable.y as f64 / 10.0);
+
+  // In which directions did the miner spend its time? Fraction of visited cells per sector.
+  let mut sectors = [0.0; NOVELTY_SECTORS];
+  let mut visited_cells = 0.0;
+  let world = &biome.world;
+  for y in world.min_y..=world.max_y {
+    for x in world.min_x..=world.max_x {
+      let cell = &world.tiles[(world.min_y.abs() + y) as usize][(world.min_x.abs() + x) as usize];
+      if cell.visited == 0 { continue; }
+      let angle = (y as f64).atan2(x as f64) + std::f64::consts::PI; // 0..=2pi
+      let sector = ((angle / (2.0 * std::f64::consts::PI)) * NOVELTY_SECTORS as f64) as usize;
+      sectors[sector.min(NOVELTY_SECTORS - 1)] += 1.0;
+      visited_cells += 1.0;
+    }
+  }
+  for sector in sectors.iter() {
+    descriptor.push(if visited_cells > 0.0 { sector / visited_cells } else { 0.0 });
+  }
+  // How much did the miner explore? Log scale so a few cells more matter less the more it visited.
+  descriptor.push((visited_cells + 1.0).log2());
+
+  // What did the miner collect? Fraction of the inventory per resource category.
+  let mut categories = [0.0; 4]; // One per ResourceCategory
+  let mut total = 0.0;
+  for info in RESOURCES.iter() {
+    let count = inventory_get(&biome.miner.meta.inventory, info.resource) as f64;
+    categories[info.category as usize] += count;
+    total += count;
+  }
+  for category in categories.iter() {
+    descriptor.push(if total > 0.0 { category / total } else { 0.0 });
+  }
+
+  // How far did the miner get into the game?
+  descriptor.push(get_phase_reached(&biome.miner.meta) as u32 as f64);
+
+  return descriptor;
+}
+
+fn descriptor_distance(a: &[f64], b: &[f64]) -> f64 {
+  return a.iter().zip(b.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f64>().sqrt();
+}
+
+pub fn get_novelty(options: &Options, descriptor: &[f64], others: &Vec<&Vec<f64>>) -> f64 {
+  // Others may contain the descriptor itself, at distance zero, which is skipped once
+  let mut distances: Vec<f64> = others.iter().map(|other| descriptor_distance(descriptor, other)).collect();
+  distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+  if distances.len() > 0 && distances[0] == 0.0 {
+    distances.remove(0);
+  }
+  let k = options.novelty_k.max(1).min(distances.len());
+  if k == 0 {
+    // Nothing to compare against, so anything goes
+    return 0.0;
+  }
+  return distances[0..k].iter().sum::<f64>() / k as f64;
+}
+
+pub fn select_by_novelty(options: &Options, state: &mut AppState, biomes: &Vec<Biome>) -> Helix {
+  // Score the batch, update the archive, and return the helix of the best scoring miner
+  let descriptors: Vec<Vec<f64>> = biomes.iter().map(get_behaviour_descriptor).collect();
+  let mut scored: Vec<(usize, f64, f64)> = vec!(); // biome index, novelty, score
+  {
+    let others: Vec<&Vec<f64>> = descriptors.iter().chain(state.novelty_archive.iter()).collect();
+    for (i, descriptor) in descriptors.iter().enumerate() {
+      let novelty = get_novelty(options, descriptor, &others);
+      let score = novelty + options.novelty_fitness_weight * get_fitness(options, &biomes[i]) as f64;
+      scored.push((i, novelty, score));
+    }
+  }
+
+  let mut winner = 0;
+  for n in 1..scored.len() {
+    if scored[n].2 > scored[winner].2 {
+      winner = n;
+    }
+  }
+  let (_, winner_novelty, winner_score) = scored[winner];
+
+  scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+  let mut added = 0;
+  for &(i, novelty, _score) in scored.iter().take(options.novelty_per_batch) {
+    if novelty >= options.novelty_threshold || state.novelty_archive.len() == 0 {
+      state.novelty_archive.push(descriptors[i].clone());
+      added += 1;
+    }
+  }
+  while state.novelty_archive.len() > options.novelty_archive_size.max(1) {
+    state.novelty_archive.remove(0);
+  }
+
+  println!(
+    "Novelty: added {} descriptors, archive has {}. Next parent is biome {} with novelty {:.2} (score {:.2}) and descriptor {}",
+    added,
+    state.novelty_archive.len(),
+    winner,
+    winner_novelty,
+    winner_score,
+    ui_descriptor(&descriptors[winner]),
+  );
+  if added > 0 {
+    state.miner_count_since_last_best = 0;
+  }
+
+  state.parent_fitness = get_fitness(options, &biomes[winner]);
+  return biomes[winner].miner.helix;
+}
+
+pub fn ui_descriptor(descriptor: &[f64]) -> String {
+  let values: Vec<String> = descriptor.iter().map(|v| format!("{:.2}", v)).collect();
+  return format!("[{}]", values.join(", "));
+}
@@ -7,6 +7,13 @@ use std::collections::HashMap;
 use super::{bridge};
 use super::app_state::*;
 use super::helix::*;
+use super::castle_ca::*;
+use super::fitness::*;
+use super::inventory::*;
+use super::island::*;
+use super::arena::*;
+use super::actor::*;
+use super::mutation::*;
 
 #[cfg(target_arch = "wasm32")]
 use serde_derive::{Serialize, Deserialize};
@@ -18,6 +25,16 @@ pub struct Options {
   pub initial_miner_code: String,
   pub mutation_rate_genes: f32,
   pub mutation_rate_slots: f32,
+  // How the mutation rates adapt over time. See mutation.rs. Can be set through --mutation
+  pub mutation_schedule: MutationSchedule,
+  pub mutation_tau: f32, // Learning rate of self-adaptive step sizes
+  pub mutation_decay: f32, // Decay schedule: rates are multiplied by this for every batch without a new best
+  // Chance (in percent) to apply each structural slot operator to a mutated helix. Can be set
+  // through --slot-operators. See SlotOperator.
+  pub mutation_slot_swap: f32,
+  pub mutation_slot_rotate: f32,
+  pub mutation_slot_duplicate: f32,
+  pub mutation_slot_delete: f32,
   // Mutate a new batch from the overall best or the last winner?
   pub mutate_from_best: bool,
   // Reset every this many generated miners
@@ -28,6 +45,39 @@ pub struct Options {
   pub return_to_move: bool,
   pub seed: u64,
   pub speed: u64,
+  // Load this biome snapshot into biome 0 at startup, in step mode. Can be set through --biome
+  pub snapshot_file: String,
+  // Json file with the recipes that miners can craft. Empty for the built-in recipes. See recipe.rs
+  pub recipes_file: String,
+  // Json file with the milestones of the progression. Empty for the built-in progression. See progression.rs
+  pub progression_file: String,
+  // What makes a miner better than another? See fitness.rs. Can be set through --fitness
+  pub fitness: FitnessFunction,
+  // Overrides the points of these resources for the Points fitness. Can be set through --fitness-points
+  pub fitness_points: Vec<(Resource, u64)>,
+  // Select miners by Pareto ranking over pareto_objectives rather than by fitness? See pareto.rs
+  pub pareto: bool,
+  pub pareto_objectives: Vec<FitnessFunction>,
+  pub pareto_archive_size: usize, // Max number of miners kept in the front
+  // Number of sub-populations that evolve side by side, each with batch_size miners. One disables it. See island.rs
+  pub islands: usize,
+  pub island_migration_interval: u32, // Migrate every this many batches
+  pub island_migrants: usize, // Number of best helices that each island sends to its neighbours
+  pub island_topology: IslandTopology,
+  // Mutation rates (genes, slots) per island. Islands without an entry use the global rates.
+  pub island_mutation_rates: Vec<(f32, f32)>,
+  // Select miners by the novelty of their behaviour rather than by fitness? See novelty.rs
+  pub novelty: bool,
+  pub novelty_k: usize, // Novelty is the average distance to this many nearest neighbours
+  pub novelty_fitness_weight: f64, // Selection score is novelty plus this times the fitness. Zero for pure novelty.
+  pub novelty_threshold: f64, // Min novelty for a miner to be added to the archive
+  pub novelty_per_batch: usize, // Max number of miners added to the archive per batch
+  pub novelty_archive_size: usize, // Max number of descriptors in the archive, oldest are dropped first
+  // Number of miners that compete in one shared world. One disables it. See arena.rs
+  pub arena_size: usize,
+  pub arena_spawn: ArenaSpawn,
+  pub arena_spawn_radius: i32, // Distance from the origin for the Spread spawn
+  pub arena_conflict: ArenaConflict, // What happens when a miner moves into a rival
 
   // -> state.cost_increase_value
   pub cost_increase_rate: f32,
@@ -39,6 +89,20 @@ pub struct Options {
   // Sandrone will pick up miner after putting down this many push tiles
   pub sandrone_pickup_count: u32,
   pub sandcastle_area_limit: u32, // Sandrone will permanently stop building the wall after the castle area is at least this big
+  // Cellular automaton rule to run over the castle once it's filled. Empty to disable. See castle_ca.rs
+  pub castle_ca_rule: String,
+  pub castle_ca_interval: u32, // Ticks between generations
+  pub castle_ca_generations: u32, // Stop after this many generations. Zero means no limit.
+  pub castle_ca_energy_cost: f32, // Miner energy spent per generation
+  pub castle_ca_reward: u32, // White stones per cell that turns into a push tile
+  // Satiety lost per tick, out of SATIETY_MAX. Zero disables hunger. See hunger.rs
+  pub hunger_rate: f32,
+  // Chance in percent per generated cell to spawn each kind of actor. Empty for no actors. See actor.rs
+  pub actor_spawn_rates: Vec<(ActorKind, f32)>,
+  // Shape of the world. See WorldTopology. Can be set through --topology
+  pub topology: WorldTopology,
+  // For bounded and torus worlds, the playable area spans -world_size..=world_size on both axes
+  pub world_size: i32,
   // Print the world in html rather than terminal ansi?
   pub html_mode: bool,
   // Show the miner in all other biomes in the map as well? Confusing but fun? :)
@@ -71,16 +135,73 @@ pub struct Options {
   pub paint_fg_colors: bool,
 }
 
-pub fn parse_cli_args() -> Options {
-  // Defaults:
-  let mut options = Options {
+#[cfg_attr(target_arch = "wasm32", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorldTopology {
+  // The world expands forever in every direction (the original behavior)
+  Infinite,
+  // Everything beyond world_size is a hard Impassible border
+  Bounded,
+  // Coordinates beyond world_size wrap around to the other side
+  Torus,
+}
+
+pub fn topology_to_code(topology: WorldTopology) -> u8 {
+  return match topology {
+    WorldTopology::Infinite => 0,
+    WorldTopology::Bounded => 1,
+    WorldTopology::Torus => 2,
+  };
+}
+
+pub fn code_to_topology(code: u8) -> WorldTopology {
+  return match code {
+    0 => WorldTopology::Infinite,
+    1 => WorldTopology::Bounded,
+    2 => WorldTopology::Torus,
+    _ => panic!("Unknown topology code: {}", code),
+  };
+}
+
+pub fn create_default_options() -> Options {
+  return Options {
     batch_size: 10, // Can be controlled through --batch-size
     initial_miner_code: "".to_string(),
     mutation_rate_genes: 5.0,
     mutation_rate_slots: 5.0,
+    mutation_schedule: MutationSchedule::Fixed,
+    mutation_tau: 0.3,
+    mutation_decay: 0.95,
+    mutation_slot_swap: 5.0,
+    mutation_slot_rotate: 5.0,
+    mutation_slot_duplicate: 2.0,
+    mutation_slot_delete: 2.0,
     mutate_from_best: false,
     seed: 210114, // 0 is random. Can be set through --seed
     speed: 10,
+    snapshot_file: "".to_string(),
+    recipes_file: "".to_string(), // Can be set through --recipes
+    progression_file: "".to_string(), // Can be set through --progression
+    fitness: FitnessFunction::Points,
+    fitness_points: vec!(),
+    pareto: false, // Can be enabled through --pareto
+    pareto_objectives: vec!(FitnessFunction::Points, FitnessFunction::Ticks, FitnessFunction::ExploredArea, FitnessFunction::PhaseReached),
+    pareto_archive_size: 20, // Can be set through --pareto-size
+    islands: 1, // Can be set through --islands
+    island_migration_interval: 5, // Can be set through --island-migration
+    island_migrants: 1,
+    island_topology: IslandTopology::Ring, // Can be set through --island-topology
+    island_mutation_rates: vec!(), // Can be set through --island-rates
+    novelty: false, // Can be enabled through --novelty
+    novelty_k: 15,
+    novelty_fitness_weight: 0.0, // Can be set through --novelty-fitness
+    novelty_threshold: 1.0,
+    novelty_per_batch: 2,
+    novelty_archive_size: 500,
+    arena_size: 1, // Can be set through --arena-size
+    arena_spawn: ArenaSpawn::Origin, // Can be set through --arena-spawn
+    arena_spawn_radius: 20, // Can be set through --arena-spawn-radius
+    arena_conflict: ArenaConflict::Block, // Can be set through --arena-conflict
 
     cost_increase_rate: 1.0,
     cost_increase_interval: 1000, // bump the cost value every 1000 frames?
@@ -95,6 +216,18 @@ pub fn parse_cli_args() -> Options {
     sandrone_pickup_count: 200,
     sandcastle_area_limit: 500,
 
+    castle_ca_rule: "".to_string(), // Can be set through --castle-ca
+    castle_ca_interval: 10,
+    castle_ca_generations: 100,
+    castle_ca_energy_cost: 20.0,
+    castle_ca_reward: 1,
+
+    hunger_rate: 0.0, // Off by default so the original balance is kept. Can be set through --hunger-rate
+    actor_spawn_rates: vec!(), // Can be set through --actors
+
+    topology: WorldTopology::Infinite,
+    world_size: 50, // Can be set through --world-size
+
     html_mode: false,
 
     show_biomes: true,
@@ -113,6 +246,10 @@ pub fn parse_cli_args() -> Options {
     paint_bg_colors: true,
     paint_fg_colors: true,
   };
+}
+
+pub fn parse_cli_args() -> Options {
+  let mut options = create_default_options();
 
   let args: Vec<String> = env::args().collect();
   println!("{:?}", args);
@@ -140,9 +277,201 @@ pub fn parse_cli_args() -> Options {
           panic!("Seed must be a non-zero positive integer");
         }
       }
+      "--topology" => {
+        index += 1;
+        options.topology = match args[index].trim() {
+          "infinite" => WorldTopology::Infinite,
+          "bounded" => WorldTopology::Bounded,
+          "torus" => WorldTopology::Torus,
+          _ => panic!("Topology must be one of `infinite`, `bounded`, or `torus`"),
+        };
+      }
+      "--hunger-rate" => {
+        index += 1;
+        options.hunger_rate = args[index].trim().parse::<f32>().unwrap_or(-1.0);
+        if options.hunger_rate < 0.0 {
+          panic!("Hunger rate must be a non-negative number");
+        }
+      }
+      "--actors" => {
+        index += 1;
+        options.actor_spawn_rates = match parse_actor_spawn_rates(args[index].trim()) {
+          Ok(rates) => rates,
+          Err(msg) => panic!("{}", msg),
+        };
+      }
+      "--world-size" => {
+        index += 1;
+        options.world_size = args[index].trim().parse::<i32>().unwrap_or(0);
+        if options.world_size <= 5 {
+          panic!("World size must be an integer larger than 5");
+        }
+      }
+      "--biome" => {
+        index += 1;
+        options.snapshot_file = args[index].trim().to_string();
+      }
+      "--recipes" => {
+        index += 1;
+        options.recipes_file = args[index].trim().to_string();
+      }
+      "--progression" => {
+        index += 1;
+        options.progression_file = args[index].trim().to_string();
+      }
+      "--fitness" => {
+        index += 1;
+        options.fitness = match parse_fitness(args[index].trim()) {
+          Ok(f) => f,
+          Err(msg) => panic!("{}", msg),
+        };
+      }
+      "--fitness-points" => {
+        index += 1;
+        options.fitness_points = match parse_fitness_points(args[index].trim()) {
+          Ok(points) => points,
+          Err(msg) => panic!("{}", msg),
+        };
+      }
+      "--pareto" => {
+        index += 1;
+        options.pareto = true;
+        let mut objectives: Vec<FitnessFunction> = vec!();
+        for spec in args[index].split(',') {
+          match parse_fitness(spec.trim()) {
+            Ok(f) => objectives.push(f),
+            Err(msg) => panic!("{}", msg),
+          }
+        }
+        if objectives.len() < 2 {
+          panic!("Pareto selection needs at least two objectives");
+        }
+        options.pareto_objectives = objectives;
+      }
+      "--pareto-size" => {
+        index += 1;
+        options.pareto_archive_size = args[index].trim().parse::<usize>().unwrap_or(0);
+        if options.pareto_archive_size == 0 {
+          panic!("Pareto archive size must be a non-zero positive integer");
+        }
+      }
+      "--mutation" => {
+        index += 1;
+        options.mutation_schedule = match args[index].trim() {
+          "fixed" => MutationSchedule::Fixed,
+          "self" => MutationSchedule::SelfAdaptive,
+          "one-fifth" => MutationSchedule::OneFifth,
+          "decay" => MutationSchedule::Decay,
+          _ => panic!("Mutation schedule must be one of `fixed`, `self`, `one-fifth`, or `decay`"),
+        };
+      }
+      "--slot-operators" => {
+        index += 1;
+        // Like `swap=5,rotate=5,duplicate=2,delete=2`. Operators that are not listed keep their chance.
+        for part in args[index].split(',') {
+          let (name, chance) = match part.split_once('=') {
+            Some(x) => x,
+            None => panic!("Slot operators must be like `swap=5`, got `{}`", part),
+          };
+          let chance = chance.trim().parse::<f32>().expect("Slot operator chance must be a number");
+          match name.trim() {
+            "swap" => options.mutation_slot_swap = chance,
+            "rotate" => options.mutation_slot_rotate = chance,
+            "duplicate" => options.mutation_slot_duplicate = chance,
+            "delete" => options.mutation_slot_delete = chance,
+            _ => panic!("Slot operator must be one of `swap`, `rotate`, `duplicate`, or `delete`, got `{}`", name),
+          }
+        }
+      }
+      "--islands" => {
+        index += 1;
+        options.islands = args[index].trim().parse::<usize>().unwrap_or(0);
+        if options.islands == 0 {
+          panic!("Island count must be a non-zero positive integer");
+        }
+      }
+      "--island-migration" => {
+        index += 1;
+        options.island_migration_interval = args[index].trim().parse::<u32>().unwrap_or(0);
+        if options.island_migration_interval == 0 {
+          panic!("Island migration interval must be a non-zero positive integer");
+        }
+      }
+      "--island-topology" => {
+        index += 1;
+        options.island_topology = match args[index].trim() {
+          "ring" => IslandTopology::Ring,
+          "full" => IslandTopology::Full,
+          _ => panic!("Island topology must be one of `ring` or `full`"),
+        };
+      }
+      "--arena-size" => {
+        index += 1;
+        options.arena_size = args[index].trim().parse::<usize>().unwrap_or(0);
+        if options.arena_size == 0 {
+          panic!("Arena size must be a positive integer");
+        }
+      }
+      "--arena-spawn" => {
+        index += 1;
+        options.arena_spawn = match args[index].trim() {
+          "origin" => ArenaSpawn::Origin,
+          "spread" => ArenaSpawn::Spread,
+          _ => panic!("Arena spawn must be one of `origin` or `spread`"),
+        };
+      }
+      "--arena-spawn-radius" => {
+        index += 1;
+        options.arena_spawn_radius = args[index].trim().parse::<i32>().unwrap_or(-1);
+        if options.arena_spawn_radius < 0 {
+          panic!("Arena spawn radius must be a non-negative integer");
+        }
+      }
+      "--arena-conflict" => {
+        index += 1;
+        options.arena_conflict = match args[index].trim() {
+          "block" => ArenaConflict::Block,
+          "ram" => ArenaConflict::Ram,
+          _ => panic!("Arena conflict must be one of `block` or `ram`"),
+        };
+      }
+      "--island-rates" => {
+        index += 1;
+        // Like `5:5,10:2,1:10`, genes:slots per island
+        options.island_mutation_rates = args[index].split(',').map(|pair| {
+          match pair.split_once(':') {
+            Some((genes, slots)) => (
+              genes.trim().parse::<f32>().expect("Island gene mutation rate must be a number"),
+              slots.trim().parse::<f32>().expect("Island slot mutation rate must be a number"),
+            ),
+            None => panic!("Island mutation rates must be like `genes:slots`, got `{}`", pair),
+          }
+        }).collect();
+      }
+      "--novelty" => {
+        options.novelty = true;
+      }
+      "--novelty-fitness" => {
+        index += 1;
+        options.novelty = true;
+        options.novelty_fitness_weight = args[index].trim().parse::<f64>().unwrap_or(-1.0);
+        if options.novelty_fitness_weight < 0.0 {
+          panic!("Novelty fitness weight must be a positive number");
+        }
+      }
+      "--castle-ca" => {
+        index += 1;
+        options.castle_ca_rule = args[index].trim().to_string();
+        if let Err(msg) = parse_castle_ca_rule(&options.castle_ca_rule) {
+          panic!("{}", msg);
+        }
+      }
       "--miner" => {
         index += 1;
         options.initial_miner_code = args[index].trim().parse::<String>().unwrap_or("".to_string());
+        if let Err(msg) = parse_serialized_helix(&options.initial_miner_code) {
+          panic!("{}", msg);
+        }
       }
       _ => {
         println!("Unknown parameter: {}", args[index]);
@@ -153,6 +482,21 @@ pub fn parse_cli_args() -> Options {
     index = index + 1;
   }
 
+  if options.pareto && options.novelty {
+    panic!("Can not use Pareto selection and novelty search at the same time");
+  }
+  if options.islands > 1 && (options.pareto || options.novelty) {
+    panic!("Can not use islands together with Pareto selection or novelty search");
+  }
+  if options.arena_size > 1 && (options.pareto || options.novelty) {
+    // Their objectives and descriptors are not computed against the shared world of an arena
+    panic!("Can not use arenas together with Pareto selection or novelty search");
+  }
+  if options.islands > 1 && (options.batch_size as usize) % options.arena_size != 0 {
+    // Otherwise an arena would span two islands
+    panic!("With islands the batch size ({}) must be a multiple of the arena size ({})", options.batch_size, options.arena_size);
+  }
+
   options
 }
 
@@ -245,6 +589,14 @@ pub fn parse_input(key: String, options: &mut Options, state: &mut AppState, hma
       // Left
       state.viewport_offset_x -= 1;
     },
+    "s\n" => {
+      if cfg!(target_arch = "wasm32") {
+        bridge::log("Snapshots are written to a file, which the web version can not do");
+      } else {
+        state.save_snapshot = true;
+        bridge::log("Saving a snapshot of the visible biome...");
+      }
+    },
     "h\n" => {
       bridge::log("Centering viewport to 0x0");
       state.viewport_offset_x = -(state.viewport_size_w as i32)/2;
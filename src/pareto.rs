@@ -0,0 +1,153 @@
+use rand::distributions::{Distribution, Uniform};
+
+use super::app_state::*;
+use super::biome::*;
+use super::fitness::*;
+use super::helix::*;
+use super::options::*;
+
+// Multi-objective selection, NSGA-II style. Rather than collapsing the score of a miner into a
+// single number, every miner gets a vector of objectives (options.pareto_objectives), all of which
+// are maximized. A miner dominates another when it is at least as good in every objective and
+// better in at least one. The GA keeps an archive of miners that are not dominated by any other
+// miner seen so far (the front), so specialists that excel in only one objective survive too.
+//
+// When the front grows beyond options.pareto_archive_size, the most crowded entries (the ones
+// whose objectives are closest to their neighbours) are dropped to keep the front spread out.
+// The parent of the next batch is picked from the front by a binary tournament on crowding.
+
+#[derive(Clone)]
+pub struct ParetoEntry {
+  pub helix: Helix,
+  pub objectives: Vec<f64>,
+  // Fitness of the miner, for the mutation schedules (see mutation.rs)
+  pub fitness: u64,
+}
+
+pub fn get_objectives(options: &Options, biome: &Biome) -> Vec<f64> {
+  return options.pareto_objectives.iter().map(|f| eval_fitness(options, biome, f)).collect();
+}
+
+pub fn dominates(a: &Vec<f64>, b: &Vec<f64>) -> bool {
+  let mut better = false;
+  for i in 0..a.len() {
+    if a[i] < b[i] { return false; }
+    if a[i] > b[i] { better = true; }
+  }
+  return better;
+}
+
+pub fn non_dominated_sort(entries: &Vec<ParetoEntry>) -> Vec<Vec<usize>> {
+  // Returns the indexes of the entries grouped by front. Front zero is not dominated at all, front
+  // one is only dominated by front zero, etc.
+  let len = entries.len();
+  let mut dominated_by_count: Vec<usize> = vec![0; len];
+  let mut dominates_list: Vec<Vec<usize>> = vec![vec!(); len];
+  for i in 0..len {
+    for j in 0..len {
+      if i == j { continue; }
+      if dominates(&entries[i].objectives, &entries[j].objectives) {
+        dominates_list[i].push(j);
+      } else if dominates(&entries[j].objectives, &entries[i].objectives) {
+        dominated_by_count[i] += 1;
+      }
+    }
+  }
+
+  let mut fronts: Vec<Vec<usize>> = vec!();
+  let mut current: Vec<usize> = (0..len).filter(|&i| dominated_by_count[i] == 0).collect();
+  while current.len() > 0 {
+    let mut next: Vec<usize> = vec!();
+    for &i in current.iter() {
+      for &j in dominates_list[i].iter() {
+        dominated_by_count[j] -= 1;
+        if dominated_by_count[j] == 0 {
+          next.push(j);
+        }
+      }
+    }
+    fronts.push(current);
+    current = next;
+  }
+  return fronts;
+}
+
+pub fn crowding_distances(entries: &Vec<ParetoEntry>) -> Vec<f64> {
+  // The crowding distance of an entry is the sum, over all objectives, of the normalized distance
+  // between its two neighbours. The extremes of each objective get an infinite distance.
+  let len = entries.len();
+  let mut distances: Vec<f64> = vec![0.0; len];
+  if len == 0 { return distances; }
+  for o in 0..entries[0].objectives.len() {
+    let mut order: Vec<usize> = (0..len).collect();
+    order.sort_by(|&a, &b| entries[a].objectives[o].partial_cmp(&entries[b].objectives[o]).unwrap());
+    let min = entries[order[0]].objectives[o];
+    let max = entries[order[len - 1]].objectives[o];
+    distances[order[0]] = f64::INFINITY;
+    distances[order[len - 1]] = f64::INFINITY;
+    if max == min { continue; }
+    for n in 1..len - 1 {
+      distances[order[n]] += (entries[order[n + 1]].objectives[o] - entries[order[n - 1]].objectives[o]) / (max - min);
+    }
+  }
+  return distances;
+}
+
+pub fn update_pareto_front(options: &Options, state: &mut AppState, biomes: &Vec<Biome>) -> bool {
+  // Merge the miners of this batch into the front. Returns true if any of them made it in.
+  let old_len = state.pareto_front.len();
+  let mut entries: Vec<ParetoEntry> = state.pareto_front.drain(..).collect();
+  for biome in biomes.iter() {
+    entries.push(ParetoEntry { helix: biome.miner.helix, objectives: get_objectives(options, biome), fitness: get_fitness(options, biome) });
+  }
+
+  let fronts = non_dominated_sort(&entries);
+  // An entry with the same objectives as an older one does not add anything, so keep the older one
+  let mut front: Vec<usize> = vec!();
+  for &i in fronts[0].iter() {
+    if !front.iter().any(|&j| entries[j].objectives == entries[i].objectives) {
+      front.push(i);
+    }
+  }
+  front.sort();
+  let changed = front.iter().any(|&i| i >= old_len);
+
+  let mut kept: Vec<ParetoEntry> = front.iter().map(|&i| entries[i].clone()).collect();
+  while kept.len() > options.pareto_archive_size.max(1) {
+    // Drop the most crowded entry and recompute, since that changes the distance of its neighbours
+    let distances = crowding_distances(&kept);
+    let mut most_crowded = 0;
+    for i in 1..kept.len() {
+      if distances[i] < distances[most_crowded] {
+        most_crowded = i;
+      }
+    }
+    kept.remove(most_crowded);
+  }
+
+  state.pareto_front = kept;
+  return changed;
+}
+
+pub fn select_pareto_parent(state: &mut AppState) -> Helix {
+  // Binary tournament: pick two entries of the front and return the least crowded one
+  let distances = crowding_distances(&state.pareto_front);
+  let roller: Uniform<usize> = Uniform::from(0..state.pareto_front.len());
+  let a = roller.sample(&mut state.instance_rng_seeded);
+  let b = roller.sample(&mut state.instance_rng_seeded);
+  let parent = if distances[b] > distances[a] { b } else { a };
+  state.parent_fitness = state.pareto_front[parent].fitness;
+  return state.pareto_front[parent].helix;
+}
+
+pub fn ui_pareto_front(options: &Options, state: &AppState) -> String {
+  let names: Vec<String> = options.pareto_objectives.iter().map(|f| format!("{:?}", f)).collect();
+  let mut out = format!("Pareto front: {} miners. Objectives: [{}]\n", state.pareto_front.len(), names.join(", "));
+  for (i, entry) in state.pareto_front.iter().enumerate() {
+    let mut he: String = "".to_string();
+    helix_to_string(&mut he, &entry.helix);
+    let objectives: Vec<String> = entry.objectives.iter().map(|v| format!("{: >8}", v.round())).collect();
+    out.push_str(format!("- {: >2}: [{}] {}\n", i, objectives.join(", "), he).as_str());
+  }
+  return out;
+}
@@ -0,0 +1,460 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::options::*;
+use super::tile::*;
+use super::world::*;
+use serde_derive::{Serialize, Deserialize};
+
+// Dijkstra/A* over the world grid so drones follow real paths rather than phasing through walls.
+// Moves are 4-directional and every step pays the cost of the tile it steps into. Open tiles are
+// cheap. Walls are impassable or can be drilled at a cost that scales with the wall strength,
+// depending on the drone. The bounded world border is never passable.
+//
+// The search is confined to the box around the start and the targets, plus a margin, so an
+// unreachable target can not make it explore the (unbounded) world forever. A torus is finite so
+// there the search is not boxed, but steps are wrapped to their canonical coordinate and the
+// distances go the short way around. When the search fails
+// because the box cut it off, the margin is doubled and it searches again, up to
+// PATH_SEARCH_MAX_MARGIN. Every search also stops after PATH_SEARCH_NODE_BUDGET cells, so a huge
+// box around far away targets can't stall a tick either.
+//
+// A drone caches its path and the world.tile_version it was planned at. As long as no tile
+// changed it can keep following it. When a tile did change, only the remaining steps are checked
+// again. If they still cost the same the path is kept (a new shortcut may go unnoticed but the
+// path is never worse than planned), otherwise it is planned again. Likewise, a target that moves
+// a single cell only nudges the end of the path. A target that could not be reached is not
+// searched for again for PATH_REPLAN_DELAY calls, and after that only once a tile changed whether
+// it can be passed at all (world.passability_version). Water flowing or a wall getting weaker
+// happens all the time and does not open up a way.
+
+// How far beyond the start/target box the search may wander to get around obstacles, at first
+pub const PATH_SEARCH_MARGIN: i32 = 10;
+// The margin is doubled while the box cut the search off, up to this
+pub const PATH_SEARCH_MAX_MARGIN: i32 = 160;
+// Max number of cells a single search may expand
+pub const PATH_SEARCH_NODE_BUDGET: usize = 50_000;
+// After a search failed, don't search for the same target again for this many calls
+pub const PATH_REPLAN_DELAY: u32 = 20;
+
+pub struct PathCosts {
+  // Cost of stepping into an open cell
+  pub open: u32,
+  // Cost per wall strength of drilling through a wall. None means walls are impassable.
+  pub wall: Option<u32>,
+  // Cost of crossing the push and impassible tiles of a sand castle. None means impassable.
+  pub castle: Option<u32>,
+}
+
+// The windrone flies around walls and castles
+pub const WINDRONE_PATH_COSTS: PathCosts = PathCosts { open: 1, wall: None, castle: None };
+// A drone of the drone launcher walks back to the miner through the tunnels (see drone_me.rs)
+pub const ME_DRONE_PATH_COSTS: PathCosts = PathCosts { open: 1, wall: None, castle: None };
+// The sandrone drills through walls and moves freely over its own castle
+pub const SANDRONE_PATH_COSTS: PathCosts = PathCosts { open: 1, wall: Some(5), castle: Some(1) };
+
+// Steps of a path with the cost they were planned at, last step first so pop() gives the next one
+pub type PathSteps = Vec<(i32, i32, u32)>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CachedPath {
+  pub to_x: i32,
+  pub to_y: i32,
+  // The world.tile_version at which the steps were last known to be valid
+  pub tile_version: u32,
+  pub steps: PathSteps,
+  // There was no way to the target at this passability_version (see wait_before_replanning)
+  pub unreachable: bool,
+  pub passability_version: u32,
+  // Calls left before searching for the unreachable target again
+  pub replan_delay: u32,
+}
+
+pub fn get_tile_path_cost(options: &Options, world: &World, costs: &PathCosts, x: i32, y: i32) -> Option<u32> {
+  if is_world_border(options, x, y) {
+    return None;
+  }
+  let tile = get_cell_tile_at(options, world, x, y);
+  if is_open_tile(tile) {
+    return Some(costs.open);
+  }
+  return match tile {
+    Tile::Wall1 => costs.wall,
+    Tile::Wall2 => costs.wall.map(|c| c * 2),
+    Tile::Wall3 => costs.wall.map(|c| c * 3),
+    Tile::Wall4 => costs.wall.map(|c| c * 4),
+    Tile::Push | Tile::Impassible => costs.castle,
+    _ => None,
+  };
+}
+
+pub fn find_path(options: &Options, world: &World, costs: &PathCosts, from_x: i32, from_y: i32, to_x: i32, to_y: i32) -> Option<PathSteps> {
+  return find_path_to_nearest(options, world, costs, from_x, from_y, &vec!((to_x, to_y))).map(|(_, steps)| steps);
+}
+
+pub fn find_path_to_nearest(options: &Options, world: &World, costs: &PathCosts, from_x: i32, from_y: i32, targets: &Vec<(i32, i32)>) -> Option<(usize, PathSteps)> {
+  // Returns the index of the cheapest target to reach and the steps to get there. With one target
+  // this is A* with a manhattan heuristic, with more targets the heuristic is dropped and it's a
+  // multi target Dijkstra.
+  // Ties are broken on the coordinate so the outcome does not depend on the heap internals.
+  if targets.len() == 0 {
+    return None;
+  }
+
+  let mut margin = PATH_SEARCH_MARGIN;
+  loop {
+    let (found, clipped) = find_path_in_box(options, world, costs, from_x, from_y, targets, margin);
+    if found.is_some() || !clipped || margin >= PATH_SEARCH_MAX_MARGIN {
+      return found;
+    }
+    margin *= 2;
+  }
+}
+
+pub fn get_path_distance(options: &Options, ax: i32, ay: i32, bx: i32, by: i32) -> i32 {
+  // Manhattan distance, the short way around on a torus
+  let (ax, ay) = wrap_coord(options, ax, ay);
+  let (bx, by) = wrap_coord(options, bx, by);
+  let dx = (ax - bx).abs();
+  let dy = (ay - by).abs();
+  if options.topology == WorldTopology::Torus {
+    let period = options.world_size * 2 + 1;
+    return dx.min(period - dx) + dy.min(period - dy);
+  }
+  return dx + dy;
+}
+
+fn find_path_in_box(options: &Options, world: &World, costs: &PathCosts, from_x: i32, from_y: i32, targets: &Vec<(i32, i32)>, margin: i32) -> (Option<(usize, PathSteps)>, bool) {
+  // See find_path_to_nearest. Also returns whether the box or the node budget cut the search
+  // short, in which case a bigger box may still find a way.
+  let (from_x, from_y) = wrap_coord(options, from_x, from_y);
+  let targets: Vec<(i32, i32)> = targets.iter().map(|&(tx, ty)| wrap_coord(options, tx, ty)).collect();
+  let boxed = options.topology != WorldTopology::Torus;
+  let mut min_x = from_x;
+  let mut min_y = from_y;
+  let mut max_x = from_x;
+  let mut max_y = from_y;
+  for &(tx, ty) in targets.iter() {
+    min_x = min_x.min(tx);
+    min_y = min_y.min(ty);
+    max_x = max_x.max(tx);
+    max_y = max_y.max(ty);
+  }
+  min_x -= margin;
+  min_y -= margin;
+  max_x += margin;
+  max_y += margin;
+
+  let heuristic = |x: i32, y: i32| -> u32 {
+    if targets.len() == 1 {
+      return (get_path_distance(options, targets[0].0, targets[0].1, x, y) as u32) * costs.open;
+    }
+    return 0;
+  };
+
+  // Best known cost to each cell and the cell it was reached from, plus the cost of that step
+  let mut best: HashMap<(i32, i32), (u32, (i32, i32), u32)> = HashMap::new();
+  let mut queue: BinaryHeap<Reverse<(u32, i32, i32)>> = BinaryHeap::new();
+  best.insert((from_x, from_y), (0, (from_x, from_y), 0));
+  queue.push(Reverse((heuristic(from_x, from_y), from_x, from_y)));
+  let mut clipped = false;
+  let mut expanded = 0;
+
+  while let Some(Reverse((_, x, y))) = queue.pop() {
+    if let Some(target_index) = targets.iter().position(|&(tx, ty)| tx == x && ty == y) {
+      let mut steps: PathSteps = vec!();
+      let mut cur = (x, y);
+      while cur != (from_x, from_y) {
+        let (_, prev, step_cost) = best[&cur];
+        steps.push((cur.0, cur.1, step_cost));
+        cur = prev;
+      }
+      return (Some((target_index, steps)), clipped);
+    }
+
+    expanded += 1;
+    if expanded > PATH_SEARCH_NODE_BUDGET {
+      // Give up. A bigger box would only be more expensive.
+      return (None, false);
+    }
+
+    let cost = best[&(x, y)].0;
+    for &(dx, dy) in [(-1, 0), (0, -1), (1, 0), (0, 1)].iter() {
+      let (nx, ny) = wrap_coord(options, x + dx, y + dy);
+      if boxed && (nx < min_x || nx > max_x || ny < min_y || ny > max_y) {
+        clipped = true;
+        continue;
+      }
+      let step_cost = match get_tile_path_cost(options, world, costs, nx, ny) {
+        Some(step_cost) => step_cost,
+        None => continue,
+      };
+      let next_cost = cost + step_cost;
+      let improved = match best.get(&(nx, ny)) {
+        Some(&(known, ..)) => next_cost < known,
+        None => true,
+      };
+      if improved {
+        best.insert((nx, ny), (next_cost, (x, y), step_cost));
+        queue.push(Reverse((next_cost + heuristic(nx, ny), nx, ny)));
+      }
+    }
+  }
+
+  return (None, clipped);
+}
+
+pub fn is_cached_path_valid(options: &Options, world: &World, costs: &PathCosts, path: &mut CachedPath, from_x: i32, from_y: i32, to_x: i32, to_y: i32) -> bool {
+  // Is the cached path still a way from here to there? Updates the tile version when the tiles
+  // changed without affecting the path.
+  if path.to_x != to_x || path.to_y != to_y {
+    return false;
+  }
+  let (nx, ny, _) = match path.steps.last() {
+    Some(&step) => step,
+    None => return false,
+  };
+  if get_path_distance(options, nx, ny, from_x, from_y) != 1 {
+    // The drone was moved by something else
+    return false;
+  }
+  if path.tile_version != world.tile_version {
+    for &(x, y, step_cost) in path.steps.iter() {
+      if get_tile_path_cost(options, world, costs, x, y) != Some(step_cost) {
+        return false;
+      }
+    }
+    path.tile_version = world.tile_version;
+  }
+  return true;
+}
+
+pub fn retarget_cached_path(options: &Options, world: &World, costs: &PathCosts, path: &mut CachedPath, to_x: i32, to_y: i32) {
+  // A moving target (like the miner) rarely goes far in one tick. Rather than planning a whole new
+  // path, cut the path short when the target moved onto it, or add one step when it moved next to
+  // the end of it. Anything else is left for is_cached_path_valid to reject.
+  if path.steps.len() == 0 || (path.to_x == to_x && path.to_y == to_y) {
+    return;
+  }
+  let (step_x, step_y) = wrap_coord(options, to_x, to_y);
+  if let Some(i) = path.steps.iter().position(|&(x, y, _)| x == step_x && y == step_y) {
+    path.steps.drain(0..i);
+  } else if get_path_distance(options, to_x, to_y, path.to_x, path.to_y) == 1 {
+    match get_tile_path_cost(options, world, costs, step_x, step_y) {
+      Some(step_cost) => path.steps.insert(0, (step_x, step_y, step_cost)),
+      None => return,
+    }
+  } else {
+    return;
+  }
+  path.to_x = to_x;
+  path.to_y = to_y;
+}
+
+pub fn cache_path(world: &World, path: &mut CachedPath, to_x: i32, to_y: i32, steps: PathSteps) {
+  path.to_x = to_x;
+  path.to_y = to_y;
+  path.tile_version = world.tile_version;
+  path.passability_version = world.passability_version;
+  path.steps = steps;
+  path.unreachable = false;
+  path.replan_delay = 0;
+}
+
+pub fn mark_cached_path_unreachable(world: &World, path: &mut CachedPath, to_x: i32, to_y: i32) {
+  cache_path(world, path, to_x, to_y, vec!());
+  path.unreachable = true;
+  path.replan_delay = PATH_REPLAN_DELAY;
+}
+
+pub fn wait_before_replanning(world: &World, path: &mut CachedPath, to_x: i32, to_y: i32) -> bool {
+  // Did the last search for this target fail? Then wait for the delay to run out and for a tile to
+  // change passability before searching again. Every call counts down the delay.
+  if !path.unreachable || path.to_x != to_x || path.to_y != to_y {
+    return false;
+  }
+  if path.replan_delay > 0 {
+    path.replan_delay -= 1;
+    return true;
+  }
+  return path.passability_version == world.passability_version;
+}
+
+pub fn next_path_step(options: &Options, world: &World, costs: &PathCosts, path: &mut CachedPath, from_x: i32, from_y: i32, to_x: i32, to_y: i32) -> Option<(i32, i32)> {
+  // Returns the next cell to step into on the way to the target, planning a new path if the cached
+  // one is no longer valid. None when there is no way to get there.
+  if wait_before_replanning(world, path, to_x, to_y) {
+    return None;
+  }
+  retarget_cached_path(options, world, costs, path, to_x, to_y);
+  if !is_cached_path_valid(options, world, costs, path, from_x, from_y, to_x, to_y) {
+    match find_path(options, world, costs, from_x, from_y, to_x, to_y) {
+      Some(steps) => cache_path(world, path, to_x, to_y, steps),
+      None => {
+        mark_cached_path_unreachable(world, path, to_x, to_y);
+        return None;
+      }
+    }
+  }
+  return path.steps.pop().map(|(x, y, _)| (x, y));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn create_open_world(size: i32) -> (Options, World) {
+    // A bounded world with nothing but empty cells inside the border
+    return create_open_world_with_topology(size, WorldTopology::Bounded);
+  }
+
+  fn create_open_world_with_topology(size: i32, topology: WorldTopology) -> (Options, World) {
+    let mut options = create_default_options();
+    options.topology = topology;
+    options.world_size = size;
+    let mut world = generate_world(&options);
+    for y in -size..=size {
+      for x in -size..=size {
+        ensure_cell_in_world(&mut world, &options, x, y);
+        set_cell_tile_at(&options, &mut world, x, y, Tile::Empty);
+      }
+    }
+    return (options, world);
+  }
+
+  fn set_wall_column(options: &Options, world: &mut World, x: i32, from_y: i32, to_y: i32) {
+    for y in from_y..=to_y {
+      set_cell_tile_at(options, world, x, y, Tile::Wall1);
+    }
+  }
+
+  #[test]
+  fn find_path_in_the_open_is_a_straight_line() {
+    let (options, world) = create_open_world(6);
+    let steps = find_path(&options, &world, &WINDRONE_PATH_COSTS, -3, 0, 3, 0).unwrap();
+    assert_eq!(steps.len(), 6);
+    // Last step first
+    assert_eq!((steps[0].0, steps[0].1), (3, 0));
+    assert_eq!((steps[5].0, steps[5].1), (-2, 0));
+  }
+
+  #[test]
+  fn find_path_goes_around_walls() {
+    let (options, mut world) = create_open_world(6);
+    set_wall_column(&options, &mut world, 0, -6, 4);
+    let steps = find_path(&options, &world, &WINDRONE_PATH_COSTS, -3, 0, 3, 0).unwrap();
+    assert!(steps.iter().all(|&(x, y, _)| is_open_tile(get_cell_tile_at(&options, &world, x, y))));
+    // Down to the gap at y=5 and back up
+    assert_eq!(steps.len(), 6 + 2 * 5);
+  }
+
+  #[test]
+  fn find_path_does_not_cross_a_closed_wall_unless_it_can_drill() {
+    let (options, mut world) = create_open_world(6);
+    set_wall_column(&options, &mut world, 0, -6, 6);
+    assert!(find_path(&options, &world, &WINDRONE_PATH_COSTS, -3, 0, 3, 0).is_none());
+    let steps = find_path(&options, &world, &SANDRONE_PATH_COSTS, -3, 0, 3, 0).unwrap();
+    assert_eq!(steps.len(), 6);
+    assert_eq!(steps.iter().filter(|&&(_, _, cost)| cost == 5).count(), 1);
+  }
+
+  #[test]
+  fn find_path_widens_the_search_for_far_detours() {
+    // The only gap is further away than PATH_SEARCH_MARGIN
+    let (options, mut world) = create_open_world(30);
+    set_wall_column(&options, &mut world, 0, -30, 25);
+    let steps = find_path(&options, &world, &WINDRONE_PATH_COSTS, -1, 0, 1, 0).unwrap();
+    assert_eq!(steps.len(), 2 + 2 * 26);
+  }
+
+  #[test]
+  fn find_path_to_nearest_picks_the_cheapest_target() {
+    let (options, world) = create_open_world(6);
+    let (index, steps) = find_path_to_nearest(&options, &world, &WINDRONE_PATH_COSTS, 0, 0, &vec!((5, 5), (-2, 1), (0, -4))).unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(steps.len(), 3);
+  }
+
+  #[test]
+  fn cached_path_survives_unrelated_tile_changes() {
+    let (options, mut world) = create_open_world(6);
+    let mut path = CachedPath::default();
+    let steps = find_path(&options, &world, &WINDRONE_PATH_COSTS, -3, 0, 3, 0).unwrap();
+    cache_path(&world, &mut path, 3, 0, steps);
+    assert!(is_cached_path_valid(&options, &world, &WINDRONE_PATH_COSTS, &mut path, -3, 0, 3, 0));
+
+    set_cell_tile_at(&options, &mut world, 0, 3, Tile::Wall2);
+    assert!(is_cached_path_valid(&options, &world, &WINDRONE_PATH_COSTS, &mut path, -3, 0, 3, 0));
+    assert_eq!(path.tile_version, world.tile_version);
+  }
+
+  #[test]
+  fn cached_path_is_invalidated_by_a_wall_on_it() {
+    let (options, mut world) = create_open_world(6);
+    let mut path = CachedPath::default();
+    let steps = find_path(&options, &world, &WINDRONE_PATH_COSTS, -3, 0, 3, 0).unwrap();
+    cache_path(&world, &mut path, 3, 0, steps);
+
+    set_cell_tile_at(&options, &mut world, 1, 0, Tile::Wall1);
+    assert!(!is_cached_path_valid(&options, &world, &WINDRONE_PATH_COSTS, &mut path, -3, 0, 3, 0));
+
+    // next_path_step plans a new path around it
+    let (x, y) = next_path_step(&options, &world, &WINDRONE_PATH_COSTS, &mut path, -3, 0, 3, 0).unwrap();
+    assert_eq!((x + 3).abs() + y.abs(), 1);
+    assert!(path.steps.iter().all(|&(x, y, _)| (x, y) != (1, 0)));
+  }
+
+  #[test]
+  fn cached_path_is_invalidated_when_moved_or_retargeted() {
+    let (options, world) = create_open_world(6);
+    let mut path = CachedPath::default();
+    let steps = find_path(&options, &world, &WINDRONE_PATH_COSTS, -3, 0, 3, 0).unwrap();
+    cache_path(&world, &mut path, 3, 0, steps);
+    assert!(!is_cached_path_valid(&options, &world, &WINDRONE_PATH_COSTS, &mut path, -3, 2, 3, 0));
+    assert!(!is_cached_path_valid(&options, &world, &WINDRONE_PATH_COSTS, &mut path, -3, 0, 3, 3));
+  }
+
+  #[test]
+  fn unreachable_target_is_not_searched_again_until_a_tile_changes() {
+    let (options, mut world) = create_open_world(6);
+    set_wall_column(&options, &mut world, 0, -6, 6);
+    let mut path = CachedPath::default();
+    assert!(next_path_step(&options, &world, &WINDRONE_PATH_COSTS, &mut path, -3, 0, 3, 0).is_none());
+    assert!(path.unreachable);
+
+    // Water flowing in or a wall getting weaker does not open a way
+    path.replan_delay = 0;
+    set_cell_tile_at(&options, &mut world, -5, 0, Tile::ExpandoWater);
+    set_cell_tile_at(&options, &mut world, 0, 0, Tile::Wall3);
+    assert!(wait_before_replanning(&world, &mut path, 3, 0));
+
+    set_cell_tile_at(&options, &mut world, 0, 6, Tile::Empty);
+    assert!(!wait_before_replanning(&world, &mut path, 3, 0));
+    assert!(next_path_step(&options, &world, &WINDRONE_PATH_COSTS, &mut path, -3, 0, 3, 0).is_some());
+    assert!(!path.unreachable);
+  }
+
+  #[test]
+  fn find_path_wraps_around_a_torus() {
+    // The only way is across the seam, which is 7 steps rather than 6 through the wall
+    let (options, mut world) = create_open_world_with_topology(6, WorldTopology::Torus);
+    set_wall_column(&options, &mut world, 0, -6, 6);
+    let steps = find_path(&options, &world, &WINDRONE_PATH_COSTS, -3, 0, 3, 0).unwrap();
+    assert_eq!(steps.len(), 7);
+    assert!(steps.iter().all(|&(x, y, _)| x.abs() <= 6 && y.abs() <= 6));
+    assert!(steps.iter().any(|&(x, _, _)| x == 6));
+    assert!(steps.iter().any(|&(x, _, _)| x == -6));
+  }
+
+  #[test]
+  fn path_distance_goes_the_short_way_around_a_torus() {
+    let mut options = create_default_options();
+    options.world_size = 6;
+    options.topology = WorldTopology::Torus;
+    assert_eq!(get_path_distance(&options, -6, 0, 6, 0), 1);
+    assert_eq!(get_path_distance(&options, -5, -6, 5, 6), 4);
+    assert_eq!(get_path_distance(&options, 7, 0, -6, 0), 0);
+    options.topology = WorldTopology::Bounded;
+    assert_eq!(get_path_distance(&options, -6, 0, 6, 0), 12);
+  }
+}
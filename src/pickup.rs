@@ -15,6 +15,36 @@ pub enum Pickup {
   Wood,
 }
 
+pub fn pickup_to_code(pickup: Pickup) -> u8 {
+  // Stable numeric code for (de)serialization. Do not change existing codes.
+  return match pickup {
+    Pickup::Diamond => 0,
+    Pickup::Nothing => 1,
+    Pickup::Energy => 2,
+    Pickup::Stone => 3,
+    Pickup::Expando => 4,
+    Pickup::Fountain => 5,
+    Pickup::Wind => 6,
+    Pickup::Water => 7,
+    Pickup::Wood => 8,
+  };
+}
+
+pub fn code_to_pickup(code: u8) -> Pickup {
+  return match code {
+    0 => Pickup::Diamond,
+    1 => Pickup::Nothing,
+    2 => Pickup::Energy,
+    3 => Pickup::Stone,
+    4 => Pickup::Expando,
+    5 => Pickup::Fountain,
+    6 => Pickup::Wind,
+    7 => Pickup::Water,
+    8 => Pickup::Wood,
+    _ => panic!("Unknown pickup code: {}", code),
+  };
+}
+
 pub fn pickup_to_priority(pickup: Pickup) -> u32 {
   return match pickup {
     Pickup::Diamond => 100,
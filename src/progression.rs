@@ -0,0 +1,176 @@
+use std::fs;
+
+use super::app_state::*;
+use super::biome::*;
+use super::drone_san::*;
+use super::fitness::*;
+use super::inventory::*;
+use super::miner::*;
+use super::slottable::*;
+use serde_derive::{Serialize, Deserialize};
+
+// Progression is data driven, like crafting. The progression is a graph of milestones. A milestone
+// is reached once the milestones it comes after are reached and all its conditions hold, at which
+// point its effects are applied once. Every miner tick the milestones are checked in order, so a
+// milestone listed after its prerequisites can be reached in the same tick.
+//
+// All phases, up to running out of energy, are set by milestones. The sandrone does not set the
+// castle phases itself, it raises castle events (see raise_castle_event) and milestones turn those
+// into phases with the CastleEvent condition and the SetPhase effect. The built-in progression (see
+// default_progression) is the original windrone, sandrone, castle chain with a few side paths. It
+// can be replaced by a json file through --progression. That file contains an array of milestones,
+// like:
+//
+// [{
+//   "name": "sandrone",
+//   "after": [],
+//   "conditions": [{"HasSlot": "Sandrone"}],
+//   "effects": [{"SetPhase": "HasSandrone_2"}, {"AddEnergy": 500.0}]
+// }]
+//
+// Every miner records the milestones it reached and its phase changes, with the tick they happened
+// at, for analysis (see meta.milestones and meta.phase_history). Both are part of a biome snapshot.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Milestone {
+  pub name: String,
+  // Names of the milestones that must be reached before this one
+  pub after: Vec<String>,
+  pub conditions: Vec<MilestoneCondition>,
+  pub effects: Vec<MilestoneEffect>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MilestoneCondition {
+  // The miner has a slot of this kind
+  HasSlot(SlotKind),
+  // The miner is in this phase or a later one (ignoring running out of energy)
+  Phase(Phase),
+  // The miner has at least this many points
+  MinPoints(u64),
+  // The miner has at least this many of the resource
+  MinResource(Resource, u32),
+  // The miner has been going for at least this many ticks
+  MinTicks(u32),
+  // The miner ate at least this many food (see hunger.rs)
+  MinEaten(u32),
+  // The sandrone raised this castle event
+  CastleEvent(CastleEvent),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MilestoneEffect {
+  // Move the miner to this phase, unless it's already further. Not OutOfEnergy_7, that's for the
+  // miner to decide.
+  SetPhase(Phase),
+  // Restore this much energy, up to the max energy
+  AddEnergy(f32),
+  // Add this to the inventory
+  AddResource(Resource, u32),
+}
+
+pub fn default_progression() -> Vec<Milestone> {
+  let milestone = |name: &str, after: Vec<&str>, conditions: Vec<MilestoneCondition>, effects: Vec<MilestoneEffect>| Milestone {
+    name: name.to_string(),
+    after: after.iter().map(|s| s.to_string()).collect(),
+    conditions,
+    effects,
+  };
+
+  return vec!(
+    // The castle path
+    milestone("windrone", vec!(), vec!(MilestoneCondition::HasSlot(SlotKind::Windrone)), vec!(MilestoneEffect::SetPhase(Phase::HasWindrone_1))),
+    milestone("sandrone", vec!(), vec!(MilestoneCondition::HasSlot(SlotKind::Sandrone)), vec!(MilestoneEffect::SetPhase(Phase::HasSandrone_2))),
+    milestone("castle walls", vec!("sandrone"), vec!(MilestoneCondition::CastleEvent(CastleEvent::WallsFinished)), vec!(MilestoneEffect::SetPhase(Phase::FinishedCastleWalls_3))),
+    milestone("airlifted", vec!("castle walls"), vec!(MilestoneCondition::CastleEvent(CastleEvent::MinerPickedUp)), vec!(MilestoneEffect::SetPhase(Phase::PickedUpMiner_4))),
+    milestone("filling castle", vec!("airlifted"), vec!(MilestoneCondition::CastleEvent(CastleEvent::MinerDelivered)), vec!(MilestoneEffect::SetPhase(Phase::FillingCastle_5))),
+    milestone("castle filled", vec!("filling castle"), vec!(MilestoneCondition::CastleEvent(CastleEvent::CastleFilled)), vec!(MilestoneEffect::SetPhase(Phase::FilledCastle_6))),
+    // Side paths. They have no effects so the original balance is kept, but they show up in the history.
+    milestone("drone launcher", vec!(), vec!(MilestoneCondition::HasSlot(SlotKind::DroneLauncher)), vec!()),
+    milestone("rich", vec!(), vec!(MilestoneCondition::MinPoints(10000)), vec!()),
+    milestone("farmer", vec!(), vec!(MilestoneCondition::MinEaten(5)), vec!()),
+    milestone("castle farmer", vec!("castle filled", "farmer"), vec!(), vec!()),
+  );
+}
+
+pub fn load_progression(file: &str) -> Vec<Milestone> {
+  let s = fs::read_to_string(file).expect("Unable to read progression file");
+  let milestones: Vec<Milestone> = serde_json::from_str(&s).expect("Unable to parse progression file");
+  if let Err(msg) = validate_progression(&milestones) {
+    panic!("Invalid progression in `{}`: {}", file, msg);
+  }
+  return milestones;
+}
+
+fn validate_progression(milestones: &[Milestone]) -> Result<(), String> {
+  for (n, milestone) in milestones.iter().enumerate() {
+    if milestones[..n].iter().any(|m| m.name == milestone.name) {
+      return Err(format!("Milestone `{}` is listed twice", milestone.name));
+    }
+    for name in milestone.after.iter() {
+      if !milestones.iter().any(|m| &m.name == name) {
+        return Err(format!("Milestone `{}` comes after unknown milestone `{}`", milestone.name, name));
+      }
+    }
+    for effect in milestone.effects.iter() {
+      if let MilestoneEffect::SetPhase(phase) = effect {
+        if matches!(phase, Phase::OutOfEnergy_7) {
+          return Err(format!("Milestone `{}` can not set phase {:?}, only running out of energy does that", milestone.name, phase));
+        }
+      }
+    }
+  }
+  return Ok(());
+}
+
+fn has_reached_milestone(meta: &MinerMeta, name: &str) -> bool {
+  return meta.milestones.iter().any(|(reached, _)| reached == name);
+}
+
+fn is_milestone_condition_met(biome: &Biome, condition: &MilestoneCondition) -> bool {
+  let miner = &biome.miner;
+  return match condition {
+    MilestoneCondition::HasSlot(kind) => miner.slots.iter().any(|slot| slot.kind as usize == *kind as usize),
+    MilestoneCondition::Phase(phase) => get_phase_reached(&miner.meta) as u32 >= *phase as u32,
+    MilestoneCondition::MinPoints(points) => get_points(&miner.meta.inventory) >= *points,
+    MilestoneCondition::MinResource(resource, amount) => inventory_get(&miner.meta.inventory, *resource) >= *amount,
+    MilestoneCondition::MinTicks(ticks) => biome.ticks >= *ticks,
+    MilestoneCondition::MinEaten(eaten) => miner.meta.eaten >= *eaten,
+    MilestoneCondition::CastleEvent(event) => miner.meta.castle_events.iter().any(|(raised, _)| raised == event),
+  };
+}
+
+fn apply_milestone_effect(biome: &mut Biome, effect: &MilestoneEffect) {
+  match effect {
+    MilestoneEffect::SetPhase(phase) => {
+      if (biome.miner.meta.phase as u32) < *phase as u32 {
+        set_miner_phase(&mut biome.miner.meta, *phase, biome.ticks);
+      }
+    }
+    MilestoneEffect::AddEnergy(energy) => {
+      biome.miner.movable.now_energy = (biome.miner.movable.now_energy + energy).min(biome.miner.meta.max_energy);
+    }
+    MilestoneEffect::AddResource(resource, amount) => {
+      inventory_add(&mut biome.miner.meta.inventory, *resource, *amount);
+    }
+  }
+}
+
+pub fn tick_progression(state: &AppState, biome: &mut Biome) {
+  for milestone in state.progression.iter() {
+    if has_reached_milestone(&biome.miner.meta, &milestone.name)
+      || !milestone.after.iter().all(|name| has_reached_milestone(&biome.miner.meta, name))
+      || !milestone.conditions.iter().all(|condition| is_milestone_condition_met(biome, condition)) {
+      continue;
+    }
+    biome.miner.meta.milestones.push((milestone.name.clone(), biome.ticks));
+    for effect in milestone.effects.iter() {
+      apply_milestone_effect(biome, effect);
+    }
+  }
+}
+
+pub fn ui_progression(biome: &Biome) -> String {
+  let parts: Vec<String> = biome.miner.meta.milestones.iter().map(|(name, ticks)| format!("{} @{}", name, ticks)).collect();
+  return format!("Milestones: {}", if parts.is_empty() { "-".to_string() } else { parts.join(", ") });
+}
@@ -0,0 +1,314 @@
+use std::fs;
+
+use super::app_state::*;
+use super::biome::*;
+use super::drone_san::*;
+use super::drone_win::*;
+use super::inventory::*;
+use super::miner::*;
+use super::slot_sandrone::*;
+use super::slot_windrone::*;
+use super::slottable::*;
+use serde_derive::{Serialize, Deserialize};
+
+// Crafting is data driven. A recipe lists what it costs, what it produces, and under which
+// conditions it can be crafted. Every miner tick the first recipe that can be crafted is crafted,
+// so at most one thing is crafted per tick and the order of the recipes is their priority.
+//
+// The built-in recipes (see default_recipes) can be replaced by a json file through --recipes.
+// That file contains an array of recipes, like:
+//
+// [{
+//   "name": "windrone",
+//   "inputs": [
+//     {"resources": ["Wood"], "amount": 5, "min": 6},
+//     {"resources": ["StoneWhite", "StoneBlue", "StoneGreen", "StoneYellow"], "amount": 5, "min": 6}
+//   ],
+//   "output": {"Drone": "Windrone"},
+//   "preconditions": [{"MissingSlot": "Windrone"}]
+// }]
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recipe {
+  pub name: String,
+  pub inputs: Vec<RecipeInput>,
+  pub output: RecipeOutput,
+  pub preconditions: Vec<RecipePrecondition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeInput {
+  // Resources that may be used for this input. The amount is taken from them in this order, so
+  // put the cheapest first.
+  pub resources: Vec<Resource>,
+  pub amount: u32,
+  // Only take the input when at least this much is available. Defaults to the amount.
+  pub min: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecipeOutput {
+  // Put an item of this kind into the first empty slot
+  Slot(SlotKind),
+  // Construct the drone of this kind (Windrone or Sandrone) and put it into the first empty slot
+  Drone(SlotKind),
+  // Add this to the inventory
+  Consumable { resource: Resource, amount: u32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecipePrecondition {
+  // The miner must not have a slot of this kind yet
+  MissingSlot(SlotKind),
+  // The miner must already have a slot of this kind
+  HasSlot(SlotKind),
+  // The miner must have at least this much energy
+  MinEnergy(f32),
+  // The miner must have fewer than this many of the resource. Keeps consumables from eating up
+  // everything.
+  LessThan(Resource, u32),
+}
+
+pub fn default_recipes() -> Vec<Recipe> {
+  return vec!(
+    Recipe {
+      name: "windrone".to_string(),
+      inputs: vec!(
+        // The drones need one more of everything than they cost
+        RecipeInput { resources: vec!(Resource::Wood), amount: 5, min: Some(6) },
+        RecipeInput { resources: vec!(Resource::StoneWhite, Resource::StoneBlue, Resource::StoneGreen, Resource::StoneYellow), amount: 5, min: Some(6) },
+      ),
+      output: RecipeOutput::Drone(SlotKind::Windrone),
+      preconditions: vec!(RecipePrecondition::MissingSlot(SlotKind::Windrone)),
+    },
+    Recipe {
+      name: "sandrone".to_string(),
+      inputs: vec!(
+        RecipeInput { resources: vec!(Resource::Wood), amount: 5, min: Some(6) },
+        RecipeInput { resources: vec!(Resource::Water), amount: 10, min: Some(11) },
+        // Do not allow the white stones. Only use more expensive stones to build a sandrone.
+        RecipeInput { resources: vec!(Resource::StoneBlue, Resource::StoneGreen, Resource::StoneYellow), amount: 5, min: Some(6) },
+      ),
+      output: RecipeOutput::Drone(SlotKind::Sandrone),
+      preconditions: vec!(RecipePrecondition::MissingSlot(SlotKind::Sandrone)),
+    },
+    // Consumables come last so they do not take the materials for the drones in the same tick. The
+    // ones that share materials with a drone wait for that drone to be built. See consumable.rs.
+    Recipe {
+      name: "ration".to_string(),
+      inputs: vec!(
+        RecipeInput { resources: vec!(Resource::Food), amount: 3, min: None },
+      ),
+      output: RecipeOutput::Consumable { resource: Resource::Ration, amount: 1 },
+      preconditions: vec!(RecipePrecondition::LessThan(Resource::Ration, 2)),
+    },
+    Recipe {
+      name: "dynamite".to_string(),
+      inputs: vec!(
+        RecipeInput { resources: vec!(Resource::Sand), amount: 5, min: None },
+        RecipeInput { resources: vec!(Resource::StoneWhite, Resource::StoneGreen), amount: 2, min: None },
+      ),
+      output: RecipeOutput::Consumable { resource: Resource::Dynamite, amount: 1 },
+      // The sandrone needs the green stones
+      preconditions: vec!(RecipePrecondition::HasSlot(SlotKind::Sandrone), RecipePrecondition::LessThan(Resource::Dynamite, 2), RecipePrecondition::MinEnergy(100.0)),
+    },
+    Recipe {
+      name: "flare".to_string(),
+      inputs: vec!(
+        RecipeInput { resources: vec!(Resource::Wood), amount: 1, min: None },
+        RecipeInput { resources: vec!(Resource::StoneWhite), amount: 3, min: None },
+      ),
+      output: RecipeOutput::Consumable { resource: Resource::Flare, amount: 1 },
+      // The windrone needs the wood and the white stones
+      preconditions: vec!(RecipePrecondition::HasSlot(SlotKind::Windrone), RecipePrecondition::HasSlot(SlotKind::JacksCompass), RecipePrecondition::LessThan(Resource::Flare, 1)),
+    },
+  );
+}
+
+pub fn load_recipes(file: &str) -> Vec<Recipe> {
+  let s = fs::read_to_string(file).expect("Unable to read recipes file");
+  let recipes: Vec<Recipe> = serde_json::from_str(&s).expect("Unable to parse recipes file");
+  for recipe in recipes.iter() {
+    if let Err(msg) = validate_recipe(recipe) {
+      panic!("Invalid recipe `{}` in `{}`: {}", recipe.name, file, msg);
+    }
+  }
+  return recipes;
+}
+
+fn validate_recipe(recipe: &Recipe) -> Result<(), String> {
+  for input in recipe.inputs.iter() {
+    if input.resources.len() == 0 {
+      return Err("an input must list at least one resource".to_string());
+    }
+  }
+  match &recipe.output {
+    RecipeOutput::Slot(kind) => if !get_slot_registration(*kind).starter {
+      return Err(format!("{:?} can not be crafted as a regular slot", kind));
+    },
+    RecipeOutput::Drone(kind) => if !matches!(kind, SlotKind::Windrone | SlotKind::Sandrone) {
+      return Err(format!("{:?} is not a drone", kind));
+    },
+    RecipeOutput::Consumable { .. } => {},
+  }
+  return Ok(());
+}
+
+fn has_slot(miner: &Miner, kind: SlotKind) -> bool {
+  return miner.slots.iter().any(|slot| slot.kind as usize == kind as usize);
+}
+
+pub fn can_craft(recipe: &Recipe, miner: &Miner) -> bool {
+  for precondition in recipe.preconditions.iter() {
+    let ok = match precondition {
+      RecipePrecondition::MissingSlot(kind) => !has_slot(miner, *kind),
+      RecipePrecondition::HasSlot(kind) => has_slot(miner, *kind),
+      RecipePrecondition::MinEnergy(energy) => miner.movable.now_energy >= *energy,
+      RecipePrecondition::LessThan(resource, amount) => inventory_get(&miner.meta.inventory, *resource) < *amount,
+    };
+    if !ok { return false; }
+  }
+
+  // Slots and drones must have a place to go
+  let fits = match recipe.output {
+    RecipeOutput::Slot(_) => has_slot(miner, SlotKind::Emptiness),
+    RecipeOutput::Drone(SlotKind::Windrone) => has_slot(miner, SlotKind::Emptiness) && matches!(miner.windrone.state, WindroneState::Unconstructed),
+    RecipeOutput::Drone(SlotKind::Sandrone) => has_slot(miner, SlotKind::Emptiness) && matches!(miner.sandrone.state, SandroneState::Unconstructed),
+    RecipeOutput::Drone(kind) => panic!("{:?} is not a drone", kind),
+    RecipeOutput::Consumable { .. } => true,
+  };
+  if !fits { return false; }
+
+  return has_recipe_inputs(&miner.meta.inventory, &recipe.inputs);
+}
+
+pub fn has_recipe_inputs(inventory: &Inventory, inputs: &[RecipeInput]) -> bool {
+  // Must have enough materials. Inputs that draw from the same resource must add up.
+  let mut have = clone_inventory(inventory);
+  for input in inputs.iter() {
+    let available: u32 = input.resources.iter().map(|resource| inventory_get(&have, *resource)).sum();
+    if available < input.min.unwrap_or(input.amount) || !take_recipe_input(&mut have, input) {
+      return false;
+    }
+  }
+  return true;
+}
+
+fn take_recipe_input(inventory: &mut Inventory, input: &RecipeInput) -> bool {
+  // Deduct the input amount from its resources, in order. Returns false if there was not enough.
+  let mut left = input.amount;
+  for resource in input.resources.iter() {
+    if left == 0 { break; }
+    left -= inventory_take(inventory, *resource, left);
+  }
+  return left == 0;
+}
+
+fn claim_empty_slot(miner: &mut Miner, kind: SlotKind) -> (usize, i32) {
+  // Returns the index of the first empty slot and the nth value for the new slot of given kind
+  let nth = miner.meta.kind_counts[kind as usize];
+  miner.meta.kind_counts[kind as usize] += 1;
+  for i in 0..miner.slots.len() {
+    if matches!(miner.slots[i].kind, SlotKind::Emptiness) {
+      return (i, nth);
+    }
+  }
+  panic!("should have asserted beforehand that the slot would fit somewhere");
+}
+
+pub fn craft(state: &mut AppState, biome: &mut Biome, recipe: &Recipe) {
+  // Deduct materials
+  for input in recipe.inputs.iter() {
+    let ok = take_recipe_input(&mut biome.miner.meta.inventory, input);
+    assert!(ok, "we asserted that there were enough materials, so we should have consumed them now");
+  }
+
+  match &recipe.output {
+    RecipeOutput::Slot(kind) => {
+      let (i, nth) = claim_empty_slot(&mut biome.miner, *kind);
+      biome.miner.slots[i] = get_slot_behavior(*kind).create(state, &biome.miner.helix, i, nth);
+    }
+    RecipeOutput::Drone(SlotKind::Windrone) => {
+      let (i, nth) = claim_empty_slot(&mut biome.miner, SlotKind::Windrone);
+      biome.miner.slots[i] = create_slot_windrone(i, nth);
+      biome.miner.windrone.state = WindroneState::WaitingForWind;
+    }
+    RecipeOutput::Drone(SlotKind::Sandrone) => {
+      let (i, nth) = claim_empty_slot(&mut biome.miner, SlotKind::Sandrone);
+      biome.miner.slots[i] = create_slot_sandrone(i, nth);
+      set_sandrone_state(&mut biome.miner.sandrone, SandroneState::WaitingForWater);
+    }
+    RecipeOutput::Drone(kind) => panic!("{:?} is not a drone", kind),
+    RecipeOutput::Consumable { resource, amount } => {
+      inventory_add(&mut biome.miner.meta.inventory, *resource, *amount);
+    }
+  }
+}
+
+pub fn tick_crafting(state: &mut AppState, biome: &mut Biome) {
+  // Craft the first recipe that can be crafted right now, if any
+  for n in 0..state.recipes.len() {
+    if can_craft(&state.recipes[n], &biome.miner) {
+      let recipe = state.recipes[n].clone();
+      craft(state, biome, &recipe);
+      return;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn get_default_recipe(name: &str) -> Recipe {
+    return default_recipes().into_iter().find(|recipe| recipe.name == name).unwrap();
+  }
+
+  fn create_test_inventory(resources: &[(Resource, u32)]) -> Inventory {
+    let mut inventory = create_inventory();
+    for &(resource, amount) in resources.iter() {
+      inventory_add(&mut inventory, resource, amount);
+    }
+    return inventory;
+  }
+
+  #[test]
+  fn windrone_needs_one_more_than_it_costs() {
+    let inputs = get_default_recipe("windrone").inputs;
+    assert!(!has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 5), (Resource::StoneWhite, 5)]), &inputs));
+    assert!(!has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 6), (Resource::StoneWhite, 5)]), &inputs));
+    assert!(!has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 5), (Resource::StoneWhite, 6)]), &inputs));
+    assert!(has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 6), (Resource::StoneWhite, 6)]), &inputs));
+    // Any mix of stones counts towards the stone input
+    assert!(has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 6), (Resource::StoneWhite, 3), (Resource::StoneYellow, 3)]), &inputs));
+  }
+
+  #[test]
+  fn sandrone_needs_one_more_than_it_costs() {
+    let inputs = get_default_recipe("sandrone").inputs;
+    assert!(!has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 6), (Resource::Water, 10), (Resource::StoneBlue, 6)]), &inputs));
+    assert!(!has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 6), (Resource::Water, 11), (Resource::StoneBlue, 5)]), &inputs));
+    assert!(has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 6), (Resource::Water, 11), (Resource::StoneBlue, 6)]), &inputs));
+    // White stones are not allowed for a sandrone
+    assert!(!has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 6), (Resource::Water, 11), (Resource::StoneWhite, 6)]), &inputs));
+  }
+
+  #[test]
+  fn inputs_from_the_same_resource_add_up() {
+    let inputs = vec!(
+      RecipeInput { resources: vec!(Resource::Wood), amount: 5, min: None },
+      RecipeInput { resources: vec!(Resource::Wood, Resource::Water), amount: 5, min: None },
+    );
+    assert!(!has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 9)]), &inputs));
+    assert!(has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 10)]), &inputs));
+    assert!(has_recipe_inputs(&create_test_inventory(&[(Resource::Wood, 5), (Resource::Water, 5)]), &inputs));
+  }
+
+  #[test]
+  fn has_recipe_inputs_does_not_take_anything() {
+    let inventory = create_test_inventory(&[(Resource::Wood, 6), (Resource::StoneWhite, 6)]);
+    assert!(has_recipe_inputs(&inventory, &get_default_recipe("windrone").inputs));
+    assert_eq!(inventory_get(&inventory, Resource::Wood), 6);
+    assert_eq!(inventory_get(&inventory, Resource::StoneWhite), 6);
+  }
+}
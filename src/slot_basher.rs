@@ -0,0 +1,77 @@
+use super::utils::*;
+use super::slottable::*;
+use super::biome::*;
+use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+
+pub const TITLE_BASHER: &str = "Basher";
+
+/**
+ * After breaking a block, do not change direction. When charged, the miner keeps bashing on in
+ * the same direction rather than turning towards the most visited neighbour. Cooldown doubles
+ * with each additional basher.
+ */
+pub fn create_slot_basher(slot_index: usize, nth: i32, max_cooldown: f32) -> Slottable {
+  assert!(max_cooldown > 0.0, "slot max cooldown should be non-zero: {}", max_cooldown);
+  return Slottable {
+    kind: SlotKind::Basher,
+    slot: slot_index,
+    title: TITLE_BASHER.to_owned(),
+    max_cooldown,
+    cur_cooldown: 0.0,
+    nth,
+    state: SlotState::Basher { bashes: 0 },
+  };
+}
+
+pub fn tick_slot_basher(biome: &mut Biome, slot_index: usize) {
+  let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
+  if slot.cur_cooldown < slot.max_cooldown {
+    slot.cur_cooldown += 1.0;
+  }
+}
+
+pub fn on_break_slot_basher(biome: &mut Biome, slot_index: usize) -> bool {
+  let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
+  if slot.cur_cooldown < slot.max_cooldown {
+    return false;
+  }
+  if let SlotState::Basher { bashes } = &mut slot.state {
+    *bashes += 1;
+  }
+  slot.cur_cooldown = 0.0;
+  return true;
+}
+
+pub fn ui_slot_basher(slot: &Slottable) -> (String, String, String) {
+  let bashes = match slot.state {
+    SlotState::Basher { bashes } => bashes,
+    _ => panic!("basher slot should have basher state"),
+  };
+  return (
+    TITLE_BASHER.to_string(),
+    progress_bar(20, slot.cur_cooldown, slot.max_cooldown, false),
+    format!("Kept going after {} broken blocks", bashes)
+  );
+}
+
+pub struct SlotBasher;
+
+impl SlotBehavior for SlotBasher {
+  fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+    return create_slot_basher(slot_index, nth, 10.0 * 2.0_f32.powf((nth + 1) as f32));
+  }
+
+  fn tick(&self, _options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+    tick_slot_basher(biome, slot_index);
+  }
+
+  fn on_break(&self, _options: &mut Options, biome: &mut Biome, slot_index: usize, _x: i32, _y: i32) -> bool {
+    return on_break_slot_basher(biome, slot_index);
+  }
+
+  fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+    return ui_slot_basher(&biome.miner.slots[slot_index]);
+  }
+}
@@ -0,0 +1,97 @@
+use super::utils::*;
+use super::movable::*;
+use super::slottable::*;
+use super::biome::*;
+use super::miner::*;
+use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+
+pub const TITLE_BIGFOOT: &str = "Bigfoot";
+
+/**
+ * One step moves you two cells, if you can. When charged, a forward move is followed by another
+ * forward step when the cell beyond is open. Cooldown doubles with each additional bigfoot.
+ */
+pub fn create_slot_bigfoot(slot_index: usize, nth: i32, max_cooldown: f32) -> Slottable {
+  assert!(max_cooldown > 0.0, "slot max cooldown should be non-zero: {}", max_cooldown);
+  return Slottable {
+    kind: SlotKind::Bigfoot,
+    slot: slot_index,
+    title: TITLE_BIGFOOT.to_owned(),
+    max_cooldown,
+    cur_cooldown: 0.0,
+    nth,
+    state: SlotState::Bigfoot { moved: false, leaps: 0 },
+  };
+}
+
+pub fn on_move_slot_bigfoot(biome: &mut Biome, slot_index: usize) {
+  // The extra step happens in the tick, once the miner is in the cell it moved into
+  if let SlotState::Bigfoot { moved, .. } = &mut biome.miner.slots[slot_index].state {
+    *moved = true;
+  }
+}
+
+pub fn tick_slot_bigfoot(options: &mut Options, biome: &mut Biome, slot_index: usize) {
+  let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
+  if slot.cur_cooldown < slot.max_cooldown {
+    slot.cur_cooldown += 1.0;
+  }
+
+  let moved = match slot.state {
+    SlotState::Bigfoot { moved, .. } => moved,
+    _ => panic!("bigfoot slot should have bigfoot state"),
+  };
+  if let SlotState::Bigfoot { moved, .. } = &mut slot.state {
+    *moved = false;
+  }
+  if !moved || slot.cur_cooldown < slot.max_cooldown || !can_slots_move_miner(biome) {
+    return;
+  }
+
+  let (x, y) = coord_forward(biome.miner.movable.x, biome.miner.movable.y, biome.miner.movable.dir);
+  if !can_miner_step_into(options, &biome.world, x, y) {
+    return;
+  }
+  step_miner_into(options, biome, x, y);
+
+  let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
+  if let SlotState::Bigfoot { leaps, .. } = &mut slot.state {
+    *leaps += 1;
+  }
+  slot.cur_cooldown = 0.0;
+}
+
+pub fn ui_slot_bigfoot(slot: &Slottable) -> (String, String, String) {
+  let leaps = match slot.state {
+    SlotState::Bigfoot { leaps, .. } => leaps,
+    _ => panic!("bigfoot slot should have bigfoot state"),
+  };
+  return (
+    TITLE_BIGFOOT.to_string(),
+    progress_bar(20, slot.cur_cooldown, slot.max_cooldown, false),
+    format!("Leaps: {}", leaps)
+  );
+}
+
+pub struct SlotBigfoot;
+
+impl SlotBehavior for SlotBigfoot {
+  fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+    return create_slot_bigfoot(slot_index, nth, 10.0 * 2.0_f32.powf((nth + 1) as f32));
+  }
+
+  fn tick(&self, options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+    tick_slot_bigfoot(options, biome, slot_index);
+  }
+
+  fn on_move(&self, _options: &mut Options, biome: &mut Biome, slot_index: usize, _from_x: i32, _from_y: i32) -> bool {
+    on_move_slot_bigfoot(biome, slot_index);
+    return false;
+  }
+
+  fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+    return ui_slot_bigfoot(&biome.miner.slots[slot_index]);
+  }
+}
@@ -3,6 +3,8 @@ use super::movable::*;
 use super::slottable::*;
 use super::biome::*;
 use super::options::*;
+use super::app_state::*;
+use super::helix::*;
 
 pub const TITLE_BROKEN_GPS: &str = "Broken GPS";
 
@@ -19,8 +21,7 @@ pub fn create_slot_broken_gps(slot_index: usize, nth: i32, max_cooldown: f32) ->
         max_cooldown,
         cur_cooldown: 0.0,
         nth,
-        val: 1.0, // last degree; 1 or -1
-        sum: 0.0,
+        state: SlotState::BrokenGps { turn_right: false, activations: 0 },
     };
 }
 
@@ -30,22 +31,40 @@ pub fn tick_slot_broken_gps(_options: &Options, biome: &mut Biome, slot_index: u
 
     slot.cur_cooldown += 1.0;
     if slot.cur_cooldown >= slot.max_cooldown {
-        biome.miner.movable.dir = match biome.miner.movable.dir {
-            Direction::Up => if slot.val < 0.0 { Direction::Right } else { Direction::Left },
-            Direction::Right => if slot.val < 0.0 { Direction::Down } else { Direction::Up },
-            Direction::Down => if slot.val < 0.0 { Direction::Left } else { Direction::Right },
-            Direction::Left => if slot.val < 0.0 { Direction::Up } else { Direction::Down },
-        };
+        if let SlotState::BrokenGps { turn_right, activations } = &mut slot.state {
+            biome.miner.movable.dir = turn_lr(biome.miner.movable.dir, !*turn_right);
+            *turn_right = !*turn_right;
+            *activations += 1;
+        }
         slot.cur_cooldown = 0.0;
-        slot.val *= -1.0;
-        slot.sum += 1.0;
     }
 }
 
 pub fn ui_slot_broken_gps(slot: &Slottable) -> (String, String, String) {
+    let (turn_right, activations) = match slot.state {
+        SlotState::BrokenGps { turn_right, activations } => (turn_right, activations),
+        _ => panic!("broken gps slot should have broken gps state"),
+    };
     return (
         TITLE_BROKEN_GPS.to_string(),
         progress_bar(20, slot.cur_cooldown, slot.max_cooldown, false),
-        format!("Activated: {} times. Last dir: {}", slot.sum, if slot.val == 1.0 { "Clockwise" } else { "Counter Clockwise" })
+        // The last turn was the opposite of the next turn
+        format!("Activated: {} times. Last dir: {}", activations, if turn_right { "Counter Clockwise" } else { "Clockwise" })
     );
 }
+
+pub struct SlotBrokenGps;
+
+impl SlotBehavior for SlotBrokenGps {
+    fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+        return create_slot_broken_gps(slot_index, nth, 100.0 * 2.0_f32.powf((nth + 1) as f32));
+    }
+
+    fn tick(&self, options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+        tick_slot_broken_gps(options, biome, slot_index);
+    }
+
+    fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+        return ui_slot_broken_gps(&biome.miner.slots[slot_index]);
+    }
+}
@@ -0,0 +1,100 @@
+use super::utils::*;
+use super::movable::*;
+use super::slottable::*;
+use super::biome::*;
+use super::miner::*;
+use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+
+pub const TITLE_DIAGONAL: &str = "Diagonal";
+
+/**
+ * Lets you move diagonally. When charged, the next forward move also takes a step to the side,
+ * alternating between forward-left and forward-right, if that side is open. This reaches cells
+ * that a miner moving in straight lines would skip. Cooldown doubles with each additional one.
+ */
+pub fn create_slot_diagonal(slot_index: usize, nth: i32, max_cooldown: f32) -> Slottable {
+  assert!(max_cooldown > 0.0, "slot max cooldown should be non-zero: {}", max_cooldown);
+  return Slottable {
+    kind: SlotKind::Diagonal,
+    slot: slot_index,
+    title: TITLE_DIAGONAL.to_owned(),
+    max_cooldown,
+    cur_cooldown: 0.0,
+    nth,
+    state: SlotState::Diagonal { moved: false, left: true, steps: 0 },
+  };
+}
+
+pub fn on_move_slot_diagonal(biome: &mut Biome, slot_index: usize) {
+  // Remember that the miner moved forward this tick. The side step happens in the tick, after the
+  // miner is in its new cell.
+  if let SlotState::Diagonal { moved, .. } = &mut biome.miner.slots[slot_index].state {
+    *moved = true;
+  }
+}
+
+pub fn tick_slot_diagonal(options: &mut Options, biome: &mut Biome, slot_index: usize) {
+  let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
+  if slot.cur_cooldown < slot.max_cooldown {
+    slot.cur_cooldown += 1.0;
+  }
+
+  let (moved, left) = match slot.state {
+    SlotState::Diagonal { moved, left, .. } => (moved, left),
+    _ => panic!("diagonal slot should have diagonal state"),
+  };
+  if let SlotState::Diagonal { moved, .. } = &mut slot.state {
+    *moved = false;
+  }
+  if !moved || slot.cur_cooldown < slot.max_cooldown || !can_slots_move_miner(biome) {
+    return;
+  }
+
+  let (x, y) = coord_lr(biome.miner.movable.x, biome.miner.movable.y, biome.miner.movable.dir, left);
+  if !can_miner_step_into(options, &biome.world, x, y) {
+    return;
+  }
+  step_miner_into(options, biome, x, y);
+
+  let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
+  if let SlotState::Diagonal { left, steps, .. } = &mut slot.state {
+    *left = !*left;
+    *steps += 1;
+  }
+  slot.cur_cooldown = 0.0;
+}
+
+pub fn ui_slot_diagonal(slot: &Slottable) -> (String, String, String) {
+  let (left, steps) = match slot.state {
+    SlotState::Diagonal { left, steps, .. } => (left, steps),
+    _ => panic!("diagonal slot should have diagonal state"),
+  };
+  return (
+    TITLE_DIAGONAL.to_string(),
+    progress_bar(20, slot.cur_cooldown, slot.max_cooldown, false),
+    format!("Diagonal steps: {}. Next: {}", steps, if left { "forward-left" } else { "forward-right" })
+  );
+}
+
+pub struct SlotDiagonal;
+
+impl SlotBehavior for SlotDiagonal {
+  fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+    return create_slot_diagonal(slot_index, nth, 20.0 * 2.0_f32.powf((nth + 1) as f32));
+  }
+
+  fn tick(&self, options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+    tick_slot_diagonal(options, biome, slot_index);
+  }
+
+  fn on_move(&self, _options: &mut Options, biome: &mut Biome, slot_index: usize, _from_x: i32, _from_y: i32) -> bool {
+    on_move_slot_diagonal(biome, slot_index);
+    return false;
+  }
+
+  fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+    return ui_slot_diagonal(&biome.miner.slots[slot_index]);
+  }
+}
@@ -1,5 +1,10 @@
 use super::utils::*;
 use super::slottable::*;
+use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+use super::biome::*;
+use super::movable::*;
 
 pub const TITLE_DRILL: &str = "Drill";
 
@@ -11,8 +16,7 @@ pub fn create_drill(slot_index: usize, nth: i32) -> Slottable {
     max_cooldown: 0.0,
     cur_cooldown: 0.0,
     nth,
-    val: 0.0,
-    sum: 0.0,
+    state: SlotState::Nothing,
   };
 }
 
@@ -23,3 +27,25 @@ pub fn ui_slot_drill(slot: &Slottable) -> (String, String, String) {
     "".to_string()
   );
 }
+
+pub struct SlotDrill;
+
+impl SlotBehavior for SlotDrill {
+  fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+    return create_drill(slot_index, nth);
+  }
+
+  fn on_bump(&self, options: &mut Options, biome: &mut Biome, slot_index: usize, x: i32, y: i32, dx: i32, dy: i32) -> i32 {
+    // All drills work together so only the first one does the drilling, with the total count
+    if biome.miner.slots[slot_index].nth == 0 {
+      let drills = biome.miner.meta.kind_counts[SlotKind::Drill as usize];
+      let hammers = biome.miner.meta.kind_counts[SlotKind::Hammer as usize];
+      drill_deeper(drills, hammers, x, y, dx, dy, &mut biome.world, options);
+    }
+    return 0;
+  }
+
+  fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+    return ui_slot_drill(&biome.miner.slots[slot_index]);
+  }
+}
@@ -1,14 +1,16 @@
 use super::utils::*;
 use super::slottable::*;
-use super::movable::*;
 use super::drone_me::*;
 use super::options::*;
 use super::biome::*;
+use super::app_state::*;
+use super::helix::*;
 
 pub const TITLE_DRONE_LAUNCHER: &str = "Drone Launcher";
+// Default of the drone energy gene, the energy budget of a freshly launched drone
 pub const DRONE_INITIAL_ENERGY: f32 = 1000.0;
 
-pub fn create_drone_launcher(slot_index: usize, nth: i32, drone_id: i32, max_cooldown: f32) -> Slottable {
+pub fn create_drone_launcher(slot_index: usize, nth: i32, max_cooldown: f32) -> Slottable {
   return Slottable {
     kind: SlotKind::DroneLauncher,
     slot: slot_index,
@@ -16,8 +18,7 @@ pub fn create_drone_launcher(slot_index: usize, nth: i32, drone_id: i32, max_coo
     max_cooldown,
     cur_cooldown: 0.0,
     nth,
-    val: drone_id as f32,
-    sum: 0.0,
+    state: SlotState::Nothing,
   };
 }
 
@@ -40,15 +41,13 @@ pub fn tick_slot_drone_launcher(options: &mut Options, biome: &mut Biome, slot_i
   } else {
     // If the launcher is charged and the miner has enough energy, launch another drone
     if slot.cur_cooldown >= slot.max_cooldown {
-      if biome.miner.movable.now_energy > 2.0 * DRONE_INITIAL_ENERGY {
-        drone.movable.now_energy = DRONE_INITIAL_ENERGY;
-        biome.miner.movable.now_energy = (biome.miner.movable.now_energy - (DRONE_INITIAL_ENERGY / 2.0)).min(biome.miner.movable.init_energy).max(0.0); // TODO: this ratio can be a tool to act as penalty for a helix property
-        // Position the drone on your location, facing perpendicular from your current direction
-        drone.movable.x = biome.miner.movable.x;
-        drone.movable.y = biome.miner.movable.y;
-        drone.movable.dir = turn_right(biome.miner.movable.dir);
+      let energy = biome.miner.helix.drone_energy;
+      if biome.miner.movable.now_energy > 2.0 * energy {
         // Reset the cooldown. It will be ignored until the drone runs out of energy.
         slot.cur_cooldown = 0.0;
+        biome.miner.movable.now_energy = (biome.miner.movable.now_energy - (energy / 2.0)).min(biome.miner.movable.init_energy).max(0.0); // TODO: this ratio can be a tool to act as penalty for a helix property
+        // The drone genes decide which way it faces
+        launch_me_drone(biome, drone_index as usize, energy);
       } else {
         // Do nothing. Wait until the miner energy goes over the threshold again, which it
         // may not do anymore, but that's not relevant.
@@ -63,14 +62,33 @@ pub fn ui_slot_drone_launcher(slot: &Slottable, drone: &MeDrone) -> (String, Str
   if drone.movable.now_energy > 0.0 {
     return (
       TITLE_DRONE_LAUNCHER.to_string(),
-      progress_bar(20, drone.movable.now_energy, DRONE_INITIAL_ENERGY, false),
-      format!("Drone is flying at {}x{}", drone.movable.x, drone.movable.y)
+      progress_bar(20, drone.movable.now_energy, drone.movable.init_energy, false),
+      if drone.homing { format!("Drone is returning {} items from {}x{}", drone.cargo.len(), drone.movable.x, drone.movable.y) }
+      else { format!("Drone is flying at {}x{}", drone.movable.x, drone.movable.y) }
     );
   }
 
   return (
     TITLE_DRONE_LAUNCHER.to_string(),
     progress_bar(20, slot.cur_cooldown, slot.max_cooldown, false),
-    format!("Waiting to launch new drone...")
+    if drone.delivered > 0 { format!("Waiting to launch new drone... Delivered {} items so far", drone.delivered) }
+    else { format!("Waiting to launch new drone...") }
   );
 }
+
+pub struct SlotDroneLauncher;
+
+impl SlotBehavior for SlotDroneLauncher {
+  fn create(&self, _state: &mut AppState, helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+    return create_drone_launcher(slot_index, nth, helix.drone_gen_cooldown * 2.0_f32.powf(((nth as f32 / 2.0) + 1.0) as f32));
+  }
+
+  fn tick(&self, options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+    tick_slot_drone_launcher(options, biome, slot_index);
+  }
+
+  fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+    let slot = &biome.miner.slots[slot_index];
+    return ui_slot_drone_launcher(slot, &biome.miner.drones[slot.nth as usize]);
+  }
+}
@@ -1,4 +1,7 @@
 use super::slottable::*;
+use super::app_state::*;
+use super::helix::*;
+use super::biome::*;
 
 pub const TITLE_EMPTINESS: &str = "Empty";
 
@@ -10,8 +13,7 @@ pub fn create_empty_slot(slot_index: usize) -> Slottable {
         max_cooldown: 0.0,
         cur_cooldown: 0.0,
         nth: 0,
-        val: 0.0,
-        sum: 0.0,
+        state: SlotState::Nothing,
     };
 }
 
@@ -23,6 +25,18 @@ pub fn ui_slot_emptiness(_slot: &Slottable) -> (String, String, String) {
     );
 }
 
+pub struct SlotEmptiness;
+
+impl SlotBehavior for SlotEmptiness {
+    fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, _nth: i32) -> Slottable {
+        return create_empty_slot(slot_index);
+    }
+
+    fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+        return ui_slot_emptiness(&biome.miner.slots[slot_index]);
+    }
+}
+
 
 /*
 pub struct Emptiness {}
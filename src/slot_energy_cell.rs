@@ -2,6 +2,8 @@ use super::utils::*;
 use super::slottable::*;
 use super::options::*;
 use super::biome::*;
+use super::app_state::*;
+use super::helix::*;
 
 pub const TITLE_ENERGY_CELL: &str = "Energy Cell";
 
@@ -17,8 +19,7 @@ pub fn create_slot_energy_cell(slot_index: usize, nth: i32, energy_bonus: i32, m
         max_cooldown, // max_energy,
         cur_cooldown: 0.0,
         nth,
-        val: energy_bonus as f32,
-        sum: 0.0,
+        state: SlotState::EnergyCell { energy_bonus: energy_bonus as f32, generated: 0.0 },
     };
 }
 
@@ -27,8 +28,10 @@ pub fn tick_slot_energy_cell(_options: &mut Options, biome: &mut Biome, slot_ind
 
     slot.cur_cooldown += 1.0;
     if slot.cur_cooldown >= slot.max_cooldown {
-        biome.miner.movable.now_energy = (biome.miner.movable.now_energy + slot.val).max(0.0);
-        slot.sum += slot.val;
+        if let SlotState::EnergyCell { energy_bonus, generated } = &mut slot.state {
+            biome.miner.movable.now_energy = (biome.miner.movable.now_energy + *energy_bonus).max(0.0);
+            *generated += *energy_bonus;
+        }
         if biome.miner.movable.now_energy > biome.miner.movable.init_energy {
             biome.miner.movable.now_energy = biome.miner.movable.init_energy;
         }
@@ -37,9 +40,29 @@ pub fn tick_slot_energy_cell(_options: &mut Options, biome: &mut Biome, slot_ind
 }
 
 pub fn ui_slot_energy_cell(slot: &Slottable) -> (String, String, String) {
+    let generated = match slot.state {
+        SlotState::EnergyCell { generated, .. } => generated,
+        _ => panic!("energy cell slot should have energy cell state"),
+    };
     return (
         TITLE_ENERGY_CELL.to_string(),
         progress_bar(20, slot.cur_cooldown, slot.max_cooldown, false),
-        format!("Generated energy: {}", slot.sum)
+        format!("Generated energy: {}", generated)
     );
 }
+
+pub struct SlotEnergyCell;
+
+impl SlotBehavior for SlotEnergyCell {
+    fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+        return create_slot_energy_cell(slot_index, nth, 100, 100.0 * 2.0_f32.powf((nth + 1) as f32));
+    }
+
+    fn tick(&self, options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+        tick_slot_energy_cell(options, biome, slot_index);
+    }
+
+    fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+        return ui_slot_energy_cell(&biome.miner.slots[slot_index]);
+    }
+}
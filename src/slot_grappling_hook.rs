@@ -0,0 +1,102 @@
+use super::utils::*;
+use super::movable::*;
+use super::slottable::*;
+use super::biome::*;
+use super::miner::*;
+use super::tile::*;
+use super::world::*;
+use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+
+pub const TITLE_GRAPPLING_HOOK: &str = "Grappling Hook";
+
+// How far (in cells) the hook reaches forward
+pub const GRAPPLING_HOOK_RANGE: i32 = 8;
+
+/**
+ * Pulls you to the nearest block in front of you. When charged and there is a wall within range
+ * straight ahead, with only open cells in between, the miner is pulled up against that wall and
+ * picks up everything on the way. Cooldown doubles with each additional hook.
+ */
+pub fn create_slot_grappling_hook(slot_index: usize, nth: i32, max_cooldown: f32) -> Slottable {
+  assert!(max_cooldown > 0.0, "slot max cooldown should be non-zero: {}", max_cooldown);
+  return Slottable {
+    kind: SlotKind::GrapplingHook,
+    slot: slot_index,
+    title: TITLE_GRAPPLING_HOOK.to_owned(),
+    max_cooldown,
+    cur_cooldown: 0.0,
+    nth,
+    state: SlotState::GrapplingHook { pulls: 0, distance: 0 },
+  };
+}
+
+pub fn tick_slot_grappling_hook(options: &mut Options, biome: &mut Biome, slot_index: usize) {
+  let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
+  if slot.cur_cooldown < slot.max_cooldown {
+    slot.cur_cooldown += 1.0;
+    return;
+  }
+  if !can_slots_move_miner(biome) {
+    return;
+  }
+
+  // Find the first cell ahead that is not open. Only a wall can be hooked.
+  let (dx, dy) = delta_forward(biome.miner.movable.dir);
+  let mx = biome.miner.movable.x;
+  let my = biome.miner.movable.y;
+  let mut pull = 0;
+  for n in 1..=GRAPPLING_HOOK_RANGE {
+    if can_miner_step_into(options, &biome.world, mx + dx * n, my + dy * n) {
+      continue;
+    }
+    if is_wall_tile(get_cell_tile_at(options, &biome.world, mx + dx * n, my + dy * n)) {
+      pull = n - 1;
+    }
+    break;
+  }
+  if pull == 0 {
+    // Nothing to hook, or already next to it. Stay charged.
+    return;
+  }
+
+  for n in 1..=pull {
+    step_miner_into(options, biome, mx + dx * n, my + dy * n);
+  }
+
+  let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
+  if let SlotState::GrapplingHook { pulls, distance } = &mut slot.state {
+    *pulls += 1;
+    *distance += pull as u32;
+  }
+  slot.cur_cooldown = 0.0;
+}
+
+pub fn ui_slot_grappling_hook(slot: &Slottable) -> (String, String, String) {
+  let (pulls, distance) = match slot.state {
+    SlotState::GrapplingHook { pulls, distance } => (pulls, distance),
+    _ => panic!("grappling hook slot should have grappling hook state"),
+  };
+  return (
+    TITLE_GRAPPLING_HOOK.to_string(),
+    progress_bar(20, slot.cur_cooldown, slot.max_cooldown, false),
+    format!("Pulled {} times over {} cells", pulls, distance)
+  );
+}
+
+pub struct SlotGrapplingHook;
+
+impl SlotBehavior for SlotGrapplingHook {
+  fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+    return create_slot_grappling_hook(slot_index, nth, 50.0 * 2.0_f32.powf((nth + 1) as f32));
+  }
+
+  fn tick(&self, options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+    tick_slot_grappling_hook(options, biome, slot_index);
+  }
+
+  fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+    return ui_slot_grappling_hook(&biome.miner.slots[slot_index]);
+  }
+}
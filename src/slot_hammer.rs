@@ -1,4 +1,8 @@
 use super::slottable::*;
+use super::app_state::*;
+use super::helix::*;
+use super::options::*;
+use super::biome::*;
 
 pub const TITLE_HAMMER: &str = "Hammer";
 
@@ -10,8 +14,7 @@ pub fn create_hammer(slot_index: usize, nth: i32) -> Slottable {
         max_cooldown: 0.0,
         cur_cooldown: 0.0,
         nth,
-        val: 0.0,
-        sum: 0.0,
+        state: SlotState::Nothing,
     };
 }
 
@@ -23,6 +26,23 @@ pub fn ui_slot_hammer(_slot: &Slottable) -> (String, String, String) {
     );
 }
 
+pub struct SlotHammer;
+
+impl SlotBehavior for SlotHammer {
+    fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+        return create_hammer(slot_index, nth);
+    }
+
+    fn on_bump(&self, _options: &mut Options, _biome: &mut Biome, _slot_index: usize, _x: i32, _y: i32, _dx: i32, _dy: i32) -> i32 {
+        // Each hammer hits the wall one additional time
+        return 1;
+    }
+
+    fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+        return ui_slot_hammer(&biome.miner.slots[slot_index]);
+    }
+}
+
 /*
 pub struct Hammer {}
 
@@ -5,6 +5,9 @@ use super::movable::*;
 use super::slottable::*;
 use super::world::*;
 use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+use super::values::*;
 
 pub const TITLE_JACKS_COMPASS: &str = "Jack's Compass";
 
@@ -23,8 +26,7 @@ pub fn create_slot_jacks_compass(slot_index: usize, nth: i32, max_cooldown: f32)
     max_cooldown,
     cur_cooldown: 0.0,
     nth,
-    val: 1.0, // last degree; 1 or -1
-    sum: 0.0,
+    state: SlotState::JacksCompass { activations: 0, last_prio: 0 },
   };
 }
 
@@ -39,6 +41,8 @@ pub fn tick_slot_jacks_compass(options: &mut Options, biome: &mut Biome, slot_in
   if slot.cur_cooldown >= slot.max_cooldown {
     // Search in an increasing radius up to 4x4 for the most valuable resource and face that way
     // If nothing is found, nothing happens. Targets the nearest, most valuable resource.
+    // While a flare is lit (see consumable.rs) the compass sees much further.
+    let range = if biome.miner.meta.flare_ticks > 0 { FLARE_RANGE } else { 2 };
 
     let mx = biome.miner.movable.x;
     let my = biome.miner.movable.y;
@@ -46,8 +50,8 @@ pub fn tick_slot_jacks_compass(options: &mut Options, biome: &mut Biome, slot_in
     let mut highest = 0;
     let mut tox = 0;
     let mut toy = 0;
-    for y in my - 2..my + 2 {
-      for x in mx - 2..mx + 2 {
+    for y in my - range..my + range {
+      for x in mx - range..mx + range {
         let pickup = get_cell_stuff_at(options, &biome.world, x, y).1;
         let prio = pickup_to_priority(pickup);
         if prio > highest {
@@ -61,8 +65,10 @@ pub fn tick_slot_jacks_compass(options: &mut Options, biome: &mut Biome, slot_in
     slot.cur_cooldown = 0.0;
 
     if highest > 0 {
-      slot.sum += 1.0;
-      slot.val = highest as f32;
+      if let SlotState::JacksCompass { activations, last_prio } = &mut slot.state {
+        *activations += 1;
+        *last_prio = highest as u32;
+      }
 
       biome.miner.movable.dir =
         if (mx - tox).abs() < (my - toy).abs() {
@@ -83,9 +89,29 @@ pub fn tick_slot_jacks_compass(options: &mut Options, biome: &mut Biome, slot_in
 }
 
 pub fn ui_slot_jacks_compass(slot: &Slottable) -> (String, String, String) {
+  let (activations, last_prio) = match slot.state {
+    SlotState::JacksCompass { activations, last_prio } => (activations, last_prio),
+    _ => panic!("jacks compass slot should have jacks compass state"),
+  };
   return (
     TITLE_JACKS_COMPASS.to_string(),
     progress_bar(20, slot.cur_cooldown, slot.max_cooldown, false),
-    format!("Activated: {} times. Last prio: {}", slot.sum, slot.val as u32)
+    format!("Activated: {} times. Last prio: {}", activations, last_prio)
   );
 }
+
+pub struct SlotJacksCompass;
+
+impl SlotBehavior for SlotJacksCompass {
+  fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+    return create_slot_jacks_compass(slot_index, nth, 40.0 * 2.0_f32.powf((nth + 1) as f32));
+  }
+
+  fn tick(&self, options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+    tick_slot_jacks_compass(options, biome, slot_index);
+  }
+
+  fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+    return ui_slot_jacks_compass(&biome.miner.slots[slot_index]);
+  }
+}
@@ -1,4 +1,10 @@
 use super::slottable::*;
+use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+use super::biome::*;
+use super::movable::*;
+use super::miner::*;
 
 /*
  A magnet allows you to pick up pickups from additional neighboring cells.
@@ -27,24 +33,69 @@ pub fn create_slot_magnet(slot_index: usize, nth: i32) -> Slottable {
         max_cooldown: 0.0,
         cur_cooldown: 0.0,
         nth,
-        val: 0.0,
-        sum: 0.0,
+        state: SlotState::Magnet { picks: 0 },
     };
 }
 
+pub fn on_move_slot_magnet(options: &mut Options, biome: &mut Biome, slot_index: usize, from_x: i32, from_y: i32) -> bool {
+    // Magnets allow to pick up neighboring tiles from the tile you started at
+    // In order of magnet count; back, left, right, forward-left, forward-right, back-left, back-right
+    // There's no advantage to having more than 7 magnets.
+    // TBD whether there's any other drawback than taking up a slot
+    let dir = biome.miner.movable.dir;
+    let (nx, ny) = match biome.miner.slots[slot_index].nth {
+        0 => coord_back(from_x, from_y, dir),
+        1 => coord_left(from_x, from_y, dir),
+        2 => coord_right(from_x, from_y, dir),
+        3 => coord_fl(from_x, from_y, dir),
+        4 => coord_fr(from_x, from_y, dir),
+        5 => coord_bl(from_x, from_y, dir),
+        6 => coord_br(from_x, from_y, dir),
+        _ => return false, // Unused magnet
+    };
+
+    if move_miner_pickup_from_empty_tile(options, biome, nx, ny) {
+        if let SlotState::Magnet { picks } = &mut biome.miner.slots[slot_index].state {
+            *picks += 1;
+        }
+        return true;
+    }
+    return false;
+}
+
 pub fn ui_slot_magnet(slot: &Slottable) -> (String, String, String) {
+    let picks = match slot.state {
+        SlotState::Magnet { picks } => picks,
+        _ => panic!("magnet slot should have magnet state"),
+    };
     return (
         TITLE_MAGNET.to_string(),
         "".to_string(),
         match slot.nth {
-            0 => format!("back tile; {} picks", slot.sum),
-            1 => format!("left tile; {} picks", slot.sum),
-            2 => format!("right tile; {} picks", slot.sum),
-            3 => format!("forward-left tile; {} picks", slot.sum),
-            4 => format!("forward-right tile; {} picks", slot.sum),
-            5 => format!("backard-left tile; {} picks", slot.sum),
-            6 => format!("backward-right tile; {} picks", slot.sum),
-            _ => format!("unused; {} picks", slot.sum),
+            0 => format!("back tile; {} picks", picks),
+            1 => format!("left tile; {} picks", picks),
+            2 => format!("right tile; {} picks", picks),
+            3 => format!("forward-left tile; {} picks", picks),
+            4 => format!("forward-right tile; {} picks", picks),
+            5 => format!("backard-left tile; {} picks", picks),
+            6 => format!("backward-right tile; {} picks", picks),
+            _ => format!("unused; {} picks", picks),
         }
     );
 }
+
+pub struct SlotMagnet;
+
+impl SlotBehavior for SlotMagnet {
+    fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+        return create_slot_magnet(slot_index, nth);
+    }
+
+    fn on_move(&self, options: &mut Options, biome: &mut Biome, slot_index: usize, from_x: i32, from_y: i32) -> bool {
+        return on_move_slot_magnet(options, biome, slot_index, from_x, from_y);
+    }
+
+    fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+        return ui_slot_magnet(&biome.miner.slots[slot_index]);
+    }
+}
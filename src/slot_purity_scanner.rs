@@ -2,12 +2,17 @@ use super::utils::*;
 use super::slottable::*;
 use super::biome::*;
 use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+use super::inventory::*;
+use super::miner::*;
 
 pub const TITLE_PURITY_SCANNER: &str = "Purity Scanner";
 
 /**
- * A purity scanner gives your next gem double points. Has a cooldown that doubles with
- * each additional scanner you get.
+ * A purity scanner improves the quality of the next gem or stone that the miner or its drones
+ * pick up by one tier (white, green, blue, yellow). Has a cooldown that doubles with each
+ * additional scanner you get, and additional scanners can not reach the higher tiers.
  */
 pub fn create_slot_purity_scanner(slot_index: usize, nth: i32, max_cooldown: f32) -> Slottable {
     assert!(max_cooldown > 0.0, "slot max cooldown should be non-zero: {}", max_cooldown);
@@ -18,8 +23,7 @@ pub fn create_slot_purity_scanner(slot_index: usize, nth: i32, max_cooldown: f32
         max_cooldown,
         cur_cooldown: 0.0,
         nth,
-        val: 0.0, // generated
-        sum: 0.0,
+        state: SlotState::PurityScanner { improved: 0 },
     };
 }
 
@@ -27,28 +31,68 @@ pub fn tick_slot_purity_scanner(_options: &mut Options, biome: &mut Biome, slot_
     let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
     if slot.cur_cooldown < slot.max_cooldown {
         slot.cur_cooldown += 1.0;
-    } else {
-        slot.cur_cooldown = slot.max_cooldown;
+    }
+}
+
+pub fn on_pickup_slot_purity_scanner(biome: &mut Biome, slot_index: usize, item: &AcquiredItem) -> Option<Resource> {
+    let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
+    if slot.cur_cooldown < slot.max_cooldown {
+        return None;
     }
 
-    // This item improves the quality of certain discovered items
-    // We translate to "any such item that was acquired in this tick gets its quality bumped by one"
+    // Only stones and gems. Other scanners may upgrade the same item further.
+    let category = get_resource_info(item.resource).category;
+    if !matches!(category, ResourceCategory::Diamond | ResourceCategory::Stone) {
+        return None;
+    }
+
+    // Stacked scanners are less effective: the first can upgrade up to yellow, the second up to
+    // blue, any others only up to green.
+    let top_tier = (3 - slot.nth).max(1) as u32;
+    let quality = quality_of_resource(item.resource);
+    if quality >= top_tier.min(max_quality(category)) {
+        return None;
+    }
 
-    // todo: points_last_move moet laatste item(s) weergeven. vec?
-    if slot.cur_cooldown >= slot.max_cooldown && biome.miner.meta.points_last_move > 0 {
-        slot.sum += 1.0;
-        slot.cur_cooldown = 0.0;
+    if let SlotState::PurityScanner { improved } = &mut slot.state {
+        *improved += 1;
     }
+    slot.cur_cooldown = 0.0;
+    return Some(resource_of_quality(category, quality + 1));
 }
 
 pub fn ui_slot_purity_scanner(slot: &Slottable) -> (String, String, String) {
+    let improved = match slot.state {
+        SlotState::PurityScanner { improved } => improved,
+        _ => panic!("purity scanner slot should have purity scanner state"),
+    };
     return (
         TITLE_PURITY_SCANNER.to_string(),
         progress_bar(20, slot.cur_cooldown, slot.max_cooldown, false),
-        format!("Improved {} gems", slot.sum)
+        format!("Improved {} gems", improved)
     );
 }
 
+pub struct SlotPurityScanner;
+
+impl SlotBehavior for SlotPurityScanner {
+    fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+        return create_slot_purity_scanner(slot_index, nth, 100.0 * 2.0_f32.powf((nth + 1) as f32));
+    }
+
+    fn tick(&self, options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+        tick_slot_purity_scanner(options, biome, slot_index);
+    }
+
+    fn on_pickup(&self, biome: &mut Biome, slot_index: usize, item: &AcquiredItem) -> Option<Resource> {
+        return on_pickup_slot_purity_scanner(biome, slot_index, item);
+    }
+
+    fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+        return ui_slot_purity_scanner(&biome.miner.slots[slot_index]);
+    }
+}
+
 /*
 pub struct PurityScanner {
     // pub point_bonus: i32, // Do we want to make this somehow scaling rather than absolute double?
@@ -0,0 +1,98 @@
+use crate::pickup::*;
+use super::utils::*;
+use super::movable::*;
+use super::slottable::*;
+use super::biome::*;
+use super::world::*;
+use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+
+pub const TITLE_RADAR: &str = "Radar";
+
+// How far (in cells, each axis) the radar looks around the miner
+pub const RADAR_RANGE: i32 = 5;
+
+/**
+ * Prefers to turn towards a diamond. When charged, it looks for the nearest diamond around the
+ * miner. If that diamond is more to the left or right than it is ahead, the miner turns towards
+ * it. It never turns the miner around. Cooldown doubles with each additional radar.
+ */
+pub fn create_slot_radar(slot_index: usize, nth: i32, max_cooldown: f32) -> Slottable {
+  assert!(max_cooldown > 0.0, "slot max cooldown should be non-zero: {}", max_cooldown);
+  return Slottable {
+    kind: SlotKind::Radar,
+    slot: slot_index,
+    title: TITLE_RADAR.to_owned(),
+    max_cooldown,
+    cur_cooldown: 0.0,
+    nth,
+    state: SlotState::Radar { turns: 0 },
+  };
+}
+
+pub fn tick_slot_radar(options: &mut Options, biome: &mut Biome, slot_index: usize) {
+  let slot: &mut Slottable = &mut biome.miner.slots[slot_index];
+  if slot.cur_cooldown < slot.max_cooldown {
+    slot.cur_cooldown += 1.0;
+    return;
+  }
+  slot.cur_cooldown = 0.0;
+
+  let mx = biome.miner.movable.x;
+  let my = biome.miner.movable.y;
+  let mut nearest: Option<(i32, i32)> = None;
+  let mut nearest_distance = i32::MAX;
+  for y in my - RADAR_RANGE..=my + RADAR_RANGE {
+    for x in mx - RADAR_RANGE..=mx + RADAR_RANGE {
+      let distance = (x - mx).abs() + (y - my).abs();
+      if distance > 0 && distance < nearest_distance && matches!(get_cell_stuff_at(options, &biome.world, x, y).1, Pickup::Diamond) {
+        nearest = Some((x - mx, y - my));
+        nearest_distance = distance;
+      }
+    }
+  }
+
+  if let Some((rx, ry)) = nearest {
+    // Project the offset on the forward and left axis of the miner
+    let dir = biome.miner.movable.dir;
+    let (fx, fy) = delta_forward(dir);
+    let (lx, ly) = coord_left(0, 0, dir);
+    let ahead = rx * fx + ry * fy;
+    let left = rx * lx + ry * ly;
+    if left != 0 && left.abs() > ahead {
+      biome.miner.movable.dir = turn_lr(dir, left > 0);
+      if let SlotState::Radar { turns } = &mut biome.miner.slots[slot_index].state {
+        *turns += 1;
+      }
+    }
+  }
+}
+
+pub fn ui_slot_radar(slot: &Slottable) -> (String, String, String) {
+  let turns = match slot.state {
+    SlotState::Radar { turns } => turns,
+    _ => panic!("radar slot should have radar state"),
+  };
+  return (
+    TITLE_RADAR.to_string(),
+    progress_bar(20, slot.cur_cooldown, slot.max_cooldown, false),
+    format!("Turned towards a diamond {} times", turns)
+  );
+}
+
+pub struct SlotRadar;
+
+impl SlotBehavior for SlotRadar {
+  fn create(&self, _state: &mut AppState, _helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+    return create_slot_radar(slot_index, nth, 20.0 * 2.0_f32.powf((nth + 1) as f32));
+  }
+
+  fn tick(&self, options: &mut Options, _state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+    tick_slot_radar(options, biome, slot_index);
+  }
+
+  fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+    return ui_slot_radar(&biome.miner.slots[slot_index]);
+  }
+}
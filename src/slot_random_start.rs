@@ -0,0 +1,23 @@
+use super::slottable::*;
+use super::app_state::*;
+use super::helix::*;
+use super::biome::*;
+
+pub const TITLE_RANDOM_START: &str = "Random";
+
+/**
+ * Not a real slot. When a miner is created from a helix with this slot, it gets a random
+ * starter slot in its place instead. Never appears on a running miner.
+ */
+pub struct SlotRandomStart;
+
+impl SlotBehavior for SlotRandomStart {
+  fn create(&self, state: &mut AppState, helix: &Helix, slot_index: usize, nth: i32) -> Slottable {
+    let kind = get_random_slot(&mut state.instance_rng_unseeded);
+    return get_slot_behavior(kind).create(state, helix, slot_index, nth);
+  }
+
+  fn ui(&self, _biome: &Biome, _slot_index: usize) -> (String, String, String) {
+    panic!("Running miners should not get the RandomStart slot");
+  }
+}
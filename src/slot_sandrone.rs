@@ -1,6 +1,11 @@
 use crate::icons::*;
 use super::slottable::*;
 use super::drone_san::*;
+use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+use super::biome::*;
+use super::inventory::*;
 
 pub const TITLE_SANDRONE: &str = "SanDrone";
 
@@ -12,8 +17,7 @@ pub fn create_slot_sandrone(slot_index: usize, nth: i32) -> Slottable {
     max_cooldown: 0.0,
     cur_cooldown: 0.0,
     nth,
-    val: 0.0,
-    sum: 0.0,
+    state: SlotState::Nothing,
   };
 }
 
@@ -28,7 +32,7 @@ pub fn ui_slot_sandrone(_sandrone_slot: &Slottable, sandrone: &Sandrone, sand: u
       10,
     ),
     // This text is offset which is caused by the character printing as two but rust {: <30} counting it as one.
-    format!("Area: {}. w: {}. h: {}. Walls: {}. Imps: {}. Open: {}. Path: {}. Pos: {},{}.",// Seeking: {}. back tracking: {}. air lifted: {}. {}",
+    format!("Area: {}. w: {}. h: {}. Walls: {}. Imps: {}. Open: {}. Path: {}. Drilled: {}. Ghosted: {}. Pos: {},{}.",// Seeking: {}. back tracking: {}. air lifted: {}. {}",
       (sandrone.expansion_max_x - sandrone.expansion_min_x + 1) * (sandrone.expansion_max_y - sandrone.expansion_min_y + 1),
       sandrone.expansion_max_x - sandrone.expansion_min_x + 1,
       sandrone.expansion_max_y - sandrone.expansion_min_y + 1,
@@ -36,6 +40,8 @@ pub fn ui_slot_sandrone(_sandrone_slot: &Slottable, sandrone: &Sandrone, sand: u
       sandrone.impassable_tiles.len(),
       ((sandrone.expansion_max_x - sandrone.expansion_min_x + 1) * (sandrone.expansion_max_y - sandrone.expansion_min_y + 1)) - ((sandrone.push_tiles.len() + sandrone.impassable_tiles.len()) as i32),
       ((sandrone.expansion_max_x - sandrone.expansion_min_x + 1) + (sandrone.expansion_max_y - sandrone.expansion_min_y + 1)) * 2 - 4,
+      sandrone.drilled,
+      sandrone.ghosted,
       sandrone.movable.x,
       sandrone.movable.y,
       // sandrone.seeking,
@@ -61,3 +67,19 @@ pub fn ui_slot_sandrone(_sandrone_slot: &Slottable, sandrone: &Sandrone, sand: u
     // ),
   );
 }
+
+pub struct SlotSandrone;
+
+impl SlotBehavior for SlotSandrone {
+  fn create(&self, _state: &mut AppState, _helix: &Helix, _slot_index: usize, _nth: i32) -> Slottable {
+    panic!("The sandrone is not a valid starting slot");
+  }
+
+  fn tick(&self, options: &mut Options, state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+    tick_sandrone(options, state, biome, slot_index);
+  }
+
+  fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+    return ui_slot_sandrone(&biome.miner.slots[slot_index], &biome.miner.sandrone, inventory_get(&biome.miner.meta.inventory, Resource::Sand));
+  }
+}
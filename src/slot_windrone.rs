@@ -1,6 +1,11 @@
 use crate::icons::*;
 use super::slottable::*;
 use super::drone_win::*;
+use super::options::*;
+use super::app_state::*;
+use super::helix::*;
+use super::biome::*;
+use super::inventory::*;
 
 pub const TITLE_WINDRONE: &str = "WinDrone";
 
@@ -12,8 +17,7 @@ pub fn create_slot_windrone(slot_index: usize, nth: i32) -> Slottable {
     max_cooldown: 0.0,
     cur_cooldown: 0.0,
     nth,
-    val: 0.0,
-    sum: 0.0,
+    state: SlotState::Nothing,
   };
 }
 
@@ -26,6 +30,22 @@ pub fn ui_slot_windrone(_windrone_slot: &Slottable, windrone: &Windrone, wind: u
       std::iter::repeat(format!(" -")).take((10i32-wind as i32).max(0) as usize).collect::<String>(),
       wind.min(10), 10
     ),
-    windrone.status_desc.to_owned(),
+    format!("{} Ghosted: {}.", windrone.status_desc, windrone.ghosted),
   );
 }
+
+pub struct SlotWindrone;
+
+impl SlotBehavior for SlotWindrone {
+  fn create(&self, _state: &mut AppState, _helix: &Helix, _slot_index: usize, _nth: i32) -> Slottable {
+    panic!("The windrone is not a valid starting slot");
+  }
+
+  fn tick(&self, options: &mut Options, state: &mut AppState, biome: &mut Biome, slot_index: usize) {
+    tick_windrone(options, state, biome, slot_index);
+  }
+
+  fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String) {
+    return ui_slot_windrone(&biome.miner.slots[slot_index], &biome.miner.windrone, inventory_get(&biome.miner.meta.inventory, Resource::Wind));
+  }
+}
@@ -1,9 +1,32 @@
 use rand_pcg::{Lcg128Xsl64};
 use rand::distributions::{Distribution, Uniform};
+use serde_derive::{Serialize, Deserialize};
 
-// use super::tile::*;
+use super::options::*;
+use super::app_state::*;
+use super::biome::*;
+use super::helix::*;
+use super::inventory::*;
+use super::miner::*;
+use super::slot_broken_gps::*;
+use super::slot_windrone::*;
+use super::slot_drill::*;
+use super::slot_drone_launcher::*;
+use super::slot_emptiness::*;
+use super::slot_energy_cell::*;
+use super::slot_hammer::*;
+use super::slot_jacks_compass::*;
+use super::slot_magnet::*;
+use super::slot_purity_scanner::*;
+use super::slot_random_start::*;
+use super::slot_sandrone::*;
+use super::slot_diagonal::*;
+use super::slot_bigfoot::*;
+use super::slot_basher::*;
+use super::slot_radar::*;
+use super::slot_grappling_hook::*;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Slottable {
     pub kind: SlotKind,
     pub slot: usize,
@@ -12,13 +35,28 @@ pub struct Slottable {
     pub cur_cooldown: f32,
     // Offset zero. This is the nth slottable of this kind
     pub nth: i32,
-    // Generic scratch value / sum for this slottable
-    pub val: f32,
-    pub sum: f32,
+    // Whatever this particular kind of slot needs to track
+    pub state: SlotState,
 }
 
-pub const SLOT_COUNT: i32 = 11; // Must manually keep up to date with the enum ;(
-#[derive(Debug, Clone, Copy)]
+// Slot specific state. Slots that only need the generic cooldown use Nothing.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SlotState {
+    Nothing,
+    BrokenGps { turn_right: bool, activations: u32 },
+    EnergyCell { energy_bonus: f32, generated: f32 },
+    JacksCompass { activations: u32, last_prio: u32 },
+    Magnet { picks: u32 },
+    PurityScanner { improved: u32 },
+    Diagonal { moved: bool, left: bool, steps: u32 },
+    Bigfoot { moved: bool, leaps: u32 },
+    Basher { bashes: u32 },
+    Radar { turns: u32 },
+    GrapplingHook { pulls: u32, distance: u32 },
+}
+
+pub const SLOT_COUNT: usize = 17; // Must equal the number of SlotKind variants (and registry entries)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum SlotKind {
     BrokenGps = 0,
     Windrone = 1,
@@ -32,88 +70,120 @@ pub enum SlotKind {
     PurityScanner = 9,
     RandomStart = 10,
     Sandrone = 11,
-    // Make sure to update the SLOT_COUNT!
+    Diagonal = 12,
+    Bigfoot = 13,
+    Basher = 14,
+    Radar = 15,
+    GrapplingHook = 16,
+    // Make sure to update the SLOT_COUNT and the SLOT_REGISTRY!
 }
 
-// This function serves as a sanity check for the size constant
-fn assert_size(x: &SlotKind) -> i32 {
-    match x {
-        | SlotKind::BrokenGps
-        | SlotKind::Drill
-        | SlotKind::DroneLauncher
-        | SlotKind::Emptiness
-        | SlotKind::EnergyCell
-        | SlotKind::Hammer
-        | SlotKind::JacksCompass
-        | SlotKind::Magnet
-        | SlotKind::PurityScanner
-        | SlotKind::RandomStart
-        | SlotKind::Sandrone
-        | SlotKind::Windrone
-        => SLOT_COUNT, // Update SLOT_COUNT when this function updates
-    }
+/**
+ * Each kind of slot implements this trait. The behavior objects themselves are stateless; all
+ * state lives in the Slottable (or elsewhere on the miner) so a miner can still be serialized.
+ * Every hook except create and ui is optional.
+ */
+pub trait SlotBehavior: Sync {
+    // Create the slot for a new miner. `nth` is the number of slots of this kind before it.
+    fn create(&self, state: &mut AppState, helix: &Helix, slot_index: usize, nth: i32) -> Slottable;
+
+    // Called every biome tick, after the miner made its move
+    fn tick(&self, _options: &mut Options, _state: &mut AppState, _biome: &mut Biome, _slot_index: usize) {}
+
+    // Called after the miner moved from from_x,from_y into an open cell. Return true if that
+    // caused anything to be picked up.
+    fn on_move(&self, _options: &mut Options, _biome: &mut Biome, _slot_index: usize, _from_x: i32, _from_y: i32) -> bool { return false; }
+
+    // Called for everything the miner or one of its drones acquires, before it goes into the
+    // inventory. Return another resource to acquire that instead (like a better gem).
+    fn on_pickup(&self, _biome: &mut Biome, _slot_index: usize, _item: &AcquiredItem) -> Option<Resource> { return None; }
+
+    // Called when the miner bumps into the wall at x,y while moving into dx,dy. Return how much
+    // additional damage to deal to that wall.
+    fn on_bump(&self, _options: &mut Options, _biome: &mut Biome, _slot_index: usize, _x: i32, _y: i32, _dx: i32, _dy: i32) -> i32 { return 0; }
+
+    // Called when the miner broke the wall at x,y. Return true to keep facing the same direction
+    // rather than turning away from the wall.
+    fn on_break(&self, _options: &mut Options, _biome: &mut Biome, _slot_index: usize, _x: i32, _y: i32) -> bool { return false; }
+
+    // Return the title, the progress bar, and the description to show in the slot list
+    fn ui(&self, biome: &Biome, slot_index: usize) -> (String, String, String);
+}
+
+pub struct SlotRegistration {
+    pub kind: SlotKind,
+    // Used in the short string of a helix
+    pub symbol: char,
+    pub title: &'static str,
+    // May this slot appear in randomly generated or mutated helices?
+    pub starter: bool,
+    // Relative chance of a starter slot to be rolled by get_random_slot
+    pub weight: u32,
+    pub behavior: &'static dyn SlotBehavior,
+}
+
+// Must be ordered by the SlotKind value
+pub static SLOT_REGISTRY: [SlotRegistration; SLOT_COUNT] = [
+    SlotRegistration { kind: SlotKind::BrokenGps, symbol: 'G', title: TITLE_BROKEN_GPS, starter: true, weight: 1, behavior: &SlotBrokenGps },
+    SlotRegistration { kind: SlotKind::Windrone, symbol: 'B', title: TITLE_WINDRONE, starter: false, weight: 0, behavior: &SlotWindrone },
+    SlotRegistration { kind: SlotKind::Drill, symbol: 'd', title: TITLE_DRILL, starter: true, weight: 1, behavior: &SlotDrill },
+    SlotRegistration { kind: SlotKind::DroneLauncher, symbol: 'D', title: TITLE_DRONE_LAUNCHER, starter: true, weight: 1, behavior: &SlotDroneLauncher },
+    SlotRegistration { kind: SlotKind::Emptiness, symbol: '.', title: TITLE_EMPTINESS, starter: true, weight: 3, behavior: &SlotEmptiness },
+    SlotRegistration { kind: SlotKind::EnergyCell, symbol: 'E', title: TITLE_ENERGY_CELL, starter: true, weight: 1, behavior: &SlotEnergyCell },
+    SlotRegistration { kind: SlotKind::Hammer, symbol: 'h', title: TITLE_HAMMER, starter: true, weight: 1, behavior: &SlotHammer },
+    SlotRegistration { kind: SlotKind::JacksCompass, symbol: 'J', title: TITLE_JACKS_COMPASS, starter: true, weight: 1, behavior: &SlotJacksCompass },
+    SlotRegistration { kind: SlotKind::Magnet, symbol: 'm', title: TITLE_MAGNET, starter: true, weight: 1, behavior: &SlotMagnet },
+    SlotRegistration { kind: SlotKind::PurityScanner, symbol: 'P', title: TITLE_PURITY_SCANNER, starter: true, weight: 1, behavior: &SlotPurityScanner },
+    // Placeholder for a random starter slot. Only useful in a helix passed in through the CLI.
+    SlotRegistration { kind: SlotKind::RandomStart, symbol: '?', title: TITLE_RANDOM_START, starter: false, weight: 0, behavior: &SlotRandomStart },
+    SlotRegistration { kind: SlotKind::Sandrone, symbol: 'H', title: TITLE_SANDRONE, starter: false, weight: 0, behavior: &SlotSandrone },
+    SlotRegistration { kind: SlotKind::Diagonal, symbol: 'X', title: TITLE_DIAGONAL, starter: true, weight: 1, behavior: &SlotDiagonal },
+    SlotRegistration { kind: SlotKind::Bigfoot, symbol: 'F', title: TITLE_BIGFOOT, starter: true, weight: 1, behavior: &SlotBigfoot },
+    SlotRegistration { kind: SlotKind::Basher, symbol: 'b', title: TITLE_BASHER, starter: true, weight: 1, behavior: &SlotBasher },
+    SlotRegistration { kind: SlotKind::Radar, symbol: 'R', title: TITLE_RADAR, starter: true, weight: 1, behavior: &SlotRadar },
+    SlotRegistration { kind: SlotKind::GrapplingHook, symbol: 'g', title: TITLE_GRAPPLING_HOOK, starter: true, weight: 1, behavior: &SlotGrapplingHook },
+];
+
+pub fn get_slot_registration(kind: SlotKind) -> &'static SlotRegistration {
+    let reg = &SLOT_REGISTRY[kind as usize];
+    assert_eq!(reg.kind as usize, kind as usize, "the SLOT_REGISTRY should be ordered by SlotKind");
+    return reg;
+}
+
+pub fn get_slot_behavior(kind: SlotKind) -> &'static dyn SlotBehavior {
+    return get_slot_registration(kind).behavior;
 }
 
 pub fn get_random_slot(rng: &mut Lcg128Xsl64) -> SlotKind {
-    assert_size(&SlotKind::Drill);
-    let slot_roller: Uniform<i32> = Uniform::from(0..SLOT_COUNT);
-    return match slot_roller.sample(rng) {
-        0 => SlotKind::BrokenGps,
-        1 => SlotKind::Drill,
-        2 => SlotKind::DroneLauncher,
-        3 => SlotKind::Emptiness,
-        4 => SlotKind::EnergyCell,
-        5 => SlotKind::Hammer,
-        6 => SlotKind::Magnet,
-        7 => SlotKind::JacksCompass,
-        8 => SlotKind::PurityScanner,
-        // Must add empty slots for slotkinds that we don't want to generate (TODO: no we dont)
-        9 => SlotKind::Emptiness,  // SlotKind::Windrone
-        10 => SlotKind::Emptiness, // SlotKind::Sandrone
-        11 => SlotKind::Emptiness, // SlotKind::RandomStart // Preventloop
-        _ => panic!("wat?"),
+    // Emptiness weighs more than the others. The original slot table rolled it for the kinds
+    // that should not be generated, and mutation dynamics depend on that ratio.
+    let total: u32 = SLOT_REGISTRY.iter().filter(|reg| reg.starter).map(|reg| reg.weight).sum();
+    let slot_roller: Uniform<u32> = Uniform::from(0..total);
+    let mut roll = slot_roller.sample(rng);
+    for reg in SLOT_REGISTRY.iter().filter(|reg| reg.starter) {
+        if roll < reg.weight {
+            return reg.kind;
+        }
+        roll -= reg.weight;
     }
+    panic!("the roll should be below the total weight");
 }
 
 pub fn slot_type_to_symbol(slot: &SlotKind) -> String {
-    return match slot {
-        SlotKind::BrokenGps => "G".to_string(),
-        SlotKind::Drill => "d".to_string(),
-        SlotKind::DroneLauncher => "D".to_string(),
-        SlotKind::Emptiness => ".".to_string(),
-        SlotKind::EnergyCell => "E".to_string(),
-        SlotKind::Hammer => "h".to_string(),
-        SlotKind::JacksCompass => "J".to_string(),
-        SlotKind::Magnet => "m".to_string(),
-        SlotKind::PurityScanner => "P".to_string(),
-        SlotKind::RandomStart => "?".to_string(),
-        SlotKind::Sandrone => "H".to_string(),
-        SlotKind::Windrone => "B".to_string(),
-    };
+    return get_slot_registration(*slot).symbol.to_string();
 }
 
 pub fn symbol_to_slot_type(sym: char) -> SlotKind {
-    match sym {
-        'G' => SlotKind::BrokenGps,
-        'd' => SlotKind::Drill,
-        'D' => SlotKind::DroneLauncher,
-        '.' => SlotKind::Emptiness,
-        'E' => SlotKind::EnergyCell,
-        'h' => SlotKind::Hammer,
-        'J' => SlotKind::JacksCompass,
-        'm' => SlotKind::Magnet,
-        'P' => SlotKind::PurityScanner,
-        '?' => SlotKind::RandomStart,
-        'H' => SlotKind::Sandrone,
-        'B' => SlotKind::Windrone,
-        // ?: random
-        _ => panic!("add me, {}", sym),
+    for reg in SLOT_REGISTRY.iter() {
+        if reg.symbol == sym {
+            return reg.kind;
+        }
     }
+    panic!("add me, {}", sym);
 }
 
 pub fn create_slot_kind_counter() -> Vec<i32> {
-    assert_eq!(SLOT_COUNT, 11);
-    return vec![0; SLOT_COUNT as usize]; // One for every slot type value
+    return vec![0; SLOT_COUNT]; // One for every slot type value
 }
 
 pub fn slots_to_short_string(slots: [SlotKind; 32]) -> String {
@@ -0,0 +1,75 @@
+use std::fs;
+
+use super::actor::*;
+use super::biome::*;
+use super::miner::*;
+use super::options::*;
+use super::app_state::*;
+use super::world_diff::*;
+use super::{bridge};
+
+// A snapshot is the entire state of one biome at a given tick, plus the batch counters that
+// affect how it ticks. The world is stored as a diff (see world_diff.rs), everything else as is.
+// Restoring a snapshot and ticking it should behave exactly like the original would have.
+
+pub type SerializedBiome = (
+  usize, // index
+  u32, // ticks
+  SerializedWorld,
+  Miner,
+  i32, // state.batch_ticks
+  f32, // state.cost_increase_value
+);
+
+// The web version has no file system to write to
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_biome_snapshot(options: &Options, state: &AppState, biome: &Biome) -> String {
+  // Returns the name of the file it wrote to
+  let s = serde_json::to_string(&(
+    biome.index,
+    biome.ticks,
+    world_serialize_diff(options, &biome.world),
+    &biome.miner,
+    state.batch_ticks,
+    state.cost_increase_value,
+  )).unwrap();
+
+  let f = format!("./biome_{}_{}_{}.json", options.seed, biome.index, biome.ticks);
+  fs::write(&f, s).expect("Unable to write file");
+  return f;
+}
+
+pub fn read_biome_snapshot(file: &str) -> SerializedBiome {
+  let s = fs::read_to_string(file).expect("Unable to read snapshot file");
+  return serde_json::from_str(&s).expect("Unable to parse snapshot file");
+}
+
+pub fn apply_snapshot_world_gen(options: &mut Options, snapshot: &SerializedBiome) {
+  // The world diff only makes sense on top of the same procedural world so adopt its config
+  let (seed, topology, world_size, actor_spawn_rates) = &(snapshot.2).0;
+  options.seed = *seed;
+  options.topology = code_to_topology(*topology);
+  options.world_size = *world_size;
+  options.actor_spawn_rates = actor_spawn_rates.iter().map(|&(code, rate)| (code_to_actor_kind(code), rate)).collect();
+}
+
+pub fn restore_biome_snapshot(options: &mut Options, state: &mut AppState, snapshot: SerializedBiome, index: usize) -> Biome {
+  // Put the snapshot biome at given index in the batch. The batch counters are restored as well.
+  let (_index, ticks, world, miner, batch_ticks, cost_increase_value) = snapshot;
+
+  state.batch_ticks = batch_ticks;
+  state.cost_increase_value = cost_increase_value;
+
+  bridge::log(format!("Restored biome snapshot at tick {} into biome {}", ticks, index).as_str());
+
+  return Biome {
+    index,
+    island: 0,
+    ticks,
+    world: world_deserialize_diff(options, &world),
+    miner,
+    shared_world: false,
+    relative_fitness: None,
+    rival_damage: 0.0,
+  };
+}
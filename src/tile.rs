@@ -24,6 +24,79 @@ pub enum Tile {
   Test3,
 }
 
+pub fn is_open_tile(tile: Tile) -> bool {
+  // Can a movable step into a cell with this tile without bumping into anything?
+  return matches!(tile,
+    | Tile::ExpandoWater
+    | Tile::Empty
+    | Tile::Fountain
+    | Tile::Soil
+    | Tile::ZeroZero
+    | Tile::TenLine
+    | Tile::HideWorld
+    | Tile::Test2
+    | Tile::Test3
+  );
+}
+
+pub fn is_same_passability(a: Tile, b: Tile) -> bool {
+  // Can every kind of movable pass through the one tile exactly when it can pass through the
+  // other? Open tiles, walls (of any strength), and the castle tiles each form a group.
+  let group = |tile: Tile| -> u8 {
+    if is_open_tile(tile) { return 0; }
+    if is_wall_tile(tile) { return 1; }
+    if matches!(tile, Tile::Push | Tile::Impassible) { return 2; }
+    return 3;
+  };
+  return group(a) == group(b);
+}
+
+pub fn is_wall_tile(tile: Tile) -> bool {
+  return matches!(tile, Tile::Wall1 | Tile::Wall2 | Tile::Wall3 | Tile::Wall4);
+}
+
+pub fn tile_to_code(tile: Tile) -> u8 {
+  // Stable numeric code for (de)serialization. Do not change existing codes.
+  return match tile {
+    Tile::ExpandoWater => 0,
+    Tile::Empty => 1,
+    Tile::Fountain => 2,
+    Tile::Impassible => 3,
+    Tile::Push => 4,
+    Tile::Soil => 5,
+    Tile::Wall1 => 6,
+    Tile::Wall2 => 7,
+    Tile::Wall3 => 8,
+    Tile::Wall4 => 9,
+    Tile::ZeroZero => 10,
+    Tile::TenLine => 11,
+    Tile::HideWorld => 12,
+    Tile::Test2 => 13,
+    Tile::Test3 => 14,
+  };
+}
+
+pub fn code_to_tile(code: u8) -> Tile {
+  return match code {
+    0 => Tile::ExpandoWater,
+    1 => Tile::Empty,
+    2 => Tile::Fountain,
+    3 => Tile::Impassible,
+    4 => Tile::Push,
+    5 => Tile::Soil,
+    6 => Tile::Wall1,
+    7 => Tile::Wall2,
+    8 => Tile::Wall3,
+    9 => Tile::Wall4,
+    10 => Tile::ZeroZero,
+    11 => Tile::TenLine,
+    12 => Tile::HideWorld,
+    13 => Tile::Test2,
+    14 => Tile::Test3,
+    _ => panic!("Unknown tile code: {}", code),
+  };
+}
+
 pub fn cell_to_uncolored_string(tile: Tile, pickup: Pickup, _tile_value: u32, wx: i32, wy: i32) -> String {
   return match tile {
     Tile::ExpandoWater => ICON_EXPANDO_WATER.to_string(),
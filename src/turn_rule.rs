@@ -0,0 +1,171 @@
+use rand_pcg::{Lcg128Xsl64};
+use rand::distributions::{Distribution, Uniform};
+
+use super::movable::*;
+use super::options::*;
+use super::world::*;
+use serde_derive::{Serialize, Deserialize};
+
+// Langton's ant style turning rules. A helix carries one rule per TurnContext. A rule is a short
+// string of turns, like the classic "RL" or "LLRR". When the miner is in that context it looks at
+// how often the relevant cell was visited and takes the turn at index `visited % rule length`.
+// Since every move visits a cell, the visit count acts as the color of the cell in Langton's ant.
+//
+// Turns:
+// - N: no turn, keep going straight
+// - L: turn left
+// - R: turn right
+// - U: u-turn
+// - V: turn towards the more visited side (see get_most_visited_dir_from_xydir)
+//
+// The default rules, "N/V", are the original hardcoded behaviour: keep going through open cells
+// and turn towards the more visited side after bumping into a wall.
+// Push and impassible tiles are not covered. The sand castle phases depend on how the miner turns
+// there (see push_corner_move), so that stays fixed.
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Turn {
+  Straight,
+  Left,
+  Right,
+  Back,
+  MostVisited,
+}
+pub const TURNS: [Turn; 5] = [Turn::Straight, Turn::Left, Turn::Right, Turn::Back, Turn::MostVisited];
+
+#[derive(Debug, Clone, Copy)]
+pub enum TurnContext {
+  // The miner just moved into an open cell. Indexed by the visits of that cell before this move.
+  Open = 0,
+  // The miner just bumped into a wall. Indexed by the visits of the cell the miner is on.
+  Wall = 1,
+}
+pub const TURN_CONTEXT_COUNT: usize = 2;
+
+pub const TURN_RULE_MAX_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TurnRule {
+  // Only the first len turns are used
+  pub turns: [Turn; TURN_RULE_MAX_LEN],
+  pub len: usize,
+}
+
+pub fn create_default_turn_rules() -> [TurnRule; TURN_CONTEXT_COUNT] {
+  return parse_turn_rules("N/V").unwrap();
+}
+
+fn turn_to_char(turn: Turn) -> char {
+  return match turn {
+    Turn::Straight => 'N',
+    Turn::Left => 'L',
+    Turn::Right => 'R',
+    Turn::Back => 'U',
+    Turn::MostVisited => 'V',
+  };
+}
+
+fn char_to_turn(c: char) -> Result<Turn, String> {
+  return match c {
+    'N' => Ok(Turn::Straight),
+    'L' => Ok(Turn::Left),
+    'R' => Ok(Turn::Right),
+    'U' => Ok(Turn::Back),
+    'V' => Ok(Turn::MostVisited),
+    _ => Err(format!("Unknown turn `{}`, expecting one of N, L, R, U, V", c)),
+  };
+}
+
+pub fn turn_rules_to_string(rules: &[TurnRule; TURN_CONTEXT_COUNT]) -> String {
+  let parts: Vec<String> = rules.iter().map(|rule| rule.turns[0..rule.len].iter().map(|&turn| turn_to_char(turn)).collect()).collect();
+  return parts.join("/");
+}
+
+pub fn parse_turn_rules(spec: &str) -> Result<[TurnRule; TURN_CONTEXT_COUNT], String> {
+  // One rule per context, separated by a slash, like "RL/V"
+  let parts: Vec<&str> = spec.split('/').collect();
+  if parts.len() != TURN_CONTEXT_COUNT {
+    return Err(format!("Expecting {} turn rules separated by a slash, got `{}`", TURN_CONTEXT_COUNT, spec));
+  }
+  let mut rules = [TurnRule { turns: [Turn::Straight; TURN_RULE_MAX_LEN], len: 1 }; TURN_CONTEXT_COUNT];
+  for (n, part) in parts.iter().enumerate() {
+    let part = part.trim();
+    if part.len() == 0 || part.len() > TURN_RULE_MAX_LEN {
+      return Err(format!("A turn rule must have 1 to {} turns, got `{}`", TURN_RULE_MAX_LEN, part));
+    }
+    for (i, c) in part.chars().enumerate() {
+      rules[n].turns[i] = char_to_turn(c)?;
+    }
+    rules[n].len = part.len();
+  }
+  return Ok(rules);
+}
+
+pub fn mutate_turn_rules(rng: &mut Lcg128Xsl64, rules: &[TurnRule; TURN_CONTEXT_COUNT], rate: f32) -> [TurnRule; TURN_CONTEXT_COUNT] {
+  // Each rule has a `rate` percent chance to change one turn, or to grow or shrink by one turn
+  let pct_roller: Uniform<f32> = Uniform::from(0.0..100.0);
+  let turn_roller: Uniform<usize> = Uniform::from(0..TURNS.len());
+  let mut mutated = *rules;
+  for rule in mutated.iter_mut() {
+    if pct_roller.sample(rng) >= rate {
+      continue;
+    }
+    match Uniform::from(0..3).sample(rng) {
+      0 => {
+        let i = Uniform::from(0..rule.len).sample(rng);
+        rule.turns[i] = TURNS[turn_roller.sample(rng)];
+      }
+      1 => if rule.len < TURN_RULE_MAX_LEN {
+        rule.turns[rule.len] = TURNS[turn_roller.sample(rng)];
+        rule.len += 1;
+      },
+      _ => if rule.len > 1 {
+        rule.len -= 1;
+      },
+    }
+  }
+  return mutated;
+}
+
+pub fn apply_turn_rule(options: &Options, world: &World, rule: &TurnRule, visited: u32, x: i32, y: i32, dir: Direction) -> Direction {
+  // Returns the new direction for a movable at x,y facing dir
+  return match rule.turns[visited as usize % rule.len] {
+    Turn::Straight => dir,
+    Turn::Left => turn_left(dir),
+    Turn::Right => turn_right(dir),
+    Turn::Back => turn_back(dir),
+    Turn::MostVisited => get_most_visited_dir_from_xydir(options, world, x, y, dir),
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_turn_rules_round_trips() {
+    for spec in ["N/V", "RL/V", "LRUNV/N", "R/LLRR"].iter() {
+      assert_eq!(turn_rules_to_string(&parse_turn_rules(spec).unwrap()), *spec);
+    }
+  }
+
+  #[test]
+  fn parse_turn_rules_reads_each_context() {
+    let rules = parse_turn_rules("RL/V").unwrap();
+    assert_eq!(rules[0].len, 2);
+    assert_eq!(rules[0].turns[0], Turn::Right);
+    assert_eq!(rules[0].turns[1], Turn::Left);
+    assert_eq!(rules[1].len, 1);
+    assert_eq!(rules[1].turns[0], Turn::MostVisited);
+  }
+
+  #[test]
+  fn parse_turn_rules_rejects_bad_specs() {
+    assert!(parse_turn_rules("").is_err());
+    assert!(parse_turn_rules("N").is_err());
+    assert!(parse_turn_rules("N/V/N").is_err());
+    assert!(parse_turn_rules("N/").is_err());
+    assert!(parse_turn_rules("Q/V").is_err());
+    assert!(parse_turn_rules(&format!("{}/V", "N".repeat(TURN_RULE_MAX_LEN + 1))).is_err());
+  }
+}
@@ -6,6 +6,14 @@ pub const INIT_BLOCKS_PER_ROW: i32 = WIDTH >> 1; // Half?
 pub const E_COUNT: i32 = 50; // How many energy pickups do we spawn
 pub const E_VALUE: i32 = 125; // Energy pickup bonus. 5%?
 pub const INIT_ENERGY: i32 = 5000;
+// Energy drained per tick for every percent of the points and energy pickup genes. Those genes
+// are not free, otherwise evolution would simply max them out.
+pub const GENE_UPKEEP: f32 = 0.01;
+// Energy restored by eating a ration
+pub const RATION_ENERGY: f32 = 500.0;
+// How long a flare keeps the area lit, and how far (in cells, each axis) a lit compass looks
+pub const FLARE_TICKS: u32 = 100;
+pub const FLARE_RANGE: i32 = 8;
 
 pub const WHAT_MINER: i32 = 0;
 pub const WHAT_DRONE: i32 = 1;
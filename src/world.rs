@@ -1,14 +1,17 @@
 // use std::fmt::Write;
+use std::collections::HashSet;
 use std::collections::VecDeque;
 
 use rand::prelude::*;
 use rand_pcg::Pcg64;
 use rand::distributions::{Standard};
-use crate::slot_jacks_compass::ui_slot_jacks_compass;
 
 use super::cell::*;
 use super::helix::*;
 use super::fountain::*;
+use super::actor::*;
+use super::hunger::*;
+use super::progression::*;
 use super::drone_win::*;
 use super::drone_san::*;
 use super::color::*;
@@ -21,18 +24,12 @@ use super::pickup::*;
 use super::biome::*;
 use super::tile::*;
 use super::utils::*;
-use super::slot_drone_launcher::*;
-use super::slot_broken_gps::*;
-use super::slot_drill::*;
-use super::slot_hammer::*;
-use super::slot_magnet::*;
-use super::slot_purity_scanner::*;
-use super::slot_energy_cell::*;
-use super::slot_emptiness::*;
-use super::slot_windrone::*;
-use super::slot_sandrone::*;
 use super::expando::*;
+use super::fluid::*;
+use super::castle_ca::*;
 use super::app_state::*;
+use super::mutation::*;
+use super::consumable::*;
 
 // The world is procedurally generated and has no theoretical bounds.
 // The map retained in memory is only has big as has been visited. Any unvisited cell (or well, any
@@ -49,13 +46,14 @@ use super::app_state::*;
 //   - Gives you a different path, period
 // - double energy
 //   - reach paths others cant (?)
-// - diagonal movement
+// - diagonal movement (see slot_diagonal.rs)
 //   - reach paths others cant
 // - random teleporter / glitching
 //   - dunno if this makes sense. maybe hefty energy or slow reload or whatever.
-// - bigfoot, one step moves you two spaces if you can
-// - basher, don't change direction if you change a block into a diamond
-// - radar, prefer to turn towards a diamond if you can
+// - bigfoot, one step moves you two spaces if you can (see slot_bigfoot.rs)
+// - basher, don't change direction if you change a block into a diamond (see slot_basher.rs)
+// - radar, prefer to turn towards a diamond if you can (see slot_radar.rs)
+// - grappling hook, pulls you to the nearest block in front of you (see slot_grappling_hook.rs)
 
 // Power up / character ability ideas:
 // - after breaking a block do not change direction
@@ -90,6 +88,20 @@ pub struct World {
   pub tiles: Grid,
   pub expandos: Vec<Expando>,
   pub fountains: Vec<Fountain>,
+  // Npcs living in this world (see actor.rs)
+  pub actors: Vec<Actor>,
+  // Cells that were turned into soil, where crops grow (see hunger.rs). May contain cells that are no longer soil.
+  pub soil_cells: HashSet<(i32, i32)>,
+  // Water cells that may still flow
+  pub fluids: FluidQueue,
+  // Bumped whenever a tile changes so cached drone paths know to check themselves (see pathfinding.rs)
+  pub tile_version: u32,
+  // Bumped only when a tile changes whether it can be passed at all, see is_same_passability
+  pub passability_version: u32,
+  // Other miners in this world while an arena miner ticks, as biome index and position (see arena.rs)
+  pub rivals: Vec<(usize, i32, i32)>,
+  // Energy lost by rivals that were rammed during this tick, by biome index
+  pub rival_hits: Vec<(usize, f32)>,
 }
 
 pub fn generate_cell(options: &Options, x: i32, y: i32) -> Cell {
@@ -102,6 +114,12 @@ pub fn generate_cell(options: &Options, x: i32, y: i32) -> Cell {
   //   return Cell::TenLine;
   // }
 
+  if is_world_border(options, x, y) {
+    return Cell { tile: Tile::Impassible, pickup: Pickup::Nothing, tile_value: 0, pickup_value: 0, visited: 0, fluid: 0 };
+  }
+  // A torus repeats itself so generate the canonical cell
+  let (x, y) = wrap_coord(options, x, y);
+
   // println!("  generate_cell({}, {})", x, y);
   // Take the world seed and add the x as a <<32 value and y as is to the seed
   // If either x or y are negative they should subtract that value from the world seed
@@ -117,9 +135,9 @@ pub fn generate_cell(options: &Options, x: i32, y: i32) -> Cell {
   if energy_roll < 0.05 {
     if energy_roll < 0.01 {
       // For windrones. Don't need as much (but some)
-      return Cell { tile: Tile::Empty, pickup: Pickup::Wind, tile_value: 0, pickup_value: 0, visited: 0 };
+      return Cell { tile: Tile::Empty, pickup: Pickup::Wind, tile_value: 0, pickup_value: 0, visited: 0, fluid: 0 };
     }
-    return Cell { tile: Tile::Empty, pickup: Pickup::Energy, tile_value: 0, pickup_value: 0, visited: 0 };
+    return Cell { tile: Tile::Empty, pickup: Pickup::Energy, tile_value: 0, pickup_value: 0, visited: 0, fluid: 0 };
   }
 
   // Roughly half the cells should be filled with walls
@@ -146,17 +164,17 @@ pub fn generate_cell(options: &Options, x: i32, y: i32) -> Cell {
       else { Pickup::Stone };
 
     if kind_roll < 0.1 {
-      return Cell { tile: Tile::Wall3, pickup: reward_value, tile_value, pickup_value, visited: 0 };
+      return Cell { tile: Tile::Wall3, pickup: reward_value, tile_value, pickup_value, visited: 0, fluid: 0 };
     }
 
     if kind_roll < 0.4 {
-      return Cell { tile: Tile::Wall2, pickup: reward_value, tile_value, pickup_value, visited: 0 };
+      return Cell { tile: Tile::Wall2, pickup: reward_value, tile_value, pickup_value, visited: 0, fluid: 0 };
     }
 
-    return Cell { tile: Tile::Wall1, pickup: reward_value, tile_value, pickup_value, visited: 0 };
+    return Cell { tile: Tile::Wall1, pickup: reward_value, tile_value, pickup_value, visited: 0, fluid: 0 };
   }
 
-  return Cell { tile: Tile::Empty, pickup: Pickup::Nothing, tile_value: 0, pickup_value: 0, visited: 0 };
+  return Cell { tile: Tile::Empty, pickup: Pickup::Nothing, tile_value: 0, pickup_value: 0, visited: 0, fluid: 0 };
 }
 
 pub fn world_width(world: &World) -> i32 {
@@ -189,7 +207,7 @@ pub fn generate_world(options: &Options) -> World {
 
   let mut ygrid: VecDeque<VecDeque<Cell>> = VecDeque::new();
   let mut xgrid: VecDeque<Cell> = VecDeque::new();
-  xgrid.push_back(Cell { tile: Tile::Empty, pickup: Pickup::Nothing, tile_value: 0, pickup_value: 0, visited: 0 });
+  xgrid.push_back(Cell { tile: Tile::Empty, pickup: Pickup::Nothing, tile_value: 0, pickup_value: 0, visited: 0, fluid: 0 });
   ygrid.push_back(xgrid);
 
   let mut world = World {
@@ -200,6 +218,13 @@ pub fn generate_world(options: &Options) -> World {
     tiles: ygrid,
     expandos: vec!(),
     fountains: vec!(),
+    actors: vec!(),
+    soil_cells: HashSet::new(),
+    fluids: create_fluid_queue(),
+    tile_version: 0,
+    passability_version: 0,
+    rivals: vec!(),
+    rival_hits: vec!(),
   };
 
   // Use this to prerender part of the world for inspection reasons
@@ -209,7 +234,7 @@ pub fn generate_world(options: &Options) -> World {
   return world;
 }
 
-pub fn tick_world(options: &mut Options, _state: &mut AppState, biome: &mut Biome) {
+pub fn tick_world(options: &mut Options, state: &mut AppState, biome: &mut Biome) {
   // world: &mut World, options: &Options, sandrone: &Sandrone
 
   // Walk backwards because they may be removed when they become depleted
@@ -219,73 +244,12 @@ pub fn tick_world(options: &mut Options, _state: &mut AppState, biome: &mut Biom
   for n in (0..biome.world.fountains.len()).rev() {
     tick_fountain(n, &mut biome.world, options);
   }
+  tick_fluids(&mut biome.world, options);
+  tick_actors(options, &mut biome.world);
+  tick_crops(options, state, &mut biome.world);
 
-  // Game of life the castle
-  if false && biome.miner.sandrone.post_castle > 0 {
-    // Figure out the rectangle and CA them
-    let magic_min_x = biome.miner.sandrone.expansion_min_x;
-    let magic_min_y = biome.miner.sandrone.expansion_min_y;
-    let magic_max_x = biome.miner.sandrone.expansion_max_x;
-    let magic_max_y = biome.miner.sandrone.expansion_max_y;
-
-    for y in magic_min_y+1..magic_max_y {
-      for x in magic_min_x+1..magic_max_x {
-        // Each cell can have one of two tiles; wall or empty. In addition it can have three
-        // sub-states; empty, medrone, or sandrone.
-        // Apply a CA rule for each. Maybe wrap-around, maybe not.
-
-        // abc
-        // def
-        // ghi
-
-        let a = matches!(get_cell_tile_at(options, &biome.world, x - 1, y - 1), Tile::Push);
-        let b = matches!(get_cell_tile_at(options, &biome.world, x + 0, y - 1), Tile::Push);
-        let c = matches!(get_cell_tile_at(options, &biome.world, x + 1, y - 1), Tile::Push);
-        let d = matches!(get_cell_tile_at(options, &biome.world, x - 1, y + 0), Tile::Push);
-        let e = matches!(get_cell_tile_at(options, &biome.world, x + 0, y + 0), Tile::Push);
-        let f = matches!(get_cell_tile_at(options, &biome.world, x + 1, y + 0), Tile::Push);
-        let g = matches!(get_cell_tile_at(options, &biome.world, x - 1, y + 1), Tile::Push);
-        let h = matches!(get_cell_tile_at(options, &biome.world, x + 0, y + 1), Tile::Push);
-        let i = matches!(get_cell_tile_at(options, &biome.world, x + 1, y + 1), Tile::Push);
-
-        // Find patterns, including rotations and mirrors
-
-        // // Clean up entirely
-        // if e && (
-        //   (d && !(b || c || f || i || h)) ||
-        //   (b && !(f || i || h || g || d)) ||
-        //   (f && !(h || g || d || a || b)) ||
-        //   (h && !(d || a || b || c || f))
-        // ) {
-        //   //  xxx   xox   xxx   xxx      xxx   oox   xxo   xxx      oxx   xoo   xxx   xxx      oxx   ooo   xxo   xxx
-        //   //  oox   xox   xoo   xox      oox   xox   xoo   xox      oox   xox   xoo   xox      oox   xox   xoo   xox
-        //   //  xxx   xxx   xxx   xox      oxx   xxx   xxx   xoo      xxx   xxx   xxo   oox      oxx   xxx   xxo   ooo
-        //
-        //   set_cell_tile_at(options, &mut biome.world, x, y, Tile::Impassible);
-        // }
-
-        // Game of life, part 1
-        // - any live cell with 2 or 3 live neighbors, survives
-        // - any dead cell with 3 neighbors, lives
-        // - anything else is ded
-        let n = (a as u8) + (b as u8) + (c as u8) + (d as u8) + (f as u8) + (g as u8) + (h as u8) + (i as u8);
-        let v = if e {
-          if n == 2 || n == 3 { 1 } else { 0 }
-        } else {
-          if n == 3 { 1 } else { 0 }
-        };
-        set_cell_tile_value_at(options, &mut biome.world, x, y, v);
-      }
-    }
-
-    for y in magic_min_y+1..magic_max_y {
-      for x in magic_min_x+1..magic_max_x {
-        // Game of life; part 2
-        let v = if get_cell_tile_value_at(options, &biome.world, x, y) == 0 { Tile::Impassible } else { Tile::Push };
-        set_cell_tile_at(options, &mut biome.world, x, y, v);
-      }
-    }
-  }
+  // Cellular automaton over the castle, if enabled
+  tick_castle_ca(options, state, biome);
 }
 
 // fn bound_ex(x: i32, y: i32, min_x: i32, min_y: i32, max_x: i32, max_y: i32) -> bool {
@@ -316,7 +280,7 @@ pub fn assert_arr_xy_in_world(world: &World, wx: i32, wy: i32, ax: usize, ay: us
   assert!(world.tiles[ay].len() > ax, "assert_arr_xy_in_world; tile.len <= ax; {} > {}", world.tiles[ay].len(), ax);
 }
 
-fn paint_maybe(x: i32, y: i32, what: String, view: &mut Vec<Vec<String>>, viewport_offset_x: i32, viewport_offset_y: i32, viewport_size_w: usize, viewport_size_h: usize, vox: i32, voy: i32) {
+fn paint_maybe(options: &Options, x: i32, y: i32, what: String, view: &mut Vec<Vec<String>>, viewport_offset_x: i32, viewport_offset_y: i32, viewport_size_w: usize, viewport_size_h: usize, vox: i32, voy: i32) {
   // if the viewport offsets at <-25, -25> and the miner is at <0,0> then paint it at <25,25>
   // <-25,-25> and <1,1> then <26,26>
   // <0,0> and <10,20> then <10,20>
@@ -326,6 +290,15 @@ fn paint_maybe(x: i32, y: i32, what: String, view: &mut Vec<Vec<String>>, viewpo
   // Subtract the viewport coords from the actor coords
   // That's where to paint the actor
 
+  // On a torus, paint the copy of the actor that is closest to the right/bottom of the viewport origin
+  let (x, y) =
+    if options.topology == WorldTopology::Torus {
+      let period = options.world_size * 2 + 1;
+      (viewport_offset_x + (x - viewport_offset_x).rem_euclid(period), viewport_offset_y + (y - viewport_offset_y).rem_euclid(period))
+    } else {
+      (x, y)
+    };
+
   // First confirm whether the actor is within the viewport anyways
   if bound_inc(x, y, viewport_offset_x, viewport_offset_y, viewport_offset_x + viewport_size_w as i32, viewport_offset_y + viewport_size_h as i32) {
     // Yes it is. Convert the coords to absolute (vec) indexes.
@@ -351,8 +324,13 @@ fn paint_maybe(x: i32, y: i32, what: String, view: &mut Vec<Vec<String>>, viewpo
   }
 }
 
-fn paint_biome_actors(biome: &Biome, options: &Options, view: &mut Vec<Vec<String>>, viewport_offset_x: i32, viewport_offset_y: i32, viewport_size_w: usize, viewport_size_h: usize, vox: i32, voy: i32) {
+fn paint_biome_actors(biome: &Biome, world: &World, options: &Options, view: &mut Vec<Vec<String>>, viewport_offset_x: i32, viewport_offset_y: i32, viewport_size_w: usize, viewport_size_h: usize, vox: i32, voy: i32) {
   if biome.index == options.visible_index {
+    // The npcs of the world go below the drones and the miner
+    for actor in &world.actors {
+      paint_maybe(options, actor.x, actor.y, ui_actor(actor, options), view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
+    }
+
     // Paint the drones first. This way the miner goes on top in case of overlap.
     for drone in &biome.miner.drones {
       if drone.movable.now_energy == 0.0 {
@@ -372,7 +350,7 @@ fn paint_biome_actors(biome: &Biome, options: &Options, view: &mut Vec<Vec<Strin
           options
         ).to_string();
 
-      paint_maybe(drone.movable.x, drone.movable.y, drone_visual, view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
+      paint_maybe(options, drone.movable.x, drone.movable.y, drone_visual, view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
     }
   }
 
@@ -408,7 +386,7 @@ fn paint_biome_actors(biome: &Biome, options: &Options, view: &mut Vec<Vec<Strin
       }
     };
 
-  paint_maybe(biome.miner.movable.x, biome.miner.movable.y, miner_visual, view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
+  paint_maybe(options, biome.miner.movable.x, biome.miner.movable.y, miner_visual, view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
 }
 
 pub fn serialize_world(world0: &World, biomes: &Vec<Biome>, options: &Options, state: &mut AppState, best_miner_str: String, hmap_str: String) -> String {
@@ -590,15 +568,15 @@ pub fn serialize_world(world0: &World, biomes: &Vec<Biome>, options: &Options, s
   if options.show_biomes {
     for (i, biome) in &mut biomes.iter().enumerate() {
       if i == 0 { continue; }
-      paint_biome_actors(biome, options, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
+      paint_biome_actors(biome, world0, options, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
     }
   }
-  paint_biome_actors(&biomes[options.visible_index], options, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
+  paint_biome_actors(&biomes[options.visible_index], world0, options, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
 
   // Paint the windrone, if it's in flight
   // The windrone is incorporeal (like a ghost, unable to collide with objects or whatever). Paint on top.
   if matches!(biomes[options.visible_index].miner.windrone.state, WindroneState::FlyingToGoal) || matches!(biomes[options.visible_index].miner.windrone.state, WindroneState::FlyingHome) {
-    paint_maybe(biomes[options.visible_index].miner.windrone.movable.x, biomes[options.visible_index].miner.windrone.movable.y, ui_windrone(&biomes[options.visible_index].miner.windrone, options), &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
+    paint_maybe(options, biomes[options.visible_index].miner.windrone.movable.x, biomes[options.visible_index].miner.windrone.movable.y, ui_windrone(&biomes[options.visible_index].miner.windrone, options), &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
   }
 
   // Paint the sandrone, if it's moving
@@ -610,7 +588,7 @@ pub fn serialize_world(world0: &World, biomes: &Vec<Biome>, options: &Options, s
     | SandroneState::PickingUpMiner
     | SandroneState::DeliveringMiner
     | SandroneState::Redecorating
-    => paint_maybe(biomes[options.visible_index].miner.sandrone.movable.x, biomes[options.visible_index].miner.sandrone.movable.y, ui_sandrone(&biomes[options.visible_index].miner.sandrone, options), &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy),
+    => paint_maybe(options, biomes[options.visible_index].miner.sandrone.movable.x, biomes[options.visible_index].miner.sandrone.movable.y, ui_sandrone(&biomes[options.visible_index].miner.sandrone, options), &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy),
     SandroneState::Unconstructed => {}
     SandroneState::WaitingForWater => {}
   }
@@ -648,18 +626,18 @@ pub fn serialize_world(world0: &World, biomes: &Vec<Biome>, options: &Options, s
     for i in biomes[options.visible_index].miner.sandrone.expansion_min_x-1..biomes[options.visible_index].miner.sandrone.expansion_max_x+1 {
       if lifted || i % 3 == 0 {
         let p = pump(post, i as f32, (biomes[options.visible_index].miner.sandrone.expansion_min_y - 1) as f32, dd);
-        paint_maybe(p.0, p.1, if ticks0 % 2 == 1 {a.to_string() } else {b.to_string()}, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
+        paint_maybe(options, p.0, p.1, if ticks0 % 2 == 1 {a.to_string() } else {b.to_string()}, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
 
         let q = pump(post, i as f32, (biomes[options.visible_index].miner.sandrone.expansion_max_y + 1) as f32, dd);
-        paint_maybe(q.0, q.1, if ticks0 % 2 == 1 {a.to_string() } else {b.to_string()}, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
+        paint_maybe(options, q.0, q.1, if ticks0 % 2 == 1 {a.to_string() } else {b.to_string()}, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
       }
     }
     for j in biomes[options.visible_index].miner.sandrone.expansion_min_y-1..biomes[options.visible_index].miner.sandrone.expansion_max_y + 2 {
       if lifted || j % 3 == 0 {
         let p = pump(post, (biomes[options.visible_index].miner.sandrone.expansion_min_x - 1) as f32, j as f32, dd);
-        paint_maybe(p.0, p.1, if ticks0 % 2 == 1 {a.to_string() } else {b.to_string()}, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
+        paint_maybe(options, p.0, p.1, if ticks0 % 2 == 1 {a.to_string() } else {b.to_string()}, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
         let q = pump(post, (biomes[options.visible_index].miner.sandrone.expansion_max_x + 1) as f32, j as f32, dd);
-        paint_maybe(q.0, q.1, if ticks0 % 2 == 1 {a.to_string() } else {b.to_string()}, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
+        paint_maybe(options, q.0, q.1, if ticks0 % 2 == 1 {a.to_string() } else {b.to_string()}, &mut view, viewport_offset_x, viewport_offset_y, viewport_size_w, viewport_size_h, vox, voy);
       }
     }
   }
@@ -689,7 +667,8 @@ pub fn serialize_world(world0: &World, biomes: &Vec<Biome>, options: &Options, s
   let vlen = view.len();
 
   // Append each line to the map
-  view[1].push(format!(" Gene mutation rate: {}%  Slot mutation rate: {}%   Miner batch size: {}   Reset rate: {: <120}", options.mutation_rate_genes, options.mutation_rate_slots, options.batch_size, options.reset_rate).to_string());
+  let (rate_genes, rate_slots) = get_mutation_rates(options, state, &biomes[options.visible_index].miner.helix);
+  view[1].push(format!(" Gene mutation rate: {:.1}%  Slot mutation rate: {:.1}% ({:?})   Miner batch size: {}   Reset rate: {: <120}", rate_genes, rate_slots, options.mutation_schedule, options.batch_size, options.reset_rate).to_string());
   view[2].push(format!(" {: <150}", best_miner_str));
   view[3].push(format!(" {: <150}", hmap_str));
   view[4].push(format!(" Batch tick: {} Decay interval: {} Decay rate: {} Current decay value: {: <100}", state.batch_ticks, options.cost_increase_interval, options.cost_increase_rate, state.cost_increase_value));
@@ -697,29 +676,16 @@ pub fn serialize_world(world0: &World, biomes: &Vec<Biome>, options: &Options, s
   view[6].push(format!(" Miner {}; {: <150}", options.visible_index, ' '));
   view[7].push(std::iter::repeat(' ').take(143).collect::<String>());
   view[8].push(format!("   {: <150}", biomes[options.visible_index].miner.helix));
-  view[9].push(format!("   XY: {: >4}, {: <10} {: <45} Points: {: <10} Energy {: <10}", biomes[options.visible_index].miner.movable.x, biomes[options.visible_index].miner.movable.y, progress_bar(30, biomes[options.visible_index].miner.movable.now_energy, biomes[options.visible_index].miner.movable.init_energy, true), get_points(&biomes[options.visible_index].miner.meta.inventory), biomes[options.visible_index].miner.movable.now_energy.round()).to_string());
-  view[10].push(format!("   Inventory:   {: <100}", ui_inventory(&biomes[options.visible_index].miner.meta.inventory, options)));
+  view[9].push(format!("   XY: {: >4}, {: <10} {: <45} Points: {: <10} Energy {: <10} {: <40}", biomes[options.visible_index].miner.movable.x, biomes[options.visible_index].miner.movable.y, progress_bar(30, biomes[options.visible_index].miner.movable.now_energy, biomes[options.visible_index].miner.movable.init_energy, true), get_points(&biomes[options.visible_index].miner.meta.inventory), biomes[options.visible_index].miner.movable.now_energy.round(), ui_hunger(&biomes[options.visible_index])).to_string());
+  view[10].push(format!("   Inventory:   {}   {: <60}", ui_inventory(&biomes[options.visible_index].miner.meta.inventory, options), ui_consumables(&biomes[options.visible_index])));
   let t = helix_serialize(&biomes[options.visible_index].miner.helix);
   view[11].push(add_fg_color_with_reset(&format!("   Current miner code: `{}`", serde_json::to_string(&t).unwrap()).to_string(), COLOR_GREY, options));
-  view[12].push(std::iter::repeat(' ').take(100).collect::<String>());
+  view[12].push(format!("   {: <150}", ui_progression(&biomes[options.visible_index])));
 
   let so = 13;
   for n in 0..biomes[options.visible_index].miner.slots.len() {
     let slot: &Slottable = &biomes[options.visible_index].miner.slots[n];
-    let (head, progress, tail) = match slot.kind {
-      SlotKind::BrokenGps => ui_slot_broken_gps(slot),
-      SlotKind::Drill => ui_slot_drill(slot),
-      SlotKind::DroneLauncher => ui_slot_drone_launcher(slot, &biomes[options.visible_index].miner.drones[slot.nth as usize]),
-      SlotKind::Emptiness => ui_slot_emptiness(slot),
-      SlotKind::EnergyCell => ui_slot_energy_cell(slot),
-      SlotKind::Hammer => ui_slot_hammer(slot),
-      SlotKind::JacksCompass => ui_slot_jacks_compass(slot),
-      SlotKind::Magnet => ui_slot_magnet(slot),
-      SlotKind::PurityScanner => ui_slot_purity_scanner(slot),
-      SlotKind::RandomStart => panic!("Running miners should not get the RandomStart slot"),
-      SlotKind::Sandrone => ui_slot_sandrone(slot, &biomes[options.visible_index].miner.sandrone, biomes[options.visible_index].miner.meta.inventory.sand),
-      SlotKind::Windrone => ui_slot_windrone(slot, &biomes[options.visible_index].miner.windrone, biomes[options.visible_index].miner.meta.inventory.wind),
-    };
+    let (head, progress, tail) = get_slot_behavior(slot.kind).ui(&biomes[options.visible_index], n);
     view[so + n].push(format!(" {: <20} {: <40} {: <70}", head, progress, tail).to_string());
   }
 
@@ -734,7 +700,7 @@ pub fn serialize_world(world0: &World, biomes: &Vec<Biome>, options: &Options, s
   view[vlen - 4].push(format!("       slot mutation rate [{}]  up: l⏎   up 5: ll⏎   down: k⏎   down 5: kk⏎ {: <50}", options.mutation_rate_slots, ' '));
   view[vlen - 3].push(format!("       batch size [{}]  up: m⏎   down: n⏎   restart with random helix: r⏎   restart from best: b⏎ {: <50}", options.batch_size, ' '));
   view[vlen - 2].push(format!("       mutate [{}]: g⏎   auto reset [{}] after [{}] miners: t⏎ {: <50}", if options.mutate_from_best { "overall best" } else { "last winner" }, if options.reset_after_noop { "after noop" } else { "regardless" }, options.reset_rate, ' '));
-  view[vlen - 1].push(format!("       arrow keys move viewport. c: center. f: toggle auto-follow. h: home"));
+  view[vlen - 1].push(format!("       arrow keys move viewport. c: center. f: toggle auto-follow. h: home. s: save biome snapshot"));
 
   if options.html_mode { // Could do this with a macro but why, :shrug:
     for y in 0..view.len() {
@@ -762,6 +728,10 @@ pub fn serialize_world(world0: &World, biomes: &Vec<Biome>, options: &Options, s
 pub fn ensure_cell_in_world(world: &mut World, options: &Options, x: i32, y: i32) {
   // Expansion occurs by pre/appending a cell to all rows/cols of the axis that expands
 
+  // On a torus the world never needs to grow beyond its canonical range. Callers that index
+  // into the tiles directly must wrap their coords with wrap_coord() as well.
+  let (x, y) = wrap_coord(options, x, y);
+
   // Note: the world bounds are inclusive (so they exist if x>=world.min_x and x<=world.max_x)
 
   // println!("ensure_cell_in_world: world box: {}X{} ~ {}X{}, target coord: {}X{}", world.min_x, world.min_y, world.max_x, world.max_y, x, y);
@@ -779,6 +749,7 @@ pub fn ensure_cell_in_world(world: &mut World, options: &Options, x: i32, y: i32
         let gx = world.min_x - i;
         let gy = world.min_y + j;
         let cell = generate_cell(&options, gx, gy);
+        spawn_cell_actor(options, &mut world.actors, gx, gy, &cell);
         row.push_front(cell);
       }
     }
@@ -796,6 +767,7 @@ pub fn ensure_cell_in_world(world: &mut World, options: &Options, x: i32, y: i32
         let gx = world.max_x + i;
         let gy = world.min_y + j;
         let cell = generate_cell(&options, gx, gy);
+        spawn_cell_actor(options, &mut world.actors, gx, gy, &cell);
         row.push_back(cell);
       }
     }
@@ -815,6 +787,7 @@ pub fn ensure_cell_in_world(world: &mut World, options: &Options, x: i32, y: i32
         let gx = world.min_x + i;
         let gy = world.min_y - j;
         let cell = generate_cell(&options, gx, gy);
+        spawn_cell_actor(options, &mut world.actors, gx, gy, &cell);
         new_row.push_back(cell);
       }
       world.tiles.push_front(new_row);
@@ -834,6 +807,7 @@ pub fn ensure_cell_in_world(world: &mut World, options: &Options, x: i32, y: i32
         let gx = world.min_x + i;
         let gy = world.max_y + j;
         let cell = generate_cell(&options, gx, gy);
+        spawn_cell_actor(options, &mut world.actors, gx, gy, &cell);
         new_row.push_back(cell);
       }
       world.tiles.push_back(new_row);
@@ -844,14 +818,15 @@ pub fn ensure_cell_in_world(world: &mut World, options: &Options, x: i32, y: i32
 }
 
 pub fn create_unvisited_cell(tile: Tile, pickup: Pickup, tile_value: u32, pickup_value: u32) -> Cell {
-  return Cell { tile, pickup, tile_value, pickup_value, visited: 0 };
+  return Cell { tile, pickup, tile_value, pickup_value, visited: 0, fluid: 0 };
 }
 
 pub fn create_visited_cell(tile: Tile, pickup: Pickup, tile_value: u32, pickup_value: u32, visited: u32) -> Cell {
-  return Cell { tile, pickup, tile_value, pickup_value, visited };
+  return Cell { tile, pickup, tile_value, pickup_value, visited, fluid: 0 };
 }
 
 pub fn get_cell_stuff_at(options: &Options, world: &World, wx: i32, wy: i32) -> (Tile, Pickup, u32, u32) {
+  let (wx, wy) = wrap_coord(options, wx, wy);
   // Return tile, pickup, value, visited.
 
   // wx/wy should be world coordinates
@@ -895,6 +870,7 @@ pub fn get_cell_stuff_at(options: &Options, world: &World, wx: i32, wy: i32) ->
 }
 
 pub fn get_cell_tile_at(options: &Options, world: &World, wx: i32, wy: i32) -> Tile {
+  let (wx, wy) = wrap_coord(options, wx, wy);
   // wx/wy should be world coordinates
 
   // Is the cell explicitly stored in the world right now? If not then use the procedure.
@@ -933,6 +909,7 @@ pub fn get_cell_tile_at(options: &Options, world: &World, wx: i32, wy: i32) -> T
   return world.tiles[ay as usize][ax as usize].tile;
 }
 pub fn get_cell_pickup_at(options: &Options, world: &World, wx: i32, wy: i32) -> Pickup {
+  let (wx, wy) = wrap_coord(options, wx, wy);
   // wx/wy should be world coordinates
 
   // Is the cell explicitly stored in the world right now? If not then use the procedure.
@@ -964,6 +941,7 @@ pub fn get_cell_pickup_at(options: &Options, world: &World, wx: i32, wy: i32) ->
 }
 
 pub fn get_cell_value_at(options: &Options, world: &World, wx: i32, wy: i32) -> u32 {
+  let (wx, wy) = wrap_coord(options, wx, wy);
   // wx/wy should be world coordinates
 
   // Is the cell explicitly stored in the world right now? If not then use the procedure.
@@ -992,15 +970,38 @@ pub fn get_cell_value_at(options: &Options, world: &World, wx: i32, wy: i32) ->
   return world.tiles[ay as usize][ax as usize].tile_value;
 }
 
-pub fn set_cell_tile_at(_options: &Options, world: &mut World, wx: i32, wy: i32, tile: Tile) {
+pub fn set_cell_tile_at(options: &Options, world: &mut World, wx: i32, wy: i32, tile: Tile) {
+  let (wx, wy) = wrap_coord(options, wx, wy);
+  if is_world_border(options, wx, wy) {
+    // The border of a bounded world can not be changed
+    return;
+  }
   let ax = world.min_x.abs() + wx;
   let ay = world.min_y.abs() + wy;
 
   assert_arr_xy_in_world(world, wx, wy, ax as usize, ay as usize);
 
+  let before = world.tiles[ay as usize][ax as usize].tile;
   world.tiles[ay as usize][ax as usize].tile = tile;
+  record_tile_change(world, before, tile);
+  if matches!(tile, Tile::Soil) {
+    world.soil_cells.insert((wx, wy));
+  }
+}
+pub fn record_tile_change(world: &mut World, before: Tile, after: Tile) {
+  // Call after changing a tile outside of set_cell_tile_at, so cached drone paths know to check
+  // themselves (see pathfinding.rs)
+  if std::mem::discriminant(&before) == std::mem::discriminant(&after) {
+    return;
+  }
+  world.tile_version += 1;
+  if !is_same_passability(before, after) {
+    world.passability_version += 1;
+  }
 }
-pub fn get_cell_tile_value_at(_options: &Options, world: &World, wx: i32, wy: i32) -> u32 {
+
+pub fn get_cell_tile_value_at(options: &Options, world: &World, wx: i32, wy: i32) -> u32 {
+  let (wx, wy) = wrap_coord(options, wx, wy);
   let ax = world.min_x.abs() + wx;
   let ay = world.min_y.abs() + wy;
 
@@ -1008,7 +1009,8 @@ pub fn get_cell_tile_value_at(_options: &Options, world: &World, wx: i32, wy: i3
 
   return world.tiles[ay as usize][ax as usize].tile_value;
 }
-pub fn set_cell_tile_value_at(_options: &Options, world: &mut World, wx: i32, wy: i32, value: u32) {
+pub fn set_cell_tile_value_at(options: &Options, world: &mut World, wx: i32, wy: i32, value: u32) {
+  let (wx, wy) = wrap_coord(options, wx, wy);
   let ax = world.min_x.abs() + wx;
   let ay = world.min_y.abs() + wy;
 
@@ -1016,7 +1018,8 @@ pub fn set_cell_tile_value_at(_options: &Options, world: &mut World, wx: i32, wy
 
   world.tiles[ay as usize][ax as usize].tile_value = value;
 }
-pub fn set_cell_pickup_at(_options: &Options, world: &mut World, wx: i32, wy: i32, pickup: Pickup) {
+pub fn set_cell_pickup_at(options: &Options, world: &mut World, wx: i32, wy: i32, pickup: Pickup) {
+  let (wx, wy) = wrap_coord(options, wx, wy);
   let ax = world.min_x.abs() + wx;
   let ay = world.min_y.abs() + wy;
 
@@ -1024,7 +1027,8 @@ pub fn set_cell_pickup_at(_options: &Options, world: &mut World, wx: i32, wy: i3
 
   world.tiles[ay as usize][ax as usize].pickup = pickup;
 }
-pub fn set_cell_pickup_value_at(_options: &Options, world: &mut World, wx: i32, wy: i32, value: u32) {
+pub fn set_cell_pickup_value_at(options: &Options, world: &mut World, wx: i32, wy: i32, value: u32) {
+  let (wx, wy) = wrap_coord(options, wx, wy);
   let ax = world.min_x.abs() + wx;
   let ay = world.min_y.abs() + wy;
 
@@ -1036,3 +1040,21 @@ pub fn set_cell_pickup_value_at(_options: &Options, world: &mut World, wx: i32,
 pub fn oob(x: i32, y: i32, minx: i32, miny: i32, maxx: i32, maxy: i32) -> bool {
   return x < minx || x > maxx || y < miny || y > maxy;
 }
+
+pub fn wrap_coord(options: &Options, x: i32, y: i32) -> (i32, i32) {
+  // On a torus, map any coordinate to the canonical -world_size..=world_size range. Every other
+  // topology returns the coordinate as is.
+  if options.topology != WorldTopology::Torus {
+    return (x, y);
+  }
+  let period = options.world_size * 2 + 1;
+  return (
+    (x + options.world_size).rem_euclid(period) - options.world_size,
+    (y + options.world_size).rem_euclid(period) - options.world_size,
+  );
+}
+
+pub fn is_world_border(options: &Options, x: i32, y: i32) -> bool {
+  // In a bounded world, everything beyond world_size is a permanent Impassible border
+  return options.topology == WorldTopology::Bounded && (x.abs() > options.world_size || y.abs() > options.world_size);
+}
@@ -0,0 +1,127 @@
+use super::cell::*;
+use super::expando::*;
+use super::fountain::*;
+use super::actor::*;
+use super::fluid::*;
+use super::options::*;
+use super::pickup::*;
+use super::tile::*;
+use super::world::*;
+
+// A world is fully determined by its generation config plus whatever changed since. Rather than
+// storing every cell, only store the cells that differ from what `generate_cell` would return.
+// Together with the expandos, fountains, pending fluid cells, and actors that reproduces the exact world.
+
+pub type SerializedWorldGen = (
+  u64, // seed
+  u8, // topology
+  i32, // world_size
+  Vec<(u8, f32)>, // actor_spawn_rates, as actor kind code and rate
+);
+
+pub type SerializedCell = (
+  i32, // x
+  i32, // y
+  u8, // tile
+  u32, // tile_value
+  u8, // pickup
+  u32, // pickup_value
+  u32, // visited
+  u32, // fluid
+);
+
+pub type SerializedWorld = (
+  SerializedWorldGen,
+  (i32, i32, i32, i32), // min_x, min_y, max_x, max_y
+  Vec<SerializedCell>, // Only the cells that differ from generate_cell
+  Vec<SerializedExpando>,
+  Vec<SerializedFountain>,
+  Vec<(i32, i32)>, // Pending fluid cells, in queue order
+  Vec<SerializedActor>,
+);
+
+pub fn world_gen_config(options: &Options) -> SerializedWorldGen {
+  // All the options that affect generate_cell and the actors it spawns
+  return (
+    options.seed,
+    topology_to_code(options.topology),
+    options.world_size,
+    options.actor_spawn_rates.iter().map(|&(kind, rate)| (actor_kind_to_code(kind), rate)).collect(),
+  );
+}
+
+fn cell_differs(a: &Cell, b: &Cell) -> bool {
+  return tile_to_code(a.tile) != tile_to_code(b.tile) ||
+    a.tile_value != b.tile_value ||
+    pickup_to_code(a.pickup) != pickup_to_code(b.pickup) ||
+    a.pickup_value != b.pickup_value ||
+    a.visited != b.visited ||
+    a.fluid != b.fluid;
+}
+
+pub fn world_serialize_diff(options: &Options, world: &World) -> SerializedWorld {
+  let mut cells: Vec<SerializedCell> = vec!();
+  for wy in world.min_y..=world.max_y {
+    for wx in world.min_x..=world.max_x {
+      let ax = (world.min_x.abs() + wx) as usize;
+      let ay = (world.min_y.abs() + wy) as usize;
+      let cell = &world.tiles[ay][ax];
+      if cell_differs(cell, &generate_cell(options, wx, wy)) {
+        cells.push((wx, wy, tile_to_code(cell.tile), cell.tile_value, pickup_to_code(cell.pickup), cell.pickup_value, cell.visited, cell.fluid));
+      }
+    }
+  }
+
+  return (
+    world_gen_config(options),
+    (world.min_x, world.min_y, world.max_x, world.max_y),
+    cells,
+    world.expandos.iter().map(|e| expando_serialize(e)).collect(),
+    world.fountains.iter().map(|f| fountain_serialize(f)).collect(),
+    world.fluids.cells.iter().map(|xy| xy.to_owned()).collect(),
+    world.actors.iter().map(|a| actor_serialize(a)).collect(),
+  );
+}
+
+pub fn world_deserialize_diff(options: &Options, serialized_world: &SerializedWorld) -> World {
+  let (gen, (min_x, min_y, max_x, max_y), cells, expandos, fountains, fluid_cells, actors) = serialized_world;
+
+  // The diff is only meaningful on top of the same procedural world
+  assert_eq!(*gen, world_gen_config(options), "World was serialized with a different world gen config (seed, topology, world_size, actor_spawn_rates); set those options to match before loading it");
+
+  let mut world = generate_world(options);
+  ensure_cell_in_world(&mut world, options, *min_x, *min_y);
+  ensure_cell_in_world(&mut world, options, *max_x, *max_y);
+  assert!(world.min_x == *min_x && world.min_y == *min_y && world.max_x == *max_x && world.max_y == *max_y, "The serialized world bounds should be reproduced exactly");
+
+  // generate_world hardcodes the origin so restore the procedural cell there. The diff will
+  // contain the origin if it differed when it was serialized.
+  let ox = world.min_x.abs() as usize;
+  let oy = world.min_y.abs() as usize;
+  world.tiles[oy][ox] = generate_cell(options, 0, 0);
+
+  for (wx, wy, tile, tile_value, pickup, pickup_value, visited, fluid) in cells.iter() {
+    let ax = (world.min_x.abs() + wx) as usize;
+    let ay = (world.min_y.abs() + wy) as usize;
+    if matches!(code_to_tile(*tile), Tile::Soil) {
+      world.soil_cells.insert((*wx, *wy));
+    }
+    world.tiles[ay][ax] = Cell {
+      tile: code_to_tile(*tile),
+      tile_value: *tile_value,
+      pickup: code_to_pickup(*pickup),
+      pickup_value: *pickup_value,
+      visited: *visited,
+      fluid: *fluid,
+    };
+  }
+
+  world.expandos = expandos.iter().map(|e| expando_deserialize(e)).collect();
+  world.fountains = fountains.iter().map(|f| fountain_deserialize(f)).collect();
+  world.actors = actors.iter().map(|a| actor_deserialize(a)).collect();
+  for (x, y) in fluid_cells.iter() {
+    enqueue_fluid_cell(&mut world, *x, *y);
+  }
+
+  return world;
+}